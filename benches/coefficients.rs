@@ -0,0 +1,90 @@
+//! Compares the old way of updating the reverb's parameters, calling `set_params` before every
+//! sample, with `Reverb::process_block()`, which only computes the comb gains when the decay
+//! changes and then ramps them over the block. Both run at 48 kHz, once with a fixed decay and
+//! once with the decay being automated.
+//!
+//! ```shell
+//! cargo bench --bench coefficients
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reverb::reverb::{Reverb, ReverbSettings, ReverbType};
+
+const SAMPLE_RATE: f32 = 48000.0;
+/// the plugin's block size
+const BLOCK_SIZE: usize = 64;
+/// one buffer of a typical host
+const BUFFER_SIZE: usize = 512;
+
+/// the decay at each sample of the `buffer`th buffer, either fixed or sweeping up by a
+/// millisecond per sample like a smoothed parameter would
+fn decays(automated: bool, buffer: usize) -> impl Fn(usize) -> f32 {
+    move |sample| {
+        if automated {
+            1000.0 + (buffer * BUFFER_SIZE + sample) as f32
+        } else {
+            1000.0
+        }
+    }
+}
+
+fn parameter_updates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parameter_updates");
+    group.throughput(Throughput::Elements(BUFFER_SIZE as u64));
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let input: Vec<f32> = (0..BUFFER_SIZE).map(|_| rng.gen_range(-0.5..0.5)).collect();
+
+    for reverb_type in [ReverbType::Comb, ReverbType::Schroeder, ReverbType::LpfComb, ReverbType::Moorer] {
+        let settings = ReverbSettings {
+            reverb_type,
+            decay: 1000.0,
+            damping: 0.3,
+            ..Default::default()
+        };
+
+        for (automation, automated) in [("fixed", false), ("automated", true)] {
+            let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+            let mut buffer = 0;
+            let id = BenchmarkId::new(format!("{:?}/{}", reverb_type, automation), "set_params_per_sample");
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    let decay = decays(automated, buffer);
+                    buffer = (buffer + 1) % 1000;
+                    for (i, x) in input.iter().enumerate() {
+                        reverb.set_params(decay(i), settings.damping, settings.comb_type, false);
+                        black_box(reverb.process(*x, *x));
+                    }
+                })
+            });
+
+            let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+            let mut buffer = 0;
+            let (mut left, mut right) = (input.clone(), input.clone());
+            let id = BenchmarkId::new(format!("{:?}/{}", reverb_type, automation), "process_block");
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    let decay = decays(automated, buffer);
+                    buffer = (buffer + 1) % 1000;
+                    left.copy_from_slice(&input);
+                    right.copy_from_slice(&input);
+                    for (i, (left, right)) in left.chunks_mut(BLOCK_SIZE).zip(right.chunks_mut(BLOCK_SIZE)).enumerate() {
+                        let params = ReverbSettings {
+                            decay: decay((i + 1) * BLOCK_SIZE - 1),
+                            ..settings
+                        }
+                        .block_params();
+                        reverb.process_block(left, right, &params);
+                    }
+                    black_box((&left, &right));
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parameter_updates);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+//! Feeds a loud noise burst into each reverb type and then measures how long blocks of silence
+//! take to process at different points in the tail. Without denormal handling the late blocks get
+//! several times slower once the feedback loops decay into subnormal numbers, with it the timings
+//! should stay flat.
+//!
+//! ```shell
+//! cargo bench --bench denormals
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reverb::denormal::ScopedFtz;
+use reverb::reverb::{Reverb, ReverbSettings, ReverbType};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const BLOCK_SIZE: usize = 512;
+const BURST_SECONDS: f32 = 0.5;
+/// with a one second decay the tail reaches the subnormal range (about -760 dB) after roughly 13
+/// seconds
+const DECAY_MS: f32 = 1000.0;
+const TAIL_AGES_SECONDS: [f32; 4] = [0.5, 5.0, 13.0, 20.0];
+
+fn process_silence(reverb: &mut Reverb, samples: usize) {
+    for _ in 0..samples {
+        black_box(reverb.process(black_box(0.0), black_box(0.0)));
+    }
+}
+
+fn silence_after_burst(c: &mut Criterion) {
+    let mut group = c.benchmark_group("silence_after_burst");
+    group.throughput(Throughput::Elements(BLOCK_SIZE as u64));
+
+    for reverb_type in [ReverbType::Comb, ReverbType::Schroeder, ReverbType::LpfComb, ReverbType::Moorer] {
+        for (ftz_name, use_ftz) in [("ftz", true), ("no_ftz", false)] {
+            let settings = ReverbSettings {
+                reverb_type,
+                decay: DECAY_MS,
+                damping: 0.3,
+                ..Default::default()
+            };
+            let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+            let _ftz = if use_ftz { Some(ScopedFtz::enable()) } else { None };
+
+            let mut rng = StdRng::seed_from_u64(0);
+            for _ in 0..(BURST_SECONDS * SAMPLE_RATE) as usize {
+                let x = rng.gen_range(-1.0..1.0);
+                reverb.process(x, x);
+            }
+
+            // the reverb keeps decaying between measurements, so every age starts where the
+            // previous one left off
+            let mut elapsed_seconds = 0.0;
+            for age in TAIL_AGES_SECONDS {
+                process_silence(&mut reverb, ((age - elapsed_seconds) * SAMPLE_RATE) as usize);
+                elapsed_seconds = age;
+
+                let id = BenchmarkId::new(format!("{:?}/{}", reverb_type, ftz_name), format!("{}s", age));
+                group.bench_function(id, |b| b.iter(|| process_silence(&mut reverb, BLOCK_SIZE)));
+            }
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, silence_after_burst);
+criterion_main!(benches);
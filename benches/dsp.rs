@@ -0,0 +1,279 @@
+//! Throughput of every DSP building block and of the full reverb, at several sample rates and
+//! block sizes. Criterion keeps track of regressions between runs, and once it's done a table
+//! with the nanoseconds per stereo sample and the realtime factor (seconds of audio processed per
+//! second of CPU time, so how many instances fit on one core) is printed. Both are computed from
+//! the estimates criterion saved for the benchmarks that just ran.
+//!
+//! ```shell
+//! cargo bench --bench dsp
+//! cargo bench --bench dsp -- reverb/moorer
+//! ```
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reverb::chorus::Chorus;
+use reverb::comb::CombType;
+use reverb::combbank::{CombBank, LANES, MAX_CHANNEL_COMBS};
+use reverb::delay::Delay;
+use reverb::delayingallpass::DelayingAllPass;
+use reverb::filter::{BiquadFilter, FilterType};
+use reverb::reverb::{Reverb, ReverbSettings, ReverbType};
+
+const SAMPLE_RATES: [f32; 3] = [44100.0, 48000.0, 96000.0];
+const BLOCK_SIZES: [usize; 2] = [64, 512];
+
+/// a benchmark that was registered with criterion, and how much audio one iteration processes
+struct Benchmark {
+    /// criterion's directory for the benchmark, `<group>/<function>/<parameter>`
+    directory: PathBuf,
+    name: String,
+    sample_rate: f32,
+    samples: usize,
+}
+
+thread_local! {
+    static BENCHMARKS: RefCell<Vec<Benchmark>> = const { RefCell::new(Vec::new()) };
+}
+
+/// the id for one sample rate and block size. it doesn't contain any characters criterion would
+/// replace in the directory name, so the estimates can be found again afterwards
+fn benchmark_id(group_name: &str, name: &str, sample_rate: f32, block_size: usize) -> BenchmarkId {
+    let parameter = format!("{}hz-{}", sample_rate, block_size);
+    BENCHMARKS.with(|benchmarks| {
+        benchmarks.borrow_mut().push(Benchmark {
+            directory: [group_name, name, &parameter].iter().collect(),
+            name: format!("{}/{}/{}", group_name, name, parameter),
+            sample_rate,
+            samples: block_size,
+        })
+    });
+
+    BenchmarkId::new(name, parameter)
+}
+
+fn noise(block_size: usize) -> Vec<f32> {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..block_size).map(|_| rng.gen_range(-0.5..0.5)).collect()
+}
+
+/// benchmarks the processors `make_processor` creates for each sample rate. a processor processes
+/// one block of stereo audio in place
+fn bench_processor(
+    c: &mut Criterion,
+    group_name: &str,
+    name: &str,
+    mut make_processor: impl FnMut(f32) -> Box<dyn FnMut(&mut [f32], &mut [f32])>,
+) {
+    let mut group = c.benchmark_group(group_name);
+
+    for sample_rate in SAMPLE_RATES {
+        for block_size in BLOCK_SIZES {
+            let input = noise(block_size);
+            let mut left = input.clone();
+            let mut right = input.clone();
+            let mut process = make_processor(sample_rate);
+
+            group.throughput(Throughput::Elements(block_size as u64));
+            group.bench_function(benchmark_id(group_name, name, sample_rate, block_size), |b| {
+                b.iter(|| {
+                    left.copy_from_slice(&input);
+                    right.copy_from_slice(&input);
+                    process(&mut left, &mut right);
+                    black_box((&left, &right));
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn building_blocks(c: &mut Criterion) {
+    bench_processor(c, "blocks", "delay", |sample_rate| {
+        let delay_samples = (0.01 * sample_rate) as usize;
+        let mut left = Delay::new(sample_rate as usize, delay_samples, 0.5);
+        let mut right = Delay::new(sample_rate as usize, delay_samples, 0.5);
+        Box::new(move |l, r| {
+            for (l, r) in l.iter_mut().zip(r.iter_mut()) {
+                *l = left.process_sample(*l, delay_samples);
+                *r = right.process_sample(*r, delay_samples);
+            }
+        })
+    });
+
+    bench_processor(c, "blocks", "allpass", |sample_rate| {
+        let mut allpass = DelayingAllPass::new(sample_rate, 5.0, 0.707);
+        Box::new(move |l, r| {
+            for (l, r) in l.iter_mut().zip(r.iter_mut()) {
+                *l = allpass.process_left(*l);
+                *r = allpass.process_right(*r);
+            }
+        })
+    });
+
+    bench_processor(c, "blocks", "chorus", |sample_rate| {
+        let mut chorus = Chorus::new(sample_rate, 25.0, 0.5, 10.0, 0.25, 1.0, 0.2, &mut StdRng::seed_from_u64(0));
+        // the depth only takes effect once `set_params` has been called
+        chorus.set_params(sample_rate, 25.0, 0.5, 10.0, 0.25, 1.0, 0.2);
+        Box::new(move |l, r| {
+            for (l, r) in l.iter_mut().zip(r.iter_mut()) {
+                *l = chorus.process_left(*l);
+                *r = chorus.process_right(*r);
+            }
+        })
+    });
+
+    bench_processor(c, "blocks", "biquad", |sample_rate| {
+        let mut biquad = BiquadFilter::new();
+        biquad.set_sample_rate(sample_rate);
+        biquad.coefficients(FilterType::LowPass2, 1000.0, 0.707, 0.0);
+        Box::new(move |l, r| {
+            for (l, r) in l.iter_mut().zip(r.iter_mut()) {
+                *l = biquad.process_left(*l);
+                *r = biquad.process_right(*r);
+            }
+        })
+    });
+}
+
+/// the delays of a full channel of combs
+const COMB_DELAYS_MS: [f32; MAX_CHANNEL_COMBS] = [29.7, 37.1, 41.1, 43.7];
+
+/// `CombBank::process_block()` with every comb of both channels, with and without SIMD
+fn combs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("combs");
+
+    for (name, use_simd) in [("simd", true), ("scalar", false)] {
+        for sample_rate in SAMPLE_RATES {
+            for block_size in BLOCK_SIZES {
+                let input: Vec<[f32; LANES]> = noise(block_size)
+                    .into_iter()
+                    .map(|x| {
+                        let mut frame = [x; LANES];
+                        frame[MAX_CHANNEL_COMBS..].fill(-x);
+                        frame
+                    })
+                    .collect();
+                let mut block = input.clone();
+
+                let mut bank = CombBank::new(sample_rate, &COMB_DELAYS_MS, &COMB_DELAYS_MS);
+                bank.set_use_simd(use_simd);
+                for comb in 0..bank.len() {
+                    bank.set_params(comb, 0.8, true, 0.1, CombType::Positive);
+                }
+
+                group.throughput(Throughput::Elements(block_size as u64));
+                group.bench_function(benchmark_id("combs", name, sample_rate, block_size), |b| {
+                    b.iter(|| {
+                        block.copy_from_slice(&input);
+                        bank.process_block(&mut block);
+                        black_box(&block);
+                    })
+                });
+            }
+        }
+    }
+
+    group.finish();
+}
+
+/// the plugin hands the reverb blocks of at most this many samples
+const PLUGIN_BLOCK_SIZE: usize = 64;
+
+/// `Reverb::process_block()` the way the plugin calls it. the `+automation` variants move the decay
+/// and damping every block, so the comb gains are ramping all the time
+fn reverbs(c: &mut Criterion) {
+    for reverb_type in [ReverbType::Comb, ReverbType::Schroeder, ReverbType::LpfComb, ReverbType::Moorer] {
+        for (enable_chorus, automate) in [(false, false), (true, false), (false, true)] {
+            let settings = ReverbSettings {
+                reverb_type,
+                decay: 2000.0,
+                damping: 0.3,
+                enable_chorus,
+                ..Default::default()
+            };
+            let name = format!(
+                "{:?}{}{}",
+                reverb_type,
+                if enable_chorus { "+chorus" } else { "" },
+                if automate { "+automation" } else { "" },
+            )
+            .to_lowercase();
+
+            bench_processor(c, "reverb", &name, |sample_rate| {
+                let mut reverb = Reverb::from_settings(sample_rate, &settings);
+                let mut params = settings.block_params();
+                Box::new(move |l, r| {
+                    for (l, r) in l.chunks_mut(PLUGIN_BLOCK_SIZE).zip(r.chunks_mut(PLUGIN_BLOCK_SIZE)) {
+                        if automate {
+                            params.decay = if params.decay == settings.decay { settings.decay + 100.0 } else { settings.decay };
+                            params.damping = if params.damping == settings.damping { settings.damping + 0.1 } else { settings.damping };
+                        }
+                        reverb.process_block(l, r, &params);
+                    }
+                })
+            });
+        }
+    }
+}
+
+/// where criterion saves its results, see `Criterion::default()`
+fn criterion_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("CRITERION_HOME") {
+        PathBuf::from(dir)
+    } else if let Some(dir) = std::env::var_os("CARGO_TARGET_DIR") {
+        PathBuf::from(dir).join("criterion")
+    } else {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("criterion")
+    }
+}
+
+/// criterion's estimate for the time one iteration takes in nanoseconds, if `benchmark` ran after
+/// `started`. benchmarks that were filtered out still have the estimates of an older run
+fn estimated_ns(benchmark: &Benchmark, started: SystemTime) -> Option<f64> {
+    let path = criterion_dir().join(&benchmark.directory).join("new").join("estimates.json");
+    let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+    if modified < started {
+        return None;
+    }
+
+    let estimates: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+    // the slope is what criterion reports as the time, it's missing for flat sampling
+    ["slope", "mean"]
+        .iter()
+        .find_map(|estimate| estimates[estimate]["point_estimate"].as_f64())
+}
+
+fn print_summary(started: SystemTime) {
+    BENCHMARKS.with(|benchmarks| {
+        let benchmarks = benchmarks.borrow();
+        let results: Vec<(&Benchmark, f64)> = benchmarks
+            .iter()
+            .filter_map(|benchmark| Some((benchmark, estimated_ns(benchmark, started)?)))
+            .collect();
+        if results.is_empty() {
+            return;
+        }
+
+        println!("\n{:<45} {:>12} {:>14}", "benchmark", "ns/sample", "realtime x");
+        for (benchmark, ns) in results {
+            let ns_per_sample = ns / benchmark.samples as f64;
+            let realtime_factor = 1e9 / (ns_per_sample * benchmark.sample_rate as f64);
+            println!("{:<45} {:>12.2} {:>14.1}", benchmark.name, ns_per_sample, realtime_factor);
+        }
+    });
+}
+
+fn main() {
+    let started = SystemTime::now();
+    let mut c = Criterion::default().configure_from_args();
+    building_blocks(&mut c);
+    combs(&mut c);
+    reverbs(&mut c);
+    c.final_summary();
+    print_summary(started);
+}
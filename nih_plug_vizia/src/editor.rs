@@ -62,6 +62,7 @@ impl Editor for ViziaEditor {
             // handle them automatically.
             widgets::ParamModel {
                 context: context.clone(),
+                history: Default::default(),
             }
             .build(cx);
 
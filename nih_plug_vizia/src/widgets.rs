@@ -16,16 +16,22 @@ use super::ViziaState;
 mod generic_ui;
 pub mod param_base;
 mod param_button;
+mod param_knob;
 mod param_slider;
+mod param_xy_pad;
 mod peak_meter;
 mod resize_handle;
+mod undo;
 pub mod util;
 
 pub use generic_ui::GenericUi;
 pub use param_button::{ParamButton, ParamButtonExt};
+pub use param_knob::{ParamKnob, ParamKnobExt};
 pub use param_slider::{ParamSlider, ParamSliderExt, ParamSliderStyle};
+pub use param_xy_pad::ParamXYPad;
 pub use peak_meter::PeakMeter;
 pub use resize_handle::ResizeHandle;
+pub use undo::UndoEvent;
 
 /// Register the default theme for the widgets exported by this module. This is automatically called
 /// for you when using [`create_vizia_editor()`][super::create_vizia_editor()].
@@ -110,6 +116,8 @@ pub enum GuiContextEvent {
 /// [`ViziaEditor::spawn()`][super::ViziaEditor::spawn()].
 pub(crate) struct ParamModel {
     pub context: Arc<dyn GuiContext>,
+    /// Every gesture that goes through this model can be undone with [`UndoEvent`]s.
+    pub history: undo::ParamHistory,
 }
 
 /// Handles interactions through `WindowEvent` for VIZIA GUIs by updating the `ViziaState`.
@@ -130,15 +138,31 @@ impl Model for ParamModel {
         // implementation below
         event.map(|param_event, _| match *param_event {
             RawParamEvent::BeginSetParameter(p) => unsafe {
+                self.history.begin_set_parameter(p);
                 self.context.raw_begin_set_parameter(p)
             },
             RawParamEvent::SetParameterNormalized(p, v) => unsafe {
+                self.history.set_parameter_normalized(p, v);
                 self.context.raw_set_parameter_normalized(p, v)
             },
-            RawParamEvent::EndSetParameter(p) => unsafe { self.context.raw_end_set_parameter(p) },
+            RawParamEvent::EndSetParameter(p) => unsafe {
+                self.context.raw_end_set_parameter(p);
+                self.history.end_set_parameter(p);
+            },
             // This can be used by widgets to be notified when parameter values have changed
             RawParamEvent::ParametersChanged => (),
         });
+
+        event.map(|undo_event, meta| {
+            match *undo_event {
+                UndoEvent::Undo => self.history.undo(self.context.as_ref()),
+                UndoEvent::Redo => self.history.redo(self.context.as_ref()),
+                UndoEvent::BeginGroup => self.history.begin_group(),
+                UndoEvent::EndGroup => self.history.end_group(),
+            }
+
+            meta.consume();
+        });
     }
 }
 
@@ -0,0 +1,654 @@
+//! A rotary knob that integrates with NIH-plug's [`Param`] types.
+
+use std::f32::consts::PI;
+
+use nih_plug::prelude::Param;
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::param_base::ParamWidgetBase;
+use super::util::ModifiersExt;
+
+/// Dragging the mouse by this many logical pixels vertically covers the knob's entire range.
+const DRAG_RANGE_PIXELS: f32 = 200.0;
+/// When shift+dragging a parameter, the drag gets this much more precise.
+const GRANULAR_DRAG_MULTIPLIER: f32 = 0.1;
+
+/// The angle the knob's arc starts at, in radians. Zero points to the top, positive values point
+/// clockwise.
+const START_ANGLE: f32 = -0.75 * PI;
+/// The angle the knob's arc ends at.
+const END_ANGLE: f32 = 0.75 * PI;
+/// The number of line segments used to draw an arc spanning the entire range.
+const ARC_SEGMENTS: usize = 64;
+
+/// The thickness of the value arc in logical pixels.
+const ARC_WIDTH: f32 = 4.0;
+/// The thickness of the modulation ring in logical pixels. This is drawn just outside of the value
+/// arc.
+const MODULATION_RING_WIDTH: f32 = 2.0;
+
+/// A rotary knob that integrates with NIH-plug's [`Param`] types. The knob is changed by dragging
+/// vertically, and the parameter's current value is shown beneath it. Use the
+/// [`bipolar()`][ParamKnobExt::bipolar()] method for parameters where the center of the range
+/// is the neutral position.
+#[derive(Lens)]
+pub struct ParamKnob {
+    param_base: ParamWidgetBase,
+
+    /// Will be set to `true` when the knob gets Alt+Click'ed which will replace the value label
+    /// with a text box.
+    text_input_active: bool,
+    /// We keep track of the start coordinate and normalized value while dragging. Knobs are
+    /// always dragged relative to where the drag started so grabbing a knob doesn't make it jump.
+    /// This is a `None` value when the knob isn't being dragged.
+    drag_status: Option<KnobDragStatus>,
+
+    // These fields are set through modifiers:
+    /// Whether or not to listen to scroll events for changing the parameter's value in steps.
+    use_scroll_wheel: bool,
+    /// The number of (fractional) scrolled lines that have not yet been turned into parameter
+    /// change events. This is needed to support trackpads with smooth scrolling.
+    scrolled_lines: f32,
+    /// Whether the arc should be drawn from the center of the range instead of from the start.
+    bipolar: bool,
+    /// A specific label to use instead of displaying the parameter's value.
+    label_override: Option<String>,
+}
+
+enum ParamKnobEvent {
+    /// Text input has been cancelled without submitting a new value.
+    CancelTextInput,
+    /// A new value has been sent by the text input dialog after pressing Enter.
+    TextInput(String),
+}
+
+// TODO: Vizia's lens derive macro requires this to be marked as pub
+#[derive(Debug, Clone, Copy)]
+pub struct KnobDragStatus {
+    /// The mouse's Y-coordinate when the drag was started, or when Shift was pressed or released
+    /// during the drag.
+    pub starting_y_coordinate: f32,
+    /// The normalized value at that point.
+    pub starting_value: f32,
+    /// Whether Shift was held down at that point.
+    pub granular: bool,
+}
+
+impl KnobDragStatus {
+    /// The normalized value for the mouse's current Y-coordinate. Dragging upwards by
+    /// [`DRAG_RANGE_PIXELS`] logical pixels covers the entire range, or a tenth of it when the drag
+    /// is granular. The mouse coordinates are in physical pixels, so this needs to be compensated
+    /// for the DPI scale to remain consistent.
+    fn value_at(&self, y_coordinate: f32, dpi_factor: f32) -> f32 {
+        let mut delta =
+            (self.starting_y_coordinate - y_coordinate) / (DRAG_RANGE_PIXELS * dpi_factor);
+        if self.granular {
+            delta *= GRANULAR_DRAG_MULTIPLIER;
+        }
+
+        (self.starting_value + delta).clamp(0.0, 1.0)
+    }
+}
+
+/// What a left click on the knob does, depending on the held modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KnobClick {
+    /// Alt+Click brings up a text entry dialog.
+    TextInput,
+    /// Ctrl+Click (Cmd+Click on macOS) resets the parameter to its default.
+    Reset,
+    /// A regular click starts a drag.
+    Drag,
+}
+
+impl KnobClick {
+    fn with_modifiers(alt: bool, command: bool) -> Self {
+        if alt {
+            KnobClick::TextInput
+        } else if command {
+            KnobClick::Reset
+        } else {
+            KnobClick::Drag
+        }
+    }
+}
+
+/// The normalized range the value arc is drawn over. Bipolar knobs are filled from the center of
+/// the range. This is `None` when the value is too close to where the arc starts to draw anything.
+fn value_arc_range(value: f32, bipolar: bool) -> Option<(f32, f32)> {
+    let fill_start = if bipolar { 0.5 } else { 0.0 };
+    if (value - fill_start).abs() >= 1e-3 {
+        Some((fill_start, value))
+    } else {
+        None
+    }
+}
+
+/// The knob's graphics, drawn manually.
+struct KnobArc<U, M, O>
+where
+    U: Lens<Target = f32>,
+    M: Lens<Target = f32>,
+    O: Lens<Target = f32>,
+{
+    unmodulated_normalized_value: U,
+    modulated_normalized_value: M,
+    /// Modulation from the plugin itself, added on top of the host's modulation.
+    modulation_offset: O,
+    bipolar: bool,
+}
+
+impl ParamKnob {
+    /// Creates a new [`ParamKnob`] for the given parameter. To accommodate VIZIA's mapping system,
+    /// you'll need to provide a lens containing your `Params` implementation object (check out how
+    /// the `Data` struct is used in `gain_gui_vizia`) and a projection function that maps the
+    /// `Params` object to the parameter you want to display a widget for. Parameter changes are
+    /// handled by emitting [`ParamEvent`][super::ParamEvent]s which are automatically handled by
+    /// the VIZIA wrapper.
+    ///
+    /// See [`ParamKnobExt`] for additional options.
+    pub fn new<L, Params, P, FMap>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        let no_modulation = params.clone().map(|_| 0.0);
+        Self::with_modulation(cx, params, params_to_param, no_modulation)
+    }
+
+    /// The same as [`new()`][Self::new()], but the knob also shows modulation that happens inside
+    /// of the plugin, for instance from its own LFOs. `modulation_offset` is a normalized offset
+    /// that's added to the parameter's value after the host's modulation. It's drawn the same way
+    /// as the host's modulation.
+    pub fn with_modulation<L, Params, P, FMap, O>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+        modulation_offset: O,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+        O: Lens<Target = f32> + Clone,
+    {
+        Self {
+            param_base: ParamWidgetBase::new(cx, params.clone(), params_to_param),
+
+            text_input_active: false,
+            drag_status: None,
+
+            use_scroll_wheel: true,
+            scrolled_lines: 0.0,
+            bipolar: false,
+            label_override: None,
+        }
+        .build(
+            cx,
+            ParamWidgetBase::build_view(params, params_to_param, move |cx, param_data| {
+                Binding::new(cx, ParamKnob::bipolar, move |cx, bipolar| {
+                    let bipolar = bipolar.get(cx);
+
+                    // Can't use `.to_string()` here as that would include the modulation.
+                    let unmodulated_normalized_value_lens =
+                        param_data.make_lens(|param| param.unmodulated_normalized_value());
+                    // If the parameter is being modulated by the host (this only works for CLAP
+                    // plugins with hosts that support this), then this is drawn as a ring around
+                    // the knob.
+                    let modulated_normalized_value_lens =
+                        param_data.make_lens(|param| param.modulated_normalized_value());
+                    let display_value_lens = param_data.make_lens(|param| {
+                        param.normalized_value_to_string(param.unmodulated_normalized_value(), true)
+                    });
+
+                    KnobArc {
+                        unmodulated_normalized_value: unmodulated_normalized_value_lens,
+                        modulated_normalized_value: modulated_normalized_value_lens,
+                        modulation_offset: modulation_offset.clone(),
+                        bipolar,
+                    }
+                    .build(cx, |_| {})
+                    .class("knob")
+                    .hoverable(false);
+
+                    // Only draw the text input widget when it gets focussed. Creating the textbox
+                    // based on `ParamKnob::text_input_active` lets us focus the textbox when it
+                    // gets created.
+                    Binding::new(
+                        cx,
+                        ParamKnob::text_input_active,
+                        move |cx, text_input_active| {
+                            if text_input_active.get(cx) {
+                                Self::text_input_view(cx, display_value_lens.clone());
+                            } else {
+                                Self::value_label_view(
+                                    cx,
+                                    display_value_lens.clone(),
+                                    ParamKnob::label_override,
+                                );
+                            }
+                        },
+                    );
+                });
+            }),
+        )
+    }
+
+    /// Create a text input that's shown in place of the value label.
+    fn text_input_view(cx: &mut Context, display_value_lens: impl Lens<Target = String>) {
+        Textbox::new(cx, display_value_lens)
+            .class("value-entry")
+            .on_submit(|cx, string, success| {
+                if success {
+                    cx.emit(ParamKnobEvent::TextInput(string))
+                } else {
+                    cx.emit(ParamKnobEvent::CancelTextInput);
+                }
+            })
+            .on_build(|cx| {
+                cx.emit(TextEvent::StartEdit);
+                cx.emit(TextEvent::SelectAll);
+            })
+            .class("align_center")
+            .child_top(Stretch(1.0))
+            .child_bottom(Stretch(1.0))
+            .width(Stretch(1.0));
+    }
+
+    /// Create the label showing the parameter's current value beneath the knob.
+    fn value_label_view(
+        cx: &mut Context,
+        display_value_lens: impl Lens<Target = String>,
+        label_override_lens: impl Lens<Target = Option<String>>,
+    ) {
+        Binding::new(cx, label_override_lens, move |cx, label_override_lens| {
+            // If the label override is set then we'll use that. If not, the parameter's current
+            // display value (before modulation) is used.
+            match label_override_lens.get(cx) {
+                Some(label_override) => Label::new(cx, &label_override),
+                None => Label::new(cx, display_value_lens.clone()),
+            }
+            .class("value")
+            .child_space(Stretch(1.0))
+            .width(Stretch(1.0))
+            .hoverable(false);
+        });
+    }
+
+    /// Reset the parameter to its default value as a single gesture.
+    fn reset(&self, cx: &mut EventContext) {
+        self.param_base.begin_set_parameter(cx);
+        self.param_base
+            .set_normalized_value(cx, self.param_base.default_normalized_value());
+        self.param_base.end_set_parameter(cx);
+    }
+
+    /// The status for a drag starting at `y_coordinate` from the parameter's current value.
+    fn drag_status_at(&self, cx: &EventContext, y_coordinate: f32) -> KnobDragStatus {
+        KnobDragStatus {
+            starting_y_coordinate: y_coordinate,
+            starting_value: self.param_base.unmodulated_normalized_value(),
+            granular: cx.modifiers.shift(),
+        }
+    }
+}
+
+impl View for ParamKnob {
+    fn element(&self) -> Option<&'static str> {
+        Some("param-knob")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|param_knob_event, meta| match param_knob_event {
+            ParamKnobEvent::CancelTextInput => {
+                self.text_input_active = false;
+                cx.set_active(false);
+
+                meta.consume();
+            }
+            ParamKnobEvent::TextInput(string) => {
+                if let Some(normalized_value) = self.param_base.string_to_normalized_value(string) {
+                    self.param_base.begin_set_parameter(cx);
+                    self.param_base.set_normalized_value(cx, normalized_value);
+                    self.param_base.end_set_parameter(cx);
+                }
+
+                self.text_input_active = false;
+
+                meta.consume();
+            }
+        });
+
+        event.map(|window_event, meta| match window_event {
+            // See `ParamSlider` for why triple clicks are treated as regular clicks
+            WindowEvent::MouseDown(MouseButton::Left)
+            | WindowEvent::MouseTripleClick(MouseButton::Left) => {
+                match KnobClick::with_modifiers(cx.modifiers.alt(), cx.modifiers.command()) {
+                    KnobClick::TextInput => {
+                        self.text_input_active = true;
+                        cx.set_active(true);
+                    }
+                    // Ctrl+Click, double click, and right clicks should reset the parameter instead
+                    // of initiating a drag operation
+                    KnobClick::Reset => self.reset(cx),
+                    KnobClick::Drag => {
+                        cx.capture();
+                        // NOTE: Otherwise we don't get key up events
+                        cx.focus();
+                        cx.set_active(true);
+
+                        // Unlike with sliders, clicking on a knob never changes its value. Only
+                        // dragging does.
+                        self.param_base.begin_set_parameter(cx);
+                        self.drag_status = Some(self.drag_status_at(cx, cx.mouse.cursory));
+                    }
+                }
+
+                meta.consume();
+            }
+            WindowEvent::MouseDoubleClick(MouseButton::Left)
+            | WindowEvent::MouseDown(MouseButton::Right)
+            | WindowEvent::MouseDoubleClick(MouseButton::Right)
+            | WindowEvent::MouseTripleClick(MouseButton::Right) => {
+                self.reset(cx);
+
+                meta.consume();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.drag_status.is_some() {
+                    self.drag_status = None;
+                    cx.release();
+                    cx.set_active(false);
+
+                    self.param_base.end_set_parameter(cx);
+
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(_x, y) => {
+                if let Some(drag_status) = self.drag_status {
+                    // Pressing or releasing Shift continues the drag from the current value with
+                    // the new precision, so the knob doesn't jump
+                    let drag_status = if drag_status.granular != cx.modifiers.shift() {
+                        let drag_status = self.drag_status_at(cx, *y);
+                        self.drag_status = Some(drag_status);
+                        drag_status
+                    } else {
+                        drag_status
+                    };
+
+                    self.param_base.set_normalized_value(
+                        cx,
+                        drag_status.value_at(*y, cx.style.dpi_factor as f32),
+                    );
+                }
+            }
+            WindowEvent::MouseScroll(_scroll_x, scroll_y) if self.use_scroll_wheel => {
+                // With a regular scroll wheel `scroll_y` will only ever be -1 or 1, but with smooth
+                // scrolling trackpads being a thing `scroll_y` could be anything.
+                self.scrolled_lines += scroll_y;
+
+                if self.scrolled_lines.abs() >= 1.0 {
+                    let use_finer_steps = cx.modifiers.shift();
+
+                    // Scrolling while dragging needs to be taken into account here
+                    let dragging = self.drag_status.is_some();
+                    if !dragging {
+                        self.param_base.begin_set_parameter(cx);
+                    }
+
+                    let mut current_value = self.param_base.unmodulated_normalized_value();
+
+                    while self.scrolled_lines >= 1.0 {
+                        current_value = self
+                            .param_base
+                            .next_normalized_step(current_value, use_finer_steps);
+                        self.param_base.set_normalized_value(cx, current_value);
+                        self.scrolled_lines -= 1.0;
+                    }
+
+                    while self.scrolled_lines <= -1.0 {
+                        current_value = self
+                            .param_base
+                            .previous_normalized_step(current_value, use_finer_steps);
+                        self.param_base.set_normalized_value(cx, current_value);
+                        self.scrolled_lines += 1.0;
+                    }
+
+                    if dragging {
+                        // The drag continues from the scrolled value
+                        self.drag_status = Some(self.drag_status_at(cx, cx.mouse.cursory));
+                    } else {
+                        self.param_base.end_set_parameter(cx);
+                    }
+                }
+
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
+
+impl<U, M, O> KnobArc<U, M, O>
+where
+    U: Lens<Target = f32>,
+    M: Lens<Target = f32>,
+    O: Lens<Target = f32>,
+{
+    /// Add an arc between two normalized values to `path`, using line segments.
+    fn arc(path: &mut vg::Path, center: (f32, f32), radius: f32, from: f32, to: f32) {
+        let (from, to) = (from.min(to), from.max(to));
+        let angle = |t: f32| START_ANGLE + (END_ANGLE - START_ANGLE) * t;
+        let point = |t: f32| {
+            let angle = angle(t);
+            (
+                center.0 + radius * angle.sin(),
+                center.1 - radius * angle.cos(),
+            )
+        };
+
+        let segments = ((ARC_SEGMENTS as f32 * (to - from)).ceil() as usize).max(1);
+        let (x, y) = point(from);
+        path.move_to(x, y);
+        for segment in 1..=segments {
+            let (x, y) = point(from + (to - from) * (segment as f32 / segments as f32));
+            path.line_to(x, y);
+        }
+    }
+}
+
+impl<U, M, O> View for KnobArc<U, M, O>
+where
+    U: Lens<Target = f32>,
+    M: Lens<Target = f32>,
+    O: Lens<Target = f32>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("knob-arc")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let unmodulated_value = self.unmodulated_normalized_value.get(cx);
+        let modulated_value = (self.modulated_normalized_value.get(cx)
+            + self.modulation_offset.get(cx))
+        .clamp(0.0, 1.0);
+
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        // The track uses the element's background color and the value arc uses its font color,
+        // so both can be styled
+        let opacity = cx.opacity();
+        let mut track_color: vg::Color = cx.background_color().cloned().unwrap_or_default().into();
+        track_color.set_alphaf(track_color.a * opacity);
+        let mut value_color: vg::Color = cx.font_color().cloned().unwrap_or_default().into();
+        value_color.set_alphaf(value_color.a * opacity);
+        let modulation_color = vg::Color::rgbaf(0.64, 0.92, 0.99, 0.8 * opacity);
+
+        let arc_width = cx.logical_to_physical(ARC_WIDTH);
+        let modulation_ring_width = cx.logical_to_physical(MODULATION_RING_WIDTH);
+        let center = (bounds.x + bounds.w / 2.0, bounds.y + bounds.h / 2.0);
+        let radius = bounds.w.min(bounds.h) / 2.0 - modulation_ring_width * 2.0 - arc_width / 2.0;
+        if radius <= 0.0 {
+            return;
+        }
+
+        let mut paint = vg::Paint::color(track_color);
+        paint.set_line_width(arc_width);
+        paint.set_line_cap(vg::LineCap::Round);
+        let mut track = vg::Path::new();
+        Self::arc(&mut track, center, radius, 0.0, 1.0);
+        canvas.stroke_path(&mut track, &paint);
+
+        if let Some((from, to)) = value_arc_range(unmodulated_value, self.bipolar) {
+            let mut paint = vg::Paint::color(value_color);
+            paint.set_line_width(arc_width);
+            paint.set_line_cap(vg::LineCap::Round);
+            let mut value_arc = vg::Path::new();
+            Self::arc(&mut value_arc, center, radius, from, to);
+            canvas.stroke_path(&mut value_arc, &paint);
+        }
+
+        // The pointer from the center to the current value
+        let mut paint = vg::Paint::color(value_color);
+        paint.set_line_width(arc_width / 2.0);
+        paint.set_line_cap(vg::LineCap::Round);
+        let mut pointer = vg::Path::new();
+        let angle = START_ANGLE + (END_ANGLE - START_ANGLE) * unmodulated_value;
+        pointer.move_to(
+            center.0 + radius * 0.3 * angle.sin(),
+            center.1 - radius * 0.3 * angle.cos(),
+        );
+        pointer.line_to(
+            center.0 + radius * 0.8 * angle.sin(),
+            center.1 - radius * 0.8 * angle.cos(),
+        );
+        canvas.stroke_path(&mut pointer, &paint);
+
+        // If the parameter is being modulated, the modulation is drawn as a ring around the value
+        // arc spanning from the unmodulated value to the modulated value
+        if (modulated_value - unmodulated_value).abs() >= 1e-3 {
+            let mut paint = vg::Paint::color(modulation_color);
+            paint.set_line_width(modulation_ring_width);
+            let mut modulation_ring = vg::Path::new();
+            Self::arc(
+                &mut modulation_ring,
+                center,
+                radius + arc_width / 2.0 + modulation_ring_width,
+                unmodulated_value,
+                modulated_value,
+            );
+            canvas.stroke_path(&mut modulation_ring, &paint);
+        }
+    }
+}
+
+/// Extension methods for [`ParamKnob`] handles.
+pub trait ParamKnobExt {
+    /// Don't respond to scroll wheel events. Useful when this knob is used as part of a scrolling
+    /// view.
+    fn disable_scroll_wheel(self) -> Self;
+
+    /// Fill the knob's arc from the center of the range instead of from the start. Useful for
+    /// parameters like panning, where the center is the neutral position.
+    fn bipolar(self, bipolar: bool) -> Self;
+
+    /// Manually set a fixed label for the knob instead of displaying the current value. This is
+    /// currently not reactive.
+    fn with_label(self, value: impl Into<String>) -> Self;
+}
+
+impl ParamKnobExt for Handle<'_, ParamKnob> {
+    fn disable_scroll_wheel(self) -> Self {
+        self.modify(|param_knob: &mut ParamKnob| param_knob.use_scroll_wheel = false)
+    }
+
+    fn bipolar(self, bipolar: bool) -> Self {
+        self.modify(|param_knob: &mut ParamKnob| param_knob.bipolar = bipolar)
+    }
+
+    fn with_label(self, value: impl Into<String>) -> Self {
+        self.modify(|param_knob: &mut ParamKnob| param_knob.label_override = Some(value.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drag(starting_value: f32, granular: bool) -> KnobDragStatus {
+        KnobDragStatus {
+            starting_y_coordinate: 500.0,
+            starting_value,
+            granular,
+        }
+    }
+
+    #[test]
+    fn dragging_up_increases_the_value() {
+        let status = drag(0.5, false);
+        assert_eq!(status.value_at(500.0, 1.0), 0.5);
+        assert_eq!(status.value_at(450.0, 1.0), 0.75);
+        assert_eq!(status.value_at(550.0, 1.0), 0.25);
+    }
+
+    #[test]
+    fn drag_range_covers_the_whole_knob() {
+        let status = drag(0.0, false);
+        assert_eq!(status.value_at(500.0 - DRAG_RANGE_PIXELS, 1.0), 1.0);
+
+        // Beyond the range the value is clamped
+        assert_eq!(status.value_at(0.0, 1.0), 1.0);
+        assert_eq!(drag(1.0, false).value_at(1000.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn drag_range_follows_the_dpi_scale() {
+        // At 2x scaling the same logical distance is twice as many physical pixels
+        let status = drag(0.0, false);
+        assert_eq!(status.value_at(500.0 - DRAG_RANGE_PIXELS, 2.0), 0.5);
+        assert_eq!(status.value_at(500.0 - DRAG_RANGE_PIXELS * 2.0, 2.0), 1.0);
+    }
+
+    #[test]
+    fn shift_drag_is_finer() {
+        let status = drag(0.5, true);
+        let value = status.value_at(500.0 - DRAG_RANGE_PIXELS, 1.0);
+        assert!((value - 0.6).abs() < 1e-6, "{value}");
+
+        let value = status.value_at(500.0 + DRAG_RANGE_PIXELS, 1.0);
+        assert!((value - 0.4).abs() < 1e-6, "{value}");
+    }
+
+    #[test]
+    fn click_modifiers() {
+        assert_eq!(KnobClick::with_modifiers(false, false), KnobClick::Drag);
+        assert_eq!(KnobClick::with_modifiers(false, true), KnobClick::Reset);
+        assert_eq!(KnobClick::with_modifiers(true, false), KnobClick::TextInput);
+        // Alt takes precedence over Ctrl
+        assert_eq!(KnobClick::with_modifiers(true, true), KnobClick::TextInput);
+    }
+
+    #[test]
+    fn bipolar_arcs_start_at_the_center() {
+        assert_eq!(value_arc_range(0.8, false), Some((0.0, 0.8)));
+        assert_eq!(value_arc_range(0.8, true), Some((0.5, 0.8)));
+        assert_eq!(value_arc_range(0.2, true), Some((0.5, 0.2)));
+
+        // Nothing is drawn at the arc's starting point
+        assert_eq!(value_arc_range(0.0, false), None);
+        assert_eq!(value_arc_range(0.5, true), None);
+        assert_eq!(value_arc_range(0.5, false), Some((0.0, 0.5)));
+    }
+}
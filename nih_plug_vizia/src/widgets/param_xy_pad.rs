@@ -0,0 +1,512 @@
+//! An XY pad that controls two of NIH-plug's [`Param`] types at the same time.
+
+use nih_plug::prelude::Param;
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::param_base::ParamWidgetBase;
+use super::util::ModifiersExt;
+
+/// When shift+dragging the pad, one pixel dragged corresponds to this much change in the
+/// normalized parameters.
+const GRANULAR_DRAG_MULTIPLIER: f32 = 0.1;
+
+/// The radius of the handle in logical pixels.
+const HANDLE_RADIUS: f32 = 6.0;
+/// The number of grid lines drawn across each axis.
+const GRID_DIVISIONS: usize = 4;
+
+/// An XY pad that binds one parameter to its horizontal axis and another to its vertical axis.
+/// Dragging the handle changes both parameters at once, with a separate automation gesture for
+/// each of them so hosts can record the movement as two automation lanes. The parameters' current
+/// values are shown in the pad's bottom left corner.
+#[derive(Lens)]
+pub struct ParamXYPad {
+    x_param_base: ParamWidgetBase,
+    y_param_base: ParamWidgetBase,
+
+    /// Will be set to `true` if we're dragging the handle. Resetting the parameters should not
+    /// initiate a drag.
+    drag_active: bool,
+    /// We keep track of the start coordinates and normalized values when holding down Shift while
+    /// dragging for higher precision dragging. This is a `None` value when granular dragging is not
+    /// active.
+    granular_drag_status: Option<XYGranularDragStatus>,
+}
+
+// TODO: Vizia's lens derive macro requires this to be marked as pub
+#[derive(Debug, Clone, Copy)]
+pub struct XYGranularDragStatus {
+    /// The mouse's coordinates when the granular drag was started.
+    pub starting_coordinates: (f32, f32),
+    /// The normalized values for the X and Y parameters when the granular drag was started.
+    pub starting_values: (f32, f32),
+}
+
+impl XYGranularDragStatus {
+    /// The normalized values for the mouse's current coordinates. The handle moves a tenth as far
+    /// as the mouse, starting from where it was when the granular drag was started.
+    fn values_at(&self, area: PadArea, (x, y): (f32, f32), dpi_factor: f32) -> (f32, f32) {
+        // These positions should be compensated for the DPI scale so it remains consistent
+        let (start_x, start_y) = area.position_of(self.starting_values);
+        let (starting_x_coordinate, starting_y_coordinate) = self.starting_coordinates;
+        let delta_x = ((x - starting_x_coordinate) * GRANULAR_DRAG_MULTIPLIER) * dpi_factor;
+        let delta_y = ((y - starting_y_coordinate) * GRANULAR_DRAG_MULTIPLIER) * dpi_factor;
+
+        area.values_at((start_x + delta_x, start_y + delta_y))
+    }
+}
+
+/// The area within the pad's border, in physical pixels. This maps between positions in the pad
+/// and normalized values, with the Y-axis pointing up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PadArea {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl PadArea {
+    /// The area of the current entity, ignoring the border width like the functions in
+    /// [`util`][super::util].
+    fn current(cx: &EventContext) -> Self {
+        let border_width = match cx.style.border_width.get(cx.current()) {
+            Some(Units::Pixels(x)) => *x,
+            _ => 0.0,
+        };
+
+        PadArea {
+            x: cx.cache.get_posx(cx.current()) + border_width,
+            y: cx.cache.get_posy(cx.current()) + border_width,
+            width: cx.cache.get_width(cx.current()) - (border_width * 2.0),
+            height: cx.cache.get_height(cx.current()) - (border_width * 2.0),
+        }
+    }
+
+    /// The normalized values corresponding to a position within the pad. Positions outside of the
+    /// pad are clamped to its edges.
+    fn values_at(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            ((x - self.x) / self.width).clamp(0.0, 1.0),
+            1.0 - ((y - self.y) / self.height).clamp(0.0, 1.0),
+        )
+    }
+
+    /// The position corresponding to a pair of normalized values.
+    fn position_of(&self, (x_value, y_value): (f32, f32)) -> (f32, f32) {
+        (
+            self.x + self.width * x_value.clamp(0.0, 1.0),
+            self.y + self.height * (1.0 - y_value.clamp(0.0, 1.0)),
+        )
+    }
+}
+
+/// What a left click on the pad does, depending on the held modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PadClick {
+    /// Ctrl+Click (Cmd+Click on macOS) resets both parameters to their defaults.
+    Reset,
+    /// Moves the handle to the mouse and starts dragging it.
+    Drag,
+    /// Shift+Click starts a granular drag without moving the handle.
+    GranularDrag,
+}
+
+impl PadClick {
+    fn with_modifiers(command: bool, shift: bool) -> Self {
+        if command {
+            PadClick::Reset
+        } else if shift {
+            PadClick::GranularDrag
+        } else {
+            PadClick::Drag
+        }
+    }
+}
+
+/// The pad's graphics, drawn manually. The values are normalized, with the Y-axis pointing up.
+struct XYPadGraphics<U, M>
+where
+    U: Lens<Target = (f32, f32)>,
+    M: Lens<Target = (f32, f32)>,
+{
+    unmodulated_normalized_values: U,
+    modulated_normalized_values: M,
+}
+
+impl ParamXYPad {
+    /// Creates a new [`ParamXYPad`] for the given parameters. This works the same way as
+    /// [`ParamSlider::new()`][super::ParamSlider::new()], except that there's a projection function
+    /// for both the parameter on the X-axis and the parameter on the Y-axis.
+    pub fn new<L, Params, Px, Py, FMapX, FMapY>(
+        cx: &mut Context,
+        params: L,
+        params_to_x_param: FMapX,
+        params_to_y_param: FMapY,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        Px: Param + 'static,
+        Py: Param + 'static,
+        FMapX: Fn(&Params) -> &Px + Copy + 'static,
+        FMapY: Fn(&Params) -> &Py + Copy + 'static,
+    {
+        Self {
+            x_param_base: ParamWidgetBase::new(cx, params.clone(), params_to_x_param),
+            y_param_base: ParamWidgetBase::new(cx, params.clone(), params_to_y_param),
+
+            drag_active: false,
+            granular_drag_status: None,
+        }
+        .build(cx, move |cx| {
+            // Can't use `.to_string()` here as that would include the modulation.
+            let unmodulated_normalized_values_lens = params.clone().map(move |params| {
+                (
+                    params_to_x_param(params).unmodulated_normalized_value(),
+                    params_to_y_param(params).unmodulated_normalized_value(),
+                )
+            });
+            // If either parameter is being modulated by the host (this only works for CLAP plugins
+            // with hosts that support this), then the modulated position is drawn as well.
+            let modulated_normalized_values_lens = params.clone().map(move |params| {
+                (
+                    params_to_x_param(params).modulated_normalized_value(),
+                    params_to_y_param(params).modulated_normalized_value(),
+                )
+            });
+            let display_values_lens = params.map(move |params| {
+                let x_param = params_to_x_param(params);
+                let y_param = params_to_y_param(params);
+
+                format!(
+                    "{}: {}\n{}: {}",
+                    x_param.name(),
+                    x_param
+                        .normalized_value_to_string(x_param.unmodulated_normalized_value(), true),
+                    y_param.name(),
+                    y_param
+                        .normalized_value_to_string(y_param.unmodulated_normalized_value(), true),
+                )
+            });
+
+            ZStack::new(cx, move |cx| {
+                XYPadGraphics {
+                    unmodulated_normalized_values: unmodulated_normalized_values_lens,
+                    modulated_normalized_values: modulated_normalized_values_lens,
+                }
+                .build(cx, |_| {})
+                .class("pad")
+                .width(Stretch(1.0))
+                .height(Stretch(1.0))
+                .hoverable(false);
+
+                Label::new(cx, display_values_lens)
+                    .class("value")
+                    .top(Stretch(1.0))
+                    .hoverable(false);
+            })
+            .hoverable(false);
+        })
+    }
+
+    /// Begin automation gestures for both parameters.
+    fn begin_set_parameters(&self, cx: &mut EventContext) {
+        self.x_param_base.begin_set_parameter(cx);
+        self.y_param_base.begin_set_parameter(cx);
+    }
+
+    /// Set both parameters to new normalized values. This needs to be wrapped in
+    /// [`begin_set_parameters()`][Self::begin_set_parameters()] and
+    /// [`end_set_parameters()`][Self::end_set_parameters()].
+    fn set_normalized_values(&self, cx: &mut EventContext, (x_value, y_value): (f32, f32)) {
+        self.x_param_base.set_normalized_value(cx, x_value);
+        self.y_param_base.set_normalized_value(cx, y_value);
+    }
+
+    /// End the automation gestures for both parameters.
+    fn end_set_parameters(&self, cx: &mut EventContext) {
+        self.x_param_base.end_set_parameter(cx);
+        self.y_param_base.end_set_parameter(cx);
+    }
+
+    /// Reset both parameters to their default values. When this happens in the middle of a granular
+    /// drag, the drag continues from the default values.
+    fn reset(&mut self, cx: &mut EventContext) {
+        self.granular_drag_status = None;
+
+        self.begin_set_parameters(cx);
+        self.set_normalized_values(
+            cx,
+            (
+                self.x_param_base.default_normalized_value(),
+                self.y_param_base.default_normalized_value(),
+            ),
+        );
+        self.end_set_parameters(cx);
+    }
+
+    fn unmodulated_normalized_values(&self) -> (f32, f32) {
+        (
+            self.x_param_base.unmodulated_normalized_value(),
+            self.y_param_base.unmodulated_normalized_value(),
+        )
+    }
+}
+
+impl View for ParamXYPad {
+    fn element(&self) -> Option<&'static str> {
+        Some("param-xy-pad")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            // See `ParamSlider` for why triple clicks are treated as regular clicks
+            WindowEvent::MouseDown(MouseButton::Left)
+            | WindowEvent::MouseTripleClick(MouseButton::Left) => {
+                let click = PadClick::with_modifiers(cx.modifiers.command(), cx.modifiers.shift());
+                if click == PadClick::Reset {
+                    // Ctrl+Click, double click, and right clicks should reset the parameters
+                    // instead of initiating a drag operation
+                    self.reset(cx);
+                } else {
+                    self.drag_active = true;
+                    cx.capture();
+                    // NOTE: Otherwise we don't get key up events
+                    cx.focus();
+                    cx.set_active(true);
+
+                    // When holding down shift while clicking on the pad we want to granuarly edit
+                    // the parameters without jumping to a new position
+                    self.begin_set_parameters(cx);
+                    if click == PadClick::GranularDrag {
+                        self.granular_drag_status = Some(XYGranularDragStatus {
+                            starting_coordinates: (cx.mouse.cursorx, cx.mouse.cursory),
+                            starting_values: self.unmodulated_normalized_values(),
+                        });
+                    } else {
+                        self.granular_drag_status = None;
+                        self.set_normalized_values(
+                            cx,
+                            PadArea::current(cx).values_at((cx.mouse.cursorx, cx.mouse.cursory)),
+                        );
+                    }
+                }
+
+                meta.consume();
+            }
+            WindowEvent::MouseDoubleClick(MouseButton::Left)
+            | WindowEvent::MouseDown(MouseButton::Right)
+            | WindowEvent::MouseDoubleClick(MouseButton::Right)
+            | WindowEvent::MouseTripleClick(MouseButton::Right) => {
+                // Ctrl+Click, double click, and right clicks should reset the parameters instead of
+                // initiating a drag operation
+                self.reset(cx);
+
+                meta.consume();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.drag_active {
+                    self.drag_active = false;
+                    cx.release();
+                    cx.set_active(false);
+
+                    self.end_set_parameters(cx);
+
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                if self.drag_active {
+                    // If shift is being held then the drag should be more granular instead of
+                    // absolute
+                    if cx.modifiers.shift() {
+                        let starting_values = self.unmodulated_normalized_values();
+                        let granular_drag_status =
+                            *self.granular_drag_status.get_or_insert_with(|| {
+                                XYGranularDragStatus {
+                                    starting_coordinates: (*x, *y),
+                                    starting_values,
+                                }
+                            });
+
+                        self.set_normalized_values(
+                            cx,
+                            granular_drag_status.values_at(
+                                PadArea::current(cx),
+                                (*x, *y),
+                                cx.style.dpi_factor as f32,
+                            ),
+                        );
+                    } else {
+                        self.granular_drag_status = None;
+
+                        self.set_normalized_values(cx, PadArea::current(cx).values_at((*x, *y)));
+                    }
+                }
+            }
+            WindowEvent::KeyUp(_, Some(Key::Shift)) => {
+                // If this happens while dragging, snap back to the current screen position
+                if self.drag_active && self.granular_drag_status.is_some() {
+                    self.granular_drag_status = None;
+                    self.set_normalized_values(
+                        cx,
+                        PadArea::current(cx).values_at((cx.mouse.cursorx, cx.mouse.cursory)),
+                    );
+                }
+            }
+            _ => {}
+        });
+    }
+}
+
+impl<U, M> View for XYPadGraphics<U, M>
+where
+    U: Lens<Target = (f32, f32)>,
+    M: Lens<Target = (f32, f32)>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("xy-pad-graphics")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let (x_value, y_value) = self.unmodulated_normalized_values.get(cx);
+        let (modulated_x_value, modulated_y_value) = self.modulated_normalized_values.get(cx);
+
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        // The grid uses the element's background color and the handle uses its font color, so
+        // both can be styled
+        let opacity = cx.opacity();
+        let mut grid_color: vg::Color = cx.background_color().cloned().unwrap_or_default().into();
+        grid_color.set_alphaf(grid_color.a * opacity);
+        let mut handle_color: vg::Color = cx.font_color().cloned().unwrap_or_default().into();
+        handle_color.set_alphaf(handle_color.a * opacity);
+        let modulation_color = vg::Color::rgbaf(0.64, 0.92, 0.99, 0.8 * opacity);
+
+        let handle_radius = cx.logical_to_physical(HANDLE_RADIUS);
+        let line_width = cx.logical_to_physical(1.0);
+        let position = |x_value: f32, y_value: f32| {
+            (
+                bounds.x + x_value.clamp(0.0, 1.0) * bounds.w,
+                bounds.y + (1.0 - y_value.clamp(0.0, 1.0)) * bounds.h,
+            )
+        };
+
+        let mut grid = vg::Path::new();
+        for division in 1..GRID_DIVISIONS {
+            let t = division as f32 / GRID_DIVISIONS as f32;
+            grid.move_to(bounds.x + t * bounds.w, bounds.top());
+            grid.line_to(bounds.x + t * bounds.w, bounds.bottom());
+            grid.move_to(bounds.left(), bounds.y + t * bounds.h);
+            grid.line_to(bounds.right(), bounds.y + t * bounds.h);
+        }
+        let mut paint = vg::Paint::color(grid_color);
+        paint.set_line_width(line_width);
+        canvas.stroke_path(&mut grid, &paint);
+
+        // A crosshair through the handle makes it easier to read off both values
+        let (handle_x, handle_y) = position(x_value, y_value);
+        let mut crosshair = vg::Path::new();
+        crosshair.move_to(handle_x, bounds.top());
+        crosshair.line_to(handle_x, bounds.bottom());
+        crosshair.move_to(bounds.left(), handle_y);
+        crosshair.line_to(bounds.right(), handle_y);
+        let mut paint = vg::Paint::color(handle_color);
+        paint.set_line_width(line_width);
+        canvas.stroke_path(&mut crosshair, &paint);
+
+        // If the parameters are being modulated, then the modulated position is drawn as a line
+        // from the handle to a hollow circle
+        if (modulated_x_value - x_value).abs() >= 1e-3
+            || (modulated_y_value - y_value).abs() >= 1e-3
+        {
+            let (modulated_x, modulated_y) = position(modulated_x_value, modulated_y_value);
+            let mut modulation = vg::Path::new();
+            modulation.move_to(handle_x, handle_y);
+            modulation.line_to(modulated_x, modulated_y);
+            modulation.circle(modulated_x, modulated_y, handle_radius * 0.75);
+            let mut paint = vg::Paint::color(modulation_color);
+            paint.set_line_width(line_width * 2.0);
+            canvas.stroke_path(&mut modulation, &paint);
+        }
+
+        let mut handle = vg::Path::new();
+        handle.circle(handle_x, handle_y, handle_radius);
+        canvas.fill_path(&mut handle, &vg::Paint::color(handle_color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 200x100 pixel pad at (10, 20).
+    const AREA: PadArea = PadArea {
+        x: 10.0,
+        y: 20.0,
+        width: 200.0,
+        height: 100.0,
+    };
+
+    fn assert_values_eq(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-5 && (actual.1 - expected.1).abs() < 1e-5,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn y_axis_points_up() {
+        assert_values_eq(AREA.values_at((10.0, 120.0)), (0.0, 0.0));
+        assert_values_eq(AREA.values_at((210.0, 20.0)), (1.0, 1.0));
+        assert_values_eq(AREA.values_at((60.0, 45.0)), (0.25, 0.75));
+        assert_values_eq(AREA.position_of((0.25, 0.75)), (60.0, 45.0));
+
+        // Dragging past the edges clamps the values
+        assert_values_eq(AREA.values_at((-100.0, 500.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn shift_drag_starts_without_jumping() {
+        // The mouse doesn't need to be over the handle when Shift is pressed
+        let status = XYGranularDragStatus {
+            starting_coordinates: (150.0, 30.0),
+            starting_values: (0.25, 0.75),
+        };
+        assert_values_eq(status.values_at(AREA, (150.0, 30.0), 1.0), (0.25, 0.75));
+    }
+
+    #[test]
+    fn shift_drag_is_finer() {
+        let status = XYGranularDragStatus {
+            starting_coordinates: (110.0, 70.0),
+            starting_values: (0.5, 0.5),
+        };
+
+        // 100 pixels to the right and 50 pixels up moves the handle by 10 and 5 pixels
+        assert_values_eq(status.values_at(AREA, (210.0, 20.0), 1.0), (0.55, 0.55));
+        assert_values_eq(AREA.values_at((210.0, 20.0)), (1.0, 1.0));
+
+        // The movement is scaled with the DPI factor, like the granular slider drag
+        assert_values_eq(status.values_at(AREA, (210.0, 20.0), 2.0), (0.6, 0.6));
+
+        // And the values still stay within the pad
+        assert_values_eq(status.values_at(AREA, (5000.0, -5000.0), 1.0), (1.0, 1.0));
+    }
+
+    #[test]
+    fn click_modifiers() {
+        assert_eq!(PadClick::with_modifiers(false, false), PadClick::Drag);
+        assert_eq!(
+            PadClick::with_modifiers(false, true),
+            PadClick::GranularDrag
+        );
+        assert_eq!(PadClick::with_modifiers(true, false), PadClick::Reset);
+        assert_eq!(PadClick::with_modifiers(true, true), PadClick::Reset);
+    }
+}
@@ -0,0 +1,409 @@
+//! Undo and redo for parameter changes made through the editor.
+
+use nih_plug::prelude::{GuiContext, ParamPtr};
+use std::collections::VecDeque;
+
+/// The maximum number of steps that can be undone. Older steps are forgotten.
+const MAX_UNDO_STEPS: usize = 100;
+
+/// Events for undoing and redoing parameter changes. These are handled by `nih_plug_vizia` the
+/// same way [`RawParamEvent`][super::RawParamEvent]s are.
+///
+/// Every automation gesture, e.g. dragging a slider from mouse down to mouse up, is a single undo
+/// step. Gestures that overlap, like the two gestures from a [`ParamXYPad`][super::ParamXYPad],
+/// are combined into one step. Use [`BeginGroup`][Self::BeginGroup] and
+/// [`EndGroup`][Self::EndGroup] to combine gestures that happen one after the other, for instance
+/// when loading a preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoEvent {
+    /// Revert the last undo step.
+    Undo,
+    /// Reapply the last undone step.
+    Redo,
+    /// Combine all gestures until the matching `EndGroup` into a single undo step. Groups can be
+    /// nested.
+    BeginGroup,
+    /// End a group started with `BeginGroup`.
+    EndGroup,
+}
+
+impl UndoEvent {
+    /// The event for pressing Z with these modifiers, where `command` is Ctrl, or Cmd on macOS.
+    /// Ctrl+Z undoes and Ctrl+Shift+Z redoes. Z without Ctrl is left to the focused widget.
+    pub fn for_z_key(command: bool, shift: bool) -> Option<Self> {
+        match (command, shift) {
+            (false, _) => None,
+            (true, false) => Some(UndoEvent::Undo),
+            (true, true) => Some(UndoEvent::Redo),
+        }
+    }
+}
+
+/// A single parameter's change during an undo step.
+#[derive(Debug, Clone, Copy)]
+struct Change {
+    param: ParamPtr,
+    /// The normalized value before the step.
+    before: f32,
+    /// The last normalized value set during the step.
+    after: f32,
+}
+
+/// The undo and redo stacks, fed with the parameter events handled by the
+/// [`ParamModel`][super::ParamModel].
+#[derive(Default)]
+pub(crate) struct ParamHistory {
+    undo_stack: VecDeque<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+
+    /// The changes for the step that's currently being recorded.
+    current_step: Vec<Change>,
+    /// The number of gestures that have been started but not yet ended.
+    active_gestures: usize,
+    /// The nesting depth of `UndoEvent::BeginGroup`.
+    group_depth: usize,
+}
+
+impl ParamHistory {
+    /// Should be called for every `RawParamEvent::BeginSetParameter`, before the parameter is
+    /// changed.
+    pub fn begin_set_parameter(&mut self, param: ParamPtr) {
+        self.active_gestures += 1;
+
+        if !self.current_step.iter().any(|change| change.param == param) {
+            let value = unsafe { param.unmodulated_normalized_value() };
+            self.current_step.push(Change {
+                param,
+                before: value,
+                after: value,
+            });
+        }
+    }
+
+    /// Should be called for every `RawParamEvent::SetParameterNormalized`. The parameter's value
+    /// may not have been updated yet by the time the gesture ends, so the last value that got set
+    /// is stored instead.
+    pub fn set_parameter_normalized(&mut self, param: ParamPtr, normalized: f32) {
+        if let Some(change) = self
+            .current_step
+            .iter_mut()
+            .find(|change| change.param == param)
+        {
+            change.after = normalized;
+        }
+    }
+
+    /// Should be called for every `RawParamEvent::EndSetParameter`.
+    pub fn end_set_parameter(&mut self, _param: ParamPtr) {
+        self.active_gestures = self.active_gestures.saturating_sub(1);
+        self.finish_step();
+    }
+
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        self.finish_step();
+    }
+
+    /// Sets the parameters back to the values they had before the last step.
+    pub fn undo(&mut self, context: &dyn GuiContext) {
+        if let Some(step) = self.undo_stack.pop_back() {
+            for change in step.iter().rev() {
+                Self::set(context, change.param, change.before);
+            }
+            self.redo_stack.push(step);
+        }
+    }
+
+    /// Reapplies the last step that was undone.
+    pub fn redo(&mut self, context: &dyn GuiContext) {
+        if let Some(step) = self.redo_stack.pop() {
+            for change in step.iter() {
+                Self::set(context, change.param, change.after);
+            }
+            self.undo_stack.push_back(step);
+        }
+    }
+
+    /// Moves the current step to the undo stack once all of its gestures and groups have ended.
+    /// Steps that didn't change anything are dropped.
+    fn finish_step(&mut self) {
+        if self.active_gestures > 0 || self.group_depth > 0 {
+            return;
+        }
+
+        let mut step = std::mem::take(&mut self.current_step);
+        step.retain(|change| change.before != change.after);
+        if step.is_empty() {
+            return;
+        }
+
+        if self.undo_stack.len() == MAX_UNDO_STEPS {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(step);
+        self.redo_stack.clear();
+    }
+
+    /// Changes a parameter as a gesture of its own. This goes straight to the `GuiContext` so it
+    /// doesn't get recorded.
+    fn set(context: &dyn GuiContext, param: ParamPtr, normalized: f32) {
+        unsafe {
+            context.raw_begin_set_parameter(param);
+            context.raw_set_parameter_normalized(param, normalized);
+            context.raw_end_set_parameter(param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nih_plug::prelude::{FloatParam, FloatRange, Param, PluginApi};
+    use nih_plug::wrapper::state::PluginState;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    /// Records the values the history sets instead of changing the parameters. Outside of
+    /// `nih_plug` a parameter's value can only be changed through a wrapper, so the parameters
+    /// keep their defaults and the recorded values are what a host would have been asked for.
+    #[derive(Default)]
+    struct RecordingContext {
+        set: Mutex<Vec<(ParamPtr, f32)>>,
+    }
+
+    impl RecordingContext {
+        fn take(&self) -> Vec<(ParamPtr, f32)> {
+            std::mem::take(&mut *self.set.lock().unwrap())
+        }
+    }
+
+    impl GuiContext for RecordingContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Clap
+        }
+
+        fn request_resize(&self) -> bool {
+            false
+        }
+
+        unsafe fn raw_begin_set_parameter(&self, _param: ParamPtr) {}
+
+        unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+            self.set.lock().unwrap().push((param, normalized));
+        }
+
+        unsafe fn raw_end_set_parameter(&self, _param: ParamPtr) {}
+
+        fn get_state(&self) -> PluginState {
+            // The undo history never reads the state
+            PluginState {
+                version: String::new(),
+                params: BTreeMap::new(),
+                fields: BTreeMap::new(),
+            }
+        }
+
+        fn set_state(&self, _state: PluginState) {}
+    }
+
+    fn param(name: &str, default: f32) -> FloatParam {
+        FloatParam::new(name, default, FloatRange::Linear { min: 0.0, max: 1.0 })
+    }
+
+    /// A complete gesture, like clicking on a slider and releasing it again.
+    fn gesture(history: &mut ParamHistory, param: &FloatParam, normalized: f32) {
+        history.begin_set_parameter(param.as_ptr());
+        history.set_parameter_normalized(param.as_ptr(), normalized);
+        history.end_set_parameter(param.as_ptr());
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let context = RecordingContext::default();
+        let gain = param("Gain", 0.5);
+        let mut history = ParamHistory::default();
+
+        // Only the last value of a gesture matters
+        history.begin_set_parameter(gain.as_ptr());
+        history.set_parameter_normalized(gain.as_ptr(), 0.6);
+        history.set_parameter_normalized(gain.as_ptr(), 0.7);
+        history.end_set_parameter(gain.as_ptr());
+
+        history.undo(&context);
+        assert_eq!(context.take(), [(gain.as_ptr(), 0.5)]);
+        history.redo(&context);
+        assert_eq!(context.take(), [(gain.as_ptr(), 0.7)]);
+
+        // There's nothing left to redo
+        history.redo(&context);
+        assert!(context.take().is_empty());
+    }
+
+    #[test]
+    fn overlapping_gestures_are_one_step() {
+        let context = RecordingContext::default();
+        let x = param("X", 0.5);
+        let y = param("Y", 0.2);
+        let mut history = ParamHistory::default();
+
+        // Like an XY pad, which starts a gesture for both parameters
+        history.begin_set_parameter(x.as_ptr());
+        history.begin_set_parameter(y.as_ptr());
+        history.set_parameter_normalized(x.as_ptr(), 0.9);
+        history.set_parameter_normalized(y.as_ptr(), 0.4);
+        history.end_set_parameter(x.as_ptr());
+        history.set_parameter_normalized(y.as_ptr(), 0.3);
+        history.end_set_parameter(y.as_ptr());
+
+        history.undo(&context);
+        assert_eq!(context.take(), [(y.as_ptr(), 0.2), (x.as_ptr(), 0.5)]);
+        history.undo(&context);
+        assert!(context.take().is_empty());
+
+        history.redo(&context);
+        assert_eq!(context.take(), [(x.as_ptr(), 0.9), (y.as_ptr(), 0.3)]);
+    }
+
+    #[test]
+    fn nested_groups_are_one_step() {
+        let context = RecordingContext::default();
+        let a = param("A", 0.1);
+        let b = param("B", 0.2);
+        let c = param("C", 0.3);
+        let mut history = ParamHistory::default();
+
+        history.begin_group();
+        gesture(&mut history, &a, 0.6);
+        history.begin_group();
+        gesture(&mut history, &b, 0.7);
+        history.end_group();
+
+        // The outer group is still open, so nothing can be undone yet
+        history.undo(&context);
+        assert!(context.take().is_empty());
+
+        gesture(&mut history, &c, 0.8);
+        history.end_group();
+
+        history.undo(&context);
+        assert_eq!(
+            context.take(),
+            [(c.as_ptr(), 0.3), (b.as_ptr(), 0.2), (a.as_ptr(), 0.1)]
+        );
+        history.undo(&context);
+        assert!(context.take().is_empty());
+    }
+
+    #[test]
+    fn old_steps_are_forgotten() {
+        let context = RecordingContext::default();
+        let gain = param("Gain", 0.0);
+        let mut history = ParamHistory::default();
+
+        for step in 1..=MAX_UNDO_STEPS + 10 {
+            gesture(&mut history, &gain, step as f32 / 1000.0);
+        }
+
+        for _ in 0..MAX_UNDO_STEPS + 10 {
+            history.undo(&context);
+        }
+        assert_eq!(context.take().len(), MAX_UNDO_STEPS);
+
+        // The oldest step that's left is the first one that wasn't forgotten
+        for _ in 0..MAX_UNDO_STEPS + 10 {
+            history.redo(&context);
+        }
+        let redone = context.take();
+        assert_eq!(redone.len(), MAX_UNDO_STEPS);
+        assert_eq!(redone[0], (gain.as_ptr(), 11.0 / 1000.0));
+    }
+
+    #[test]
+    fn steps_without_changes_are_dropped() {
+        let context = RecordingContext::default();
+        let a = param("A", 0.5);
+        let b = param("B", 0.5);
+        let mut history = ParamHistory::default();
+
+        // A gesture that ends where it started, one that never sets a value, and an empty group
+        gesture(&mut history, &a, 0.5);
+        history.begin_set_parameter(a.as_ptr());
+        history.end_set_parameter(a.as_ptr());
+        history.begin_group();
+        history.end_group();
+        history.undo(&context);
+        assert!(context.take().is_empty());
+
+        // Parameters that didn't change are left out of a step that changed others
+        history.begin_group();
+        gesture(&mut history, &a, 0.5);
+        gesture(&mut history, &b, 0.9);
+        history.end_group();
+        history.undo(&context);
+        assert_eq!(context.take(), [(b.as_ptr(), 0.5)]);
+    }
+
+    #[test]
+    fn new_changes_clear_the_redo_stack() {
+        let context = RecordingContext::default();
+        let a = param("A", 0.5);
+        let b = param("B", 0.5);
+        let mut history = ParamHistory::default();
+
+        gesture(&mut history, &a, 0.9);
+        history.undo(&context);
+        context.take();
+
+        // A no-op doesn't count as a new change
+        gesture(&mut history, &b, 0.5);
+        history.redo(&context);
+        assert_eq!(context.take(), [(a.as_ptr(), 0.9)]);
+
+        history.undo(&context);
+        context.take();
+        gesture(&mut history, &b, 0.1);
+        history.redo(&context);
+        assert!(context.take().is_empty());
+    }
+
+    #[test]
+    fn loading_a_preset_is_one_step() {
+        let context = RecordingContext::default();
+        let params = [param("A", 0.1), param("B", 0.2), param("C", 0.3)];
+        let mut history = ParamHistory::default();
+
+        // This is what an editor sends when it loads a preset, one gesture per parameter
+        // wrapped in a group. B already has the preset's value.
+        history.begin_group();
+        for (param, value) in params.iter().zip([0.6, 0.2, 0.8]) {
+            gesture(&mut history, param, value);
+        }
+        history.end_group();
+
+        history.undo(&context);
+        assert_eq!(
+            context.take(),
+            [(params[2].as_ptr(), 0.3), (params[0].as_ptr(), 0.1)]
+        );
+        history.undo(&context);
+        assert!(context.take().is_empty());
+
+        history.redo(&context);
+        assert_eq!(
+            context.take(),
+            [(params[0].as_ptr(), 0.6), (params[2].as_ptr(), 0.8)]
+        );
+    }
+
+    #[test]
+    fn z_key_shortcuts() {
+        assert_eq!(UndoEvent::for_z_key(true, false), Some(UndoEvent::Undo));
+        assert_eq!(UndoEvent::for_z_key(true, true), Some(UndoEvent::Redo));
+        assert_eq!(UndoEvent::for_z_key(false, false), None);
+        assert_eq!(UndoEvent::for_z_key(false, true), None);
+    }
+}
@@ -0,0 +1,87 @@
+//! Room acoustics measurements on rendered impulse responses.
+
+/// decay times in seconds. a value is `None` when the energy decay curve never gets low enough
+/// to measure it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DecayTimes {
+    /// early decay time, the 0 to -10 dB slope extrapolated to 60 dB
+    pub edt: Option<f32>,
+    /// the -5 to -25 dB slope extrapolated to 60 dB
+    pub t20: Option<f32>,
+    /// the -5 to -35 dB slope extrapolated to 60 dB
+    pub t30: Option<f32>,
+}
+
+impl DecayTimes {
+    /// the best available RT60 estimate, T30 if the curve is long enough for it, then T20, then EDT
+    pub fn rt60(&self) -> Option<f32> {
+        self.t30.or(self.t20).or(self.edt)
+    }
+}
+
+/// Schroeder backwards integration of the impulse response energy, in dB relative to the total
+/// energy. the energy of all channels is summed. the result starts at 0 dB and falls towards
+/// -inf at the end of the impulse response
+pub fn energy_decay_curve(channels: &[&[f32]]) -> Vec<f32> {
+    let len = channels.iter().map(|channel| channel.len()).max().unwrap_or(0);
+
+    let mut curve = vec![0.0f32; len];
+    // the running sum gets very large compared to the last samples, f64 keeps the tail accurate
+    let mut energy = 0.0f64;
+    for i in (0..len).rev() {
+        for channel in channels {
+            if let Some(x) = channel.get(i) {
+                energy += (*x as f64) * (*x as f64);
+            }
+        }
+        curve[i] = energy as f32;
+    }
+
+    let total_energy = energy;
+    for value in curve.iter_mut() {
+        *value = if total_energy > 0.0 && *value > 0.0 {
+            10.0 * (*value as f64 / total_energy).log10() as f32
+        } else {
+            f32::NEG_INFINITY
+        };
+    }
+    curve
+}
+
+/// measures EDT, T20 and T30 on an energy decay curve from `energy_decay_curve()`
+pub fn decay_times(curve: &[f32], sample_rate: f32) -> DecayTimes {
+    DecayTimes {
+        edt: decay_time(curve, sample_rate, 0.0, -10.0),
+        t20: decay_time(curve, sample_rate, -5.0, -25.0),
+        t30: decay_time(curve, sample_rate, -5.0, -35.0),
+    }
+}
+
+/// fits a line through the part of the curve between `start_db` and `end_db` and returns the time
+/// it would take that line to fall by 60 dB
+fn decay_time(curve: &[f32], sample_rate: f32, start_db: f32, end_db: f32) -> Option<f32> {
+    let start = curve.iter().position(|db| *db <= start_db)?;
+    let end = start + curve[start..].iter().position(|db| *db <= end_db)?;
+    if end <= start {
+        return None;
+    }
+
+    // least squares fit of dB over seconds
+    let n = (end - start + 1) as f64;
+    let (mut sum_t, mut sum_db, mut sum_tt, mut sum_tdb) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+    for (i, db) in curve[start..=end].iter().enumerate() {
+        let t = (start + i) as f64 / sample_rate as f64;
+        let db = *db as f64;
+        sum_t += t;
+        sum_db += db;
+        sum_tt += t * t;
+        sum_tdb += t * db;
+    }
+    let slope = (n * sum_tdb - sum_t * sum_db) / (n * sum_tt - sum_t * sum_t);
+
+    if slope < 0.0 {
+        Some((-60.0 / slope) as f32)
+    } else {
+        None
+    }
+}
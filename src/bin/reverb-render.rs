@@ -0,0 +1,311 @@
+//! Offline renderer, runs WAV files through `reverb::Reverb` without a host.
+//!
+//! ```shell
+//! cargo run --release --bin reverb-render -- in.wav out.wav --type moorer --decay 2500 --damping 0.4
+//! cargo run --release --bin reverb-render -- in.wav out.wav --preset hall.json --wet 1 --dry 0
+//! cargo run --release --bin reverb-render -- --impulse out.wav --preset hall.json --sample-rate 48000
+//! ```
+
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use nih_plug::prelude::Enum;
+use serde::{Deserialize, Serialize};
+
+use reverb::comb::CombType;
+use reverb::denormal::ScopedFtz;
+use reverb::ir;
+use reverb::predelay;
+use reverb::reverb::{Reverb, ReverbSettings, ReverbType};
+
+const USAGE: &str = "\
+usage: reverb-render <input.wav> <output.wav> [options]
+       reverb-render --impulse <output.wav> [options]
+
+options:
+  --preset <file.json>    load settings from a preset file, other options override it
+  --type <type>           comb, schroeder, lpfcomb or moorer
+  --decay <ms>            decay time in milliseconds, 100 to 20000
+  --damping <0..1>        damping amount
+  --comb-type <type>      positive or negative
+  --chorus                enable the chorus
+  --chorus-depth <ms>     how far the chorus moves its delays, 0 to 20, defaults to 0
+  --pre-delay <ms>        delay before the reverb, 0 to 200, defaults to 0
+  --seed <n>              seed for the random delay offsets, defaults to 0
+  --wet <0..1>            wet level, defaults to 0.25
+  --dry <0..1>            dry level, defaults to 1.0
+  --tail <seconds>        length of the tail rendered after the input, by default this renders
+                          until the tail falls below -90 dBFS
+  --sample-rate <hz>      sample rate for --impulse, defaults to 48000
+  --impulse               render the wet impulse response instead of processing a file";
+
+/// the plugin's parameter ranges. settings outside of them are rejected, the plugin would never
+/// render them
+const DECAY_RANGE: RangeInclusive<f32> = 100.0..=20000.0;
+const LEVEL_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+const CHORUS_DEPTH_RANGE: RangeInclusive<f32> = 0.0..=20.0;
+const PRE_DELAY_RANGE: RangeInclusive<f32> = 0.0..=predelay::MAX_PRE_DELAY_MS;
+
+/// the preset file format. every field is optional, missing fields use the plugin's defaults
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct RenderSettings {
+    #[serde(flatten)]
+    reverb: ReverbSettings,
+    wet: f32,
+    dry: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            reverb: ReverbSettings::default(),
+            wet: 0.25,
+            dry: 1.0,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// checks the values that came from the command line or the preset file
+    fn validate(&self) -> Result<(), String> {
+        for (name, value, range) in [
+            ("decay", self.reverb.decay, DECAY_RANGE),
+            ("damping", self.reverb.damping, LEVEL_RANGE),
+            ("chorus depth", self.reverb.chorus_depth, CHORUS_DEPTH_RANGE),
+            ("pre-delay", self.reverb.pre_delay, PRE_DELAY_RANGE),
+            ("wet", self.wet, LEVEL_RANGE),
+            ("dry", self.dry, LEVEL_RANGE),
+        ] {
+            if !range.contains(&value) {
+                return Err(format!("{} has to be between {} and {}, not {}", name, range.start(), range.end(), value));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Args {
+    input: Option<PathBuf>,
+    output: PathBuf,
+    settings: RenderSettings,
+    tail_seconds: Option<f32>,
+    sample_rate: f32,
+}
+
+fn main() -> ExitCode {
+    if std::env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match &args.input {
+        Some(input) => render_file(input, &args),
+        None => render_impulse(&args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = args;
+    let mut positional = Vec::new();
+    let mut impulse = false;
+    let mut preset = None;
+    let mut overrides: Vec<(String, String)> = Vec::new();
+    let mut chorus = false;
+    let mut tail_seconds = None;
+    let mut sample_rate = 48000.0;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--impulse" => impulse = true,
+            "--chorus" => chorus = true,
+            "--preset" | "--type" | "--decay" | "--damping" | "--comb-type" | "--chorus-depth" | "--pre-delay"
+            | "--seed" | "--wet" | "--dry" | "--tail" | "--sample-rate" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("'{}' needs a value", arg))?;
+                match arg.as_str() {
+                    "--preset" => preset = Some(PathBuf::from(value)),
+                    "--tail" => tail_seconds = Some(parse_number(&arg, &value)?),
+                    "--sample-rate" => sample_rate = parse_number(&arg, &value)?,
+                    _ => overrides.push((arg, value)),
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option '{}'", arg)),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    let mut settings = match preset {
+        Some(path) => load_preset(&path)?,
+        None => RenderSettings::default(),
+    };
+    for (option, value) in overrides {
+        match option.as_str() {
+            "--type" => settings.reverb.reverb_type = parse_enum::<ReverbType>(&option, &value)?,
+            "--decay" => settings.reverb.decay = parse_number(&option, &value)?,
+            "--damping" => settings.reverb.damping = parse_number(&option, &value)?,
+            "--comb-type" => settings.reverb.comb_type = parse_enum::<CombType>(&option, &value)?,
+            "--chorus-depth" => settings.reverb.chorus_depth = parse_number(&option, &value)?,
+            "--pre-delay" => settings.reverb.pre_delay = parse_number(&option, &value)?,
+            "--seed" => {
+                settings.reverb.seed = value
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a valid value for --seed", value))?
+            }
+            "--wet" => settings.wet = parse_number(&option, &value)?,
+            "--dry" => settings.dry = parse_number(&option, &value)?,
+            _ => unreachable!(),
+        }
+    }
+    if chorus {
+        settings.reverb.enable_chorus = true;
+    }
+    settings.validate()?;
+    if !(1.0..).contains(&sample_rate) {
+        return Err(format!("'{}' is not a valid sample rate", sample_rate));
+    }
+    if tail_seconds.is_some_and(|tail_seconds| !(0.0..).contains(&tail_seconds)) {
+        return Err(String::from("--tail can't be negative"));
+    }
+
+    let (input, output) = match (impulse, positional.as_slice()) {
+        (false, [input, output]) => (Some(input.clone()), output.clone()),
+        (true, [output]) => (None, output.clone()),
+        _ => return Err(String::from("wrong number of files")),
+    };
+
+    Ok(Args {
+        input,
+        output,
+        settings,
+        tail_seconds,
+        sample_rate,
+    })
+}
+
+fn parse_number(option: &str, value: &str) -> Result<f32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid value for {}", value, option))
+}
+
+/// matches against the same ids the plugin uses, case insensitive
+fn parse_enum<T: Enum>(option: &str, value: &str) -> Result<T, String> {
+    T::ids()
+        .unwrap()
+        .iter()
+        .position(|id| id.eq_ignore_ascii_case(value))
+        .map(T::from_index)
+        .ok_or_else(|| format!("'{}' is not a valid value for {}", value, option))
+}
+
+fn load_preset(path: &Path) -> Result<RenderSettings, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read '{}': {}", path.display(), err))?;
+    serde_json::from_str(&json).map_err(|err| format!("could not parse '{}': {}", path.display(), err))
+}
+
+fn render_impulse(args: &Args) -> Result<(), String> {
+    let ir = ir::render(&args.settings.reverb, args.sample_rate);
+    ir.write_wav(&args.output)
+        .map_err(|err| format!("could not write '{}': {}", args.output.display(), err))
+}
+
+fn render_file(input: &Path, args: &Args) -> Result<(), String> {
+    let mut reader = hound::WavReader::open(input)
+        .map_err(|err| format!("could not open '{}': {}", input.display(), err))?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    if channels == 0 || channels > 2 {
+        return Err(format!("'{}' has {} channels, only mono and stereo are supported", input.display(), channels));
+    }
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect::<Result<_, _>>()
+        }
+    }
+    .map_err(|err| format!("could not read '{}': {}", input.display(), err))?;
+
+    let _ftz = ScopedFtz::enable();
+    let sample_rate = spec.sample_rate as f32;
+    let settings = args.settings;
+    let mut reverb = Reverb::from_settings(sample_rate, &settings.reverb);
+
+    let mut left = Vec::with_capacity(samples.len() / channels);
+    let mut right = Vec::with_capacity(samples.len() / channels);
+    for frame in samples.chunks_exact(channels) {
+        let (l, r) = (frame[0], frame[channels - 1]);
+        let (wet_l, wet_r) = reverb.process(l, r);
+        left.push(mix(&settings, l, wet_l));
+        right.push(mix(&settings, r, wet_r));
+    }
+
+    // the tail is pure reverb, so it's rendered as the wet signal of silence
+    match args.tail_seconds {
+        Some(tail_seconds) => {
+            for _ in 0..(tail_seconds * sample_rate) as usize {
+                let (wet_l, wet_r) = reverb.process(0.0, 0.0);
+                left.push(mix(&settings, 0.0, wet_l));
+                right.push(mix(&settings, 0.0, wet_r));
+            }
+        }
+        None => {
+            let threshold = 10f32.powf(ir::TAIL_THRESHOLD_DB / 20.0);
+            let hold_samples = (ir::TAIL_HOLD_SECONDS * sample_rate) as usize;
+            let max_len = left.len() + (ir::MAX_IR_SECONDS * sample_rate) as usize;
+
+            let mut tail_end = left.len();
+            while left.len() < max_len && left.len() < tail_end + hold_samples {
+                let (wet_l, wet_r) = reverb.process(0.0, 0.0);
+                let l = mix(&settings, 0.0, wet_l);
+                let r = mix(&settings, 0.0, wet_r);
+                left.push(l);
+                right.push(r);
+
+                if l.abs() >= threshold || r.abs() >= threshold {
+                    tail_end = left.len();
+                }
+            }
+
+            left.truncate(tail_end);
+            right.truncate(tail_end);
+        }
+    }
+
+    ir::write_stereo_wav(&args.output, sample_rate, &left, &right)
+        .map_err(|err| format!("could not write '{}': {}", args.output.display(), err))
+}
+
+/// the same dry/wet mix the plugin uses
+fn mix(settings: &RenderSettings, dry: f32, wet: f32) -> f32 {
+    let y = dry * settings.dry + wet * settings.wet;
+    if settings.dry + settings.wet > 1.0 {
+        y / (settings.dry + settings.wet)
+    } else {
+        y
+    }
+}
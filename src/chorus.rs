@@ -1,8 +1,10 @@
 use std::collections::VecDeque;
 
-use rand::distributions::uniform::SampleRange;
+use std::f32::consts::PI;
 
-use crate::{delay::Delay, lfo};
+use rand::Rng;
+
+use crate::{delay::Delay, denormal, lfo, stability};
 
 #[derive(Clone)]
 pub struct Chorus {
@@ -23,7 +25,7 @@ pub struct Chorus {
 }
 
 impl Chorus {
-    pub fn new(sample_rate: f32, delay_ms: f32, feedback: f32, depth: f32, rate: f32, wet: f32, dry: f32) -> Self {
+    pub fn new(sample_rate: f32, delay_ms: f32, feedback: f32, depth: f32, rate: f32, wet: f32, dry: f32, rng: &mut impl Rng) -> Self {
         let mut left_delays: Vec<Delay> = Vec::with_capacity(5);
         let mut right_delays: Vec<Delay> = Vec::with_capacity(5);
         let mut left_lfos: Vec<lfo::LFO> = Vec::with_capacity(5);
@@ -34,8 +36,8 @@ impl Chorus {
         for i in 0..5 {
             left_delays.push(Delay::new(sample_rate as usize, delay_samples, 0.0));
             right_delays.push(Delay::new(sample_rate as usize, delay_samples, 0.0));
-            left_lfos.push(lfo::LFO::new_random_phase(sample_rate, rate));
-            right_lfos.push(lfo::LFO::new_random_phase(sample_rate, rate));
+            left_lfos.push(lfo::LFO::new_with_phase(sample_rate, rate, rng.gen_range(0.0..(2.0 * PI))));
+            right_lfos.push(lfo::LFO::new_with_phase(sample_rate, rate, rng.gen_range(0.0..(2.0 * PI))));
         }
 
         let mut left_feedback_buffer: Box<VecDeque<f32>> 
@@ -55,7 +57,7 @@ impl Chorus {
             left_feedback_buffer,
             right_feedback_buffer,
             sample_rate,
-            feedback: feedback,
+            feedback: stability::limit_chorus_feedback(wet, feedback),
             depth: depth,
             calc_depth: 0.0,
             wet: wet,
@@ -83,7 +85,7 @@ impl Chorus {
             d.delay = delay_samples;
         }
 
-        self.feedback = feedback;
+        self.feedback = stability::limit_chorus_feedback(wet, feedback);
 
         self.depth = depth;
         self.calc_depth = depth / 1000.0 * self.sample_rate;
@@ -102,6 +104,26 @@ impl Chorus {
         self.delay_samples = delay_samples;
     }
 
+    /// how far the LFOs move the delays in milliseconds, without touching the other parameters
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth;
+        self.calc_depth = depth / 1000.0 * self.sample_rate;
+    }
+
+    /// the worst-case loop gain with the current parameters, see `stability::chorus_loop_gain()`
+    pub fn loop_gain(&self) -> f32 {
+        stability::chorus_loop_gain(self.wet, self.feedback)
+    }
+
+    /// clears the delay lines without reallocating them
+    pub fn reset(&mut self) {
+        for delay in self.left_delays.iter_mut().chain(self.right_delays.iter_mut()) {
+            delay.reset();
+        }
+        self.left_feedback_buffer.iter_mut().for_each(|x| *x = 0.0);
+        self.right_feedback_buffer.iter_mut().for_each(|x| *x = 0.0);
+    }
+
     pub fn resize_buffers(&mut self, sample_rate: f32) {
         for (dl, dr) in self.left_delays.iter_mut().zip(self.right_delays.iter_mut()) {
             dl.resize_buffers(sample_rate as usize);
@@ -135,7 +157,7 @@ impl Chorus {
         delayed_signal += self.left_delays[2].process_sample(xx, (self.delay_samples as i32 + offset3) as usize);
 
         self.left_feedback_buffer.rotate_right(1);
-        self.left_feedback_buffer[0] = delayed_signal / 3.0;
+        self.left_feedback_buffer[0] = denormal::flush(delayed_signal / 3.0);
 
         let mut left_out = 
         self.dry * x 
@@ -165,7 +187,7 @@ impl Chorus {
         delayed_signal += self.right_delays[2].process_sample(xx, (self.delay_samples as i32 + offset3) as usize);
 
         self.right_feedback_buffer.rotate_right(1);
-        self.right_feedback_buffer[0] = delayed_signal / 3.0;
+        self.right_feedback_buffer[0] = denormal::flush(delayed_signal / 3.0);
 
         let mut right_out = self.dry * x 
         + self.wet * 1.0/3.0 * delayed_signal;
@@ -0,0 +1,289 @@
+//! The parallel combs of a `Reverb`, processed together with SIMD.
+//!
+//! Each comb is one lane of an `f32x8`: the left channel's combs are the first four lanes and the
+//! right channel's the last four (padded with silent lanes), so one vector operation steps every
+//! comb of both channels. Every lane computes
+//!
+//! `y[n] = x[n] + feedback * y[n - 1 - D] ± x[n - D] - damp * x[n - 2 - D] + damp * y[n - 2]`
+//!
+//! The combs have different delays, so reading them from one shared history would take a
+//! separate load for every lane. Instead each lane writes its samples ahead by its own delay:
+//! `x[n]` goes to slot `n + D` and `y[n]` to slot `n + 1 + D`. Slot `n` then holds `x[n - D]` and
+//! `y[n - 1 - D]` of every lane and slot `n - 2` holds `x[n - 2 - D]`, so the delayed values take
+//! three vector loads and only the writes go lane by lane.
+//!
+//! The scalar version computes one lane at a time from the same histories. It's kept as a
+//! reference and produces bit-identical output, see `CombBank::set_use_simd()`.
+
+use wide::{f32x8, CmpLt};
+
+use crate::{comb::CombType, denormal, stability};
+
+/// the combs of both channels
+pub const LANES: usize = 8;
+
+/// the most combs a channel can have
+pub const MAX_CHANNEL_COMBS: usize = LANES / 2;
+
+#[derive(Clone)]
+pub struct CombBank {
+    sample_rate: f32,
+    /// the left channel's delays followed by the right channel's
+    delays_ms: Vec<f32>,
+    channel_combs: usize,
+    /// in samples, per lane
+    delays: [usize; LANES],
+    feedback: f32x8,
+    /// 1.0 for `CombType::Positive`, -1.0 for `CombType::Negative`
+    feedforward: f32x8,
+    /// zero when the low-pass is disabled
+    damp: f32x8,
+    /// `feedback` and `damp` move towards these in a straight line over `ramp_remaining` samples
+    feedback_target: f32x8,
+    damp_target: f32x8,
+    feedback_step: f32x8,
+    damp_step: f32x8,
+    ramp_remaining: usize,
+    /// every lane's input, written `D` samples ahead
+    x_history: Vec<f32x8>,
+    /// every lane's output, written `D + 1` samples ahead
+    y_history: Vec<f32x8>,
+    /// the outputs one and two samples ago
+    y_previous: [f32x8; 2],
+    /// the slot of the current sample in the histories
+    position: usize,
+    use_simd: bool,
+}
+
+impl CombBank {
+    pub fn new(sample_rate: f32, left_delays_ms: &[f32], right_delays_ms: &[f32]) -> Self {
+        assert_eq!(left_delays_ms.len(), right_delays_ms.len());
+        assert!(left_delays_ms.len() <= MAX_CHANNEL_COMBS);
+
+        let mut bank = Self {
+            sample_rate,
+            delays_ms: left_delays_ms.iter().chain(right_delays_ms).copied().collect(),
+            channel_combs: left_delays_ms.len(),
+            delays: [1; LANES],
+            feedback: f32x8::ZERO,
+            feedforward: f32x8::ZERO,
+            damp: f32x8::ZERO,
+            feedback_target: f32x8::ZERO,
+            damp_target: f32x8::ZERO,
+            feedback_step: f32x8::ZERO,
+            damp_step: f32x8::ZERO,
+            ramp_remaining: 0,
+            x_history: Vec::new(),
+            y_history: Vec::new(),
+            y_previous: [f32x8::ZERO; 2],
+            position: 0,
+            use_simd: true,
+        };
+        bank.resize_buffers(sample_rate);
+        bank
+    }
+
+    /// reallocates the histories for a new sample rate, the coefficients are kept
+    pub fn resize_buffers(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        // the padding lanes never get any input and their coefficients stay at zero
+        self.delays = [1; LANES];
+        for comb in 0..self.len() {
+            self.delays[self.lane(comb)] = ((self.delays_ms[comb] / 1000.0) * sample_rate).round() as usize;
+        }
+
+        // a slot gets written `D + 1` samples before it's read and is read until two samples after
+        let len = (self.delays.iter().max().unwrap() + 3).next_power_of_two();
+        self.x_history = vec![f32x8::ZERO; len];
+        self.y_history = vec![f32x8::ZERO; len];
+        self.y_previous = [f32x8::ZERO; 2];
+        self.position = 0;
+
+        // any ramp in progress is finished right away
+        self.start_ramp(0);
+    }
+
+    /// the number of combs in both channels together
+    pub fn len(&self) -> usize {
+        self.delays_ms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.delays_ms.is_empty()
+    }
+
+    /// the number of combs in each channel
+    pub fn channel_combs(&self) -> usize {
+        self.channel_combs
+    }
+
+    pub fn get_delay_ms(&self, comb: usize) -> f32 {
+        self.delays_ms[comb]
+    }
+
+    /// sets a comb's coefficients. `comb` counts the left channel's combs first
+    pub fn set_params(&mut self, comb: usize, feedback: f32, use_lpf: bool, damp: f32, comb_type: CombType) {
+        self.ramp_params(comb, feedback, use_lpf, damp, comb_type, 0);
+    }
+
+    /// like `set_params()`, but the feedback and damping move to their new values in a straight
+    /// line over the next `samples` samples. every other comb starts a new ramp of the same length
+    /// from where it currently is. the comb type changes right away.
+    ///
+    /// both ends of the ramp are within the `stability` bounds and the loop gain is convex, so
+    /// everything in between stays stable as well
+    pub fn ramp_params(&mut self, comb: usize, feedback: f32, use_lpf: bool, damp: f32, comb_type: CombType, samples: usize) {
+        let (feedback, damp) = stability::limit_comb(feedback, use_lpf, damp);
+        let lane = self.lane(comb);
+
+        self.feedback_target.as_array_mut()[lane] = feedback;
+        self.damp_target.as_array_mut()[lane] = if use_lpf { damp } else { 0.0 };
+        self.feedforward.as_array_mut()[lane] = match comb_type {
+            CombType::Positive => 1.0,
+            CombType::Negative => -1.0,
+        };
+        self.start_ramp(samples);
+    }
+
+    /// starts a new ramp from the current coefficients to the targets, or jumps straight to them
+    /// when `samples` is zero
+    fn start_ramp(&mut self, samples: usize) {
+        self.ramp_remaining = samples;
+        if samples == 0 {
+            self.feedback = self.feedback_target;
+            self.damp = self.damp_target;
+        } else {
+            let samples = f32x8::splat(samples as f32);
+            self.feedback_step = (self.feedback_target - self.feedback) / samples;
+            self.damp_step = (self.damp_target - self.damp) / samples;
+        }
+    }
+
+    /// the current feedback and damping of every lane, ramps included
+    pub fn coefficients(&self) -> ([f32; LANES], [f32; LANES]) {
+        (self.feedback.to_array(), self.damp.to_array())
+    }
+
+    /// the largest worst-case loop gain of all combs, both at their current coefficients and at the
+    /// ones they are ramping to. see `stability::comb_loop_gain()`
+    pub fn loop_gain(&self) -> f32 {
+        [(self.feedback, self.damp), (self.feedback_target, self.damp_target)]
+            .into_iter()
+            .flat_map(|(feedback, damp)| {
+                let (feedback, damp) = (feedback.to_array(), damp.to_array());
+                (0..LANES).map(move |lane| stability::comb_loop_gain(feedback[lane], true, damp[lane]))
+            })
+            .fold(0.0, f32::max)
+    }
+
+    /// clears the histories without reallocating them
+    pub fn reset(&mut self) {
+        self.x_history.iter_mut().for_each(|x| *x = f32x8::ZERO);
+        self.y_history.iter_mut().for_each(|y| *y = f32x8::ZERO);
+        self.y_previous = [f32x8::ZERO; 2];
+    }
+
+    /// switches between the SIMD and the scalar implementation. both produce identical output, the
+    /// scalar one is only there as a reference
+    pub fn set_use_simd(&mut self, use_simd: bool) {
+        self.use_simd = use_simd;
+    }
+
+    /// runs one sample through every comb. the left channel's combs are the first
+    /// `MAX_CHANNEL_COMBS` values of `inputs` and of the result, the right channel's the rest.
+    /// the values past each channel's `channel_combs()` are ignored
+    pub fn process(&mut self, inputs: &[f32; LANES]) -> [f32; LANES] {
+        self.step_ramp();
+
+        let mask = self.x_history.len() - 1;
+        let position = self.position;
+        let x = f32x8::from(*inputs);
+        Self::write_ahead(&mut self.x_history, position, &self.delays, x);
+
+        let y = if self.use_simd {
+            self.process_simd(x)
+        } else {
+            self.process_scalar(x)
+        };
+
+        let stored = y.abs().cmp_lt(f32x8::splat(denormal::DENORMAL_THRESHOLD)).blend(f32x8::ZERO, y);
+        Self::write_ahead(&mut self.y_history, position + 1, &self.delays, stored);
+        self.y_previous = [stored, self.y_previous[0]];
+        self.position = (position + 1) & mask;
+
+        y.to_array()
+    }
+
+    /// `process()` for every sample of `block`, replacing each sample's inputs with the outputs
+    pub fn process_block(&mut self, block: &mut [[f32; LANES]]) {
+        for frame in block.iter_mut() {
+            *frame = self.process(frame);
+        }
+    }
+
+    fn step_ramp(&mut self) {
+        if self.ramp_remaining > 0 {
+            self.ramp_remaining -= 1;
+            // the last step lands exactly on the target instead of wherever the rounding errors
+            // of the steps add up to
+            if self.ramp_remaining == 0 {
+                self.feedback = self.feedback_target;
+                self.damp = self.damp_target;
+            } else {
+                self.feedback += self.feedback_step;
+                self.damp += self.damp_step;
+            }
+        }
+    }
+
+    /// writes every lane of `values` to the slot its delay ahead of `position`
+    fn write_ahead(history: &mut [f32x8], position: usize, delays: &[usize; LANES], values: f32x8) {
+        let mask = history.len() - 1;
+        for (lane, (delay, value)) in delays.iter().zip(values.to_array()).enumerate() {
+            history[(position + delay) & mask].as_array_mut()[lane] = value;
+        }
+    }
+
+    /// the slot `delay` samples before the current one
+    fn slot(history: &[f32x8], position: usize, delay: usize) -> f32x8 {
+        history[position.wrapping_sub(delay) & (history.len() - 1)]
+    }
+
+    fn process_simd(&self, x: f32x8) -> f32x8 {
+        let x_delayed = Self::slot(&self.x_history, self.position, 0);
+        let x_delayed_2 = Self::slot(&self.x_history, self.position, 2);
+        let y_delayed = Self::slot(&self.y_history, self.position, 0);
+
+        let mut y = x + self.feedback * y_delayed;
+        y += self.feedforward * x_delayed;
+        y -= self.damp * x_delayed_2;
+        y += self.damp * self.y_previous[1];
+        y
+    }
+
+    fn process_scalar(&self, x: f32x8) -> f32x8 {
+        let x = x.to_array();
+        let x_delayed = Self::slot(&self.x_history, self.position, 0).to_array();
+        let x_delayed_2 = Self::slot(&self.x_history, self.position, 2).to_array();
+        let y_delayed = Self::slot(&self.y_history, self.position, 0).to_array();
+        let y_previous_2 = self.y_previous[1].to_array();
+        let feedback = self.feedback.to_array();
+        let feedforward = self.feedforward.to_array();
+        let damp = self.damp.to_array();
+
+        let mut y = [0.0; LANES];
+        for lane in 0..LANES {
+            y[lane] = x[lane] + feedback[lane] * y_delayed[lane];
+            y[lane] += feedforward[lane] * x_delayed[lane];
+            y[lane] -= damp[lane] * x_delayed_2[lane];
+            y[lane] += damp[lane] * y_previous_2[lane];
+        }
+        f32x8::from(y)
+    }
+
+    /// the lane a comb lives in
+    fn lane(&self, comb: usize) -> usize {
+        let channel = comb / self.channel_combs;
+        channel * MAX_CHANNEL_COMBS + comb % self.channel_combs
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::VecDeque;
 
+use crate::{denormal, stability};
+
 const MAX_DELAY: usize = 3; // 3 seconds at 44100Hz
 
 #[derive(Clone)]
@@ -22,8 +24,8 @@ impl Delay {
             ybuf.push_front(0.0);
         }
 
-        let feedback = if feedback > 1.0 {
-            1.0
+        let feedback = if feedback > stability::MAX_LOOP_GAIN {
+            stability::MAX_LOOP_GAIN
         } else if feedback < 0.0 {
             0.0
         } else {
@@ -47,6 +49,12 @@ impl Delay {
         }
     }
 
+    /// clears the delay line without reallocating it
+    pub fn reset(&mut self) {
+        self.x_buffer.iter_mut().for_each(|x| *x = 0.0);
+        self.y_buffer.iter_mut().for_each(|y| *y = 0.0);
+    }
+
     // y(n) = x(n - delay) + fb * y(n - delay)
     pub fn process_sample(&mut self, x: f32, delay: usize) -> f32 {
         self.x_buffer.rotate_right(1);
@@ -57,7 +65,7 @@ impl Delay {
         + self.feedback * self.y_buffer.get(delay).unwrap();
 
         self.y_buffer.rotate_right(1);
-        self.y_buffer[0] = y;
+        self.y_buffer[0] = denormal::flush(y);
 
         y
     }
@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use crate::delay::Delay;
+use crate::{delay::Delay, denormal, stability};
 
 #[derive(Clone)]
 pub struct DelayingAllPass {
@@ -42,7 +42,7 @@ impl DelayingAllPass {
             right_y_buffer: right_y_buffer,
             left_x_buffer: left_x_buffer,
             right_x_buffer: right_x_buffer,
-            gain,
+            gain: stability::limit_allpass(gain),
         }
     }
 
@@ -63,7 +63,24 @@ impl DelayingAllPass {
     pub fn set_params(&mut self, delay_ms: f32, gain: f32) {
         self.delay_samples = ((delay_ms as f32 / 1000.0) * self.sample_rate).round() as usize;
         self.delay_ms = delay_ms;
-        self.gain = gain;
+        self.gain = stability::limit_allpass(gain);
+    }
+
+    /// the worst-case loop gain with the current parameters, see `stability::allpass_loop_gain()`
+    pub fn loop_gain(&self) -> f32 {
+        stability::allpass_loop_gain(self.gain)
+    }
+
+    /// clears the delay lines without reallocating them
+    pub fn reset(&mut self) {
+        for buffer in [
+            &mut self.left_y_buffer,
+            &mut self.right_y_buffer,
+            &mut self.left_x_buffer,
+            &mut self.right_x_buffer,
+        ] {
+            buffer.iter_mut().for_each(|x| *x = 0.0);
+        }
     }
 
     pub fn process_left(&mut self, x: f32) -> f32 {
@@ -74,7 +91,7 @@ impl DelayingAllPass {
         self.left_x_buffer.rotate_right(1);
         self.left_x_buffer[0] = x;
         self.left_y_buffer.rotate_right(1);
-        self.left_y_buffer[0] = y;
+        self.left_y_buffer[0] = denormal::flush(y);
         y
     }
 
@@ -86,7 +103,7 @@ impl DelayingAllPass {
         self.right_x_buffer.rotate_right(1);
         self.right_x_buffer[0] = x;
         self.right_y_buffer.rotate_right(1);
-        self.right_y_buffer[0] = y;
+        self.right_y_buffer[0] = denormal::flush(y);
         y
     }
 }
\ No newline at end of file
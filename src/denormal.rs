@@ -0,0 +1,105 @@
+//! Denormal handling. When the input goes silent the feedback loops keep decaying until the samples
+//! become subnormal floats, which are very slow to compute with on most CPUs. We deal with that in
+//! two ways: `ScopedFtz` makes the CPU flush them to zero while processing, and the feedback paths
+//! call `flush()` before storing a sample so the loops reach real silence even when FTZ isn't
+//! available.
+
+use std::marker::PhantomData;
+
+/// samples below this are treated as silence in the feedback paths. this is around -300 dB, far
+/// below anything audible but still well above the subnormal range
+pub const DENORMAL_THRESHOLD: f32 = 1e-15;
+
+/// returns zero for samples that are about to become subnormal
+#[inline]
+pub fn flush(x: f32) -> f32 {
+    if x.abs() < DENORMAL_THRESHOLD {
+        0.0
+    } else {
+        x
+    }
+}
+
+/// enables flush-to-zero (and denormals-are-zero on x86) for the current thread until it's dropped,
+/// after which the previous floating point mode is restored
+pub struct ScopedFtz {
+    previous_mode: Option<usize>,
+    /// the floating point mode is a per-thread setting, so this can't be moved to another thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ScopedFtz {
+    pub fn enable() -> Self {
+        let previous_mode = imp::get_mode();
+        if let Some(mode) = previous_mode {
+            imp::set_mode(mode | imp::FTZ_MASK);
+        }
+
+        Self {
+            previous_mode,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// whether this platform supports flushing denormals to zero
+    pub fn is_supported() -> bool {
+        imp::get_mode().is_some()
+    }
+}
+
+impl Drop for ScopedFtz {
+    fn drop(&mut self) {
+        if let Some(mode) = self.previous_mode {
+            imp::set_mode(mode);
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod imp {
+    use std::arch::asm;
+
+    /// the flush-to-zero (bit 15) and denormals-are-zero (bit 6) flags in MXCSR
+    pub const FTZ_MASK: usize = (1 << 15) | (1 << 6);
+
+    pub fn get_mode() -> Option<usize> {
+        let mut mxcsr: u32 = 0;
+        unsafe { asm!("stmxcsr [{}]", in(reg) &mut mxcsr, options(nostack, preserves_flags)) };
+        Some(mxcsr as usize)
+    }
+
+    pub fn set_mode(mode: usize) {
+        let mxcsr = mode as u32;
+        unsafe { asm!("ldmxcsr [{}]", in(reg) &mxcsr, options(nostack, readonly, preserves_flags)) };
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod imp {
+    use std::arch::asm;
+
+    /// the flush-to-zero flag (bit 24) in FPCR, this covers both inputs and outputs on ARM
+    pub const FTZ_MASK: usize = 1 << 24;
+
+    pub fn get_mode() -> Option<usize> {
+        let fpcr: u64;
+        unsafe { asm!("mrs {}, fpcr", out(reg) fpcr, options(nomem, nostack, preserves_flags)) };
+        Some(fpcr as usize)
+    }
+
+    pub fn set_mode(mode: usize) {
+        let fpcr = mode as u64;
+        unsafe { asm!("msr fpcr, {}", in(reg) fpcr, options(nomem, nostack, preserves_flags)) };
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod imp {
+    pub const FTZ_MASK: usize = 0;
+
+    pub fn get_mode() -> Option<usize> {
+        None
+    }
+
+    pub fn set_mode(_mode: usize) {}
+}
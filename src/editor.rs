@@ -1,39 +1,370 @@
 use std::sync::Arc;
+use std::time::Duration;
 
-use nih_plug::prelude::{util, Editor, Vst3Plugin, EnumParam};
+use nih_plug::prelude::{util, nih_error, Editor, Vst3Plugin, EnumParam, FloatParam, Param, AsyncExecutor, Enum};
 use nih_plug_vizia::vizia::image::Pixel;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
+use nih_plug_vizia::widgets::util::ModifiersExt;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
 
 
-use crate::ReverbPluginParams;
+use crate::{ReverbPlugin, ReverbPluginParams, Task};
 use crate::reverb::ReverbType;
+use crate::ir;
+use crate::meter::{Level, Meters};
+use crate::irplot::IrPlotState;
+use crate::spectrum::{self, Averaging, SpectrumTaps};
+use crate::preset::{self, Preset, PresetValues};
+use crate::snapshot::SLOT_NAMES;
+use crate::modulation::{ModTarget, SharedOffsets, SOURCE_NAMES};
+
+mod ir_view;
+mod spectrum_view;
+
+use ir_view::IrView;
+use spectrum_view::SpectrumView;
+
+/// how long the meters hold their peaks
+const PEAK_HOLD: Duration = Duration::from_millis(600);
 
 
 #[derive(Lens)]
 struct Data {
-    filter_data: Arc<ReverbPluginParams>
+    filter_data: Arc<ReverbPluginParams>,
+    meters: Arc<Meters>,
+    ir_plot: Arc<IrPlotState>,
+    spectrum: Arc<SpectrumTaps>,
+    /// what the plugin's own LFOs and envelope follower add to the parameters
+    modulation_offsets: Arc<SharedOffsets>,
+    async_executor: AsyncExecutor<ReverbPlugin>,
+    /// index into `ir::EXPORT_SAMPLE_RATES`
+    ir_sample_rate_idx: usize,
+    /// index into `spectrum::FFT_SIZES`
+    fft_size_idx: usize,
+    averaging: Averaging,
+    /// the factory presets followed by the user presets
+    presets: Vec<Preset>,
+    /// index into `presets` of the preset that was loaded last
+    preset_idx: Option<usize>,
+    /// what the next user preset gets saved as
+    preset_name: String,
+    /// index into `snapshot::SLOT_NAMES`
+    active_slot: usize,
+    /// whether the active slot changed since it was switched to
+    slot_modified: bool,
+}
+
+enum EditorEvent {
+    CycleIrSampleRate,
+    ExportIr,
+    CycleFftSize,
+    CycleAveraging,
+    LoadPreset(usize),
+    NextPreset,
+    PreviousPreset,
+    SetPresetName(String),
+    SavePreset,
+    SwitchSlot(usize),
+    CopySlot { from: usize, to: usize },
+}
+
+impl Data {
+    /// renders a new impulse response plot in the background, unless one is being rendered
+    /// already. that one picks up the change when it's done
+    fn update_ir_plot(&self) {
+        if self.ir_plot.invalidate() {
+            self.async_executor.execute_background(Task::RenderIrPlot);
+        }
+    }
+
+    /// mirrors the A/B state for the editor
+    fn update_slots(&mut self) {
+        let snapshots = self.filter_data.snapshots.lock().unwrap();
+        self.active_slot = snapshots.active;
+        self.slot_modified = snapshots.is_modified(&self.filter_data.preset_values());
+    }
+
+    fn switch_slot(&mut self, cx: &mut EventContext, slot: usize) {
+        let current = self.filter_data.preset_values();
+        let values = self.filter_data.snapshots.lock().unwrap().switch(slot, current);
+        if let Some(values) = values {
+            apply_preset_values(cx, &self.filter_data, &values);
+        }
+        self.update_slots();
+    }
+
+    fn copy_slot(&mut self, cx: &mut EventContext, from: usize, to: usize) {
+        let current = self.filter_data.preset_values();
+        let values = self.filter_data.snapshots.lock().unwrap().copy(from, to, current);
+        if let Some(values) = values {
+            apply_preset_values(cx, &self.filter_data, &values);
+        }
+        self.update_slots();
+    }
+
+    fn load_preset(&mut self, cx: &mut EventContext, idx: usize) {
+        let preset = &self.presets[idx];
+        apply_preset_values(cx, &self.filter_data, &preset.values);
+        self.preset_name = preset.name.clone();
+        self.preset_idx = Some(idx);
+    }
+
+    /// saves the current settings as a user preset called `preset_name` and selects it
+    fn save_preset(&mut self) {
+        let name = self.preset_name.trim();
+        if name.is_empty() {
+            return;
+        }
+
+        let preset = Preset::new(name, self.filter_data.preset_values());
+        match preset::save_user_preset(&preset::user_preset_dir(), &preset) {
+            Ok(_) => {
+                self.presets = all_presets();
+                self.preset_idx = self.presets.iter().rposition(|other| other.name == preset.name);
+            },
+            Err(err) => nih_error!("Could not save the preset '{}': {}", preset.name, err),
+        }
+    }
 }
 
-impl Model for Data {}
+fn all_presets() -> Vec<Preset> {
+    let mut presets = preset::factory_presets();
+    presets.extend(preset::load_user_presets(&preset::user_preset_dir()));
+    presets
+}
+
+/// sets every parameter to its value in `values`, each one in its own gesture so the host
+/// records the change. it's undone as a whole
+fn apply_preset_values(cx: &mut EventContext, params: &ReverbPluginParams, values: &PresetValues) {
+    fn set<P: Param>(cx: &mut EventContext, param: &P, value: P::Plain) {
+        cx.emit(ParamEvent::BeginSetParameter(param).upcast());
+        cx.emit(ParamEvent::SetParameter(param, value).upcast());
+        cx.emit(ParamEvent::EndSetParameter(param).upcast());
+    }
+
+    cx.emit(UndoEvent::BeginGroup);
+    set(cx, &params.reverb_type, values.reverb_type);
+    set(cx, &params.decay, values.decay);
+    set(cx, &params.damping, values.damping);
+    set(cx, &params.comb_type, values.comb_type);
+    set(cx, &params.wet, values.wet);
+    set(cx, &params.dry, values.dry);
+    set(cx, &params.enable_chorus, values.enable_chorus);
+    set(cx, &params.chorus_depth, values.chorus_depth);
+    set(cx, &params.pre_delay, values.pre_delay);
+    set(cx, &params.mix_mode, values.mix_mode);
+    set(cx, &params.dry_gain, values.dry_gain);
+    set(cx, &params.wet_gain, values.wet_gain);
+    set(cx, &params.mix, values.mix);
+    set(cx, &params.output_gain, values.output_gain);
+    set(cx, &params.send_mode, values.send_mode);
+    set(cx, &params.lfo1_rate, values.lfo1_rate);
+    set(cx, &params.lfo1_target, values.lfo1_target);
+    set(cx, &params.lfo1_depth, values.lfo1_depth);
+    set(cx, &params.lfo2_rate, values.lfo2_rate);
+    set(cx, &params.lfo2_target, values.lfo2_target);
+    set(cx, &params.lfo2_depth, values.lfo2_depth);
+    set(cx, &params.env_release, values.env_release);
+    set(cx, &params.env_target, values.env_target);
+    set(cx, &params.env_depth, values.env_depth);
+    cx.emit(UndoEvent::EndGroup);
+}
+
+impl Model for Data {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        // the host changed a parameter, or one of the widgets did
+        event.map(|param_event, _| match param_event {
+            RawParamEvent::ParametersChanged | RawParamEvent::SetParameterNormalized(_, _) => {
+                self.update_ir_plot();
+                self.update_slots();
+            },
+            _ => (),
+        });
+
+        event.map(|editor_event, _| match editor_event {
+            EditorEvent::CycleIrSampleRate => {
+                self.ir_sample_rate_idx = (self.ir_sample_rate_idx + 1) % ir::EXPORT_SAMPLE_RATES.len();
+            },
+            EditorEvent::ExportIr => {
+                self.async_executor.execute_background(Task::ExportIr {
+                    sample_rate: ir::EXPORT_SAMPLE_RATES[self.ir_sample_rate_idx],
+                });
+            },
+            EditorEvent::CycleFftSize => {
+                self.fft_size_idx = (self.fft_size_idx + 1) % spectrum::FFT_SIZES.len();
+            },
+            EditorEvent::CycleAveraging => {
+                self.averaging = Averaging::from_index((self.averaging.to_index() + 1) % Averaging::variants().len());
+            },
+            EditorEvent::LoadPreset(idx) => {
+                self.load_preset(cx, *idx);
+            },
+            EditorEvent::NextPreset => {
+                let idx = self.preset_idx.map_or(0, |idx| (idx + 1) % self.presets.len());
+                self.load_preset(cx, idx);
+            },
+            EditorEvent::PreviousPreset => {
+                let idx = self.preset_idx.map_or(0, |idx| (idx + self.presets.len() - 1) % self.presets.len());
+                self.load_preset(cx, idx);
+            },
+            EditorEvent::SetPresetName(name) => {
+                self.preset_name = name.clone();
+            },
+            EditorEvent::SavePreset => {
+                self.save_preset();
+            },
+            EditorEvent::SwitchSlot(slot) => {
+                self.switch_slot(cx, *slot);
+            },
+            EditorEvent::CopySlot { from, to } => {
+                self.copy_slot(cx, *from, *to);
+            },
+        });
+
+        // ctrl+z and ctrl+shift+z, or cmd on macOS
+        event.map(|window_event, meta| {
+            if let WindowEvent::KeyDown(Code::KeyZ, _) = window_event {
+                if let Some(undo_event) = UndoEvent::for_z_key(cx.modifiers.command(), cx.modifiers.shift()) {
+                    cx.emit(undo_event);
+                    meta.consume();
+                }
+            }
+        });
+    }
+}
 
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (400, 350))
+    ViziaState::new(|| (1160, 740))
+}
+
+/// a labelled peak meter with the RMS level next to it. `level` picks the signal out of `Meters`
+fn meter_row(cx: &mut Context, name: &str, level: fn(&Meters) -> &Level) {
+    HStack::new(cx, |cx| {
+        Label::new(cx, name)
+        .font_size(15.0)
+        .width(Pixels(50.0));
+
+        PeakMeter::new(
+            cx,
+            Data::meters.map(move |meters| level(meters).peak_db()),
+            Some(PEAK_HOLD),
+        )
+        .width(Stretch(1.0));
+
+        Label::new(cx, Data::meters.map(move |meters| {
+            format!("{:.1} dB RMS", level(meters).rms_db())
+        }))
+        .font_size(12.0)
+        .width(Pixels(90.0));
+    }).height(Pixels(30.0))
+    .col_between(Pixels(10.0));
+}
+
+/// a knob with its name above it
+fn knob(cx: &mut Context, name: &str, param: fn(&Arc<ReverbPluginParams>) -> &FloatParam) {
+    modulated_knob(cx, name, param, ModTarget::None);
+}
+
+/// a knob that also shows what the modulation sources routed to `target` add to the parameter
+fn modulated_knob(cx: &mut Context, name: &str, param: fn(&Arc<ReverbPluginParams>) -> &FloatParam, target: ModTarget) {
+    VStack::new(cx, |cx| {
+        Label::new(cx, name)
+        .font_size(15.0)
+        .height(Pixels(20.0))
+        .child_left(Stretch(1.0))
+        .child_right(Stretch(1.0));
+
+        ParamKnob::with_modulation(
+            cx,
+            Data::filter_data,
+            param,
+            Data::modulation_offsets.map(move |offsets| offsets.load().get(target)),
+        );
+    }).height(Auto)
+    .width(Pixels(80.0))
+    .child_left(Stretch(1.0))
+    .child_right(Stretch(1.0));
+}
+
+/// one modulation source: where it goes, its rate or release time and its depth
+fn modulation_row(
+    cx: &mut Context,
+    name: &str,
+    target: fn(&Arc<ReverbPluginParams>) -> &EnumParam<ModTarget>,
+    time_name: &str,
+    time: fn(&Arc<ReverbPluginParams>) -> &FloatParam,
+    depth: fn(&Arc<ReverbPluginParams>) -> &FloatParam,
+) {
+    HStack::new(cx, |cx| {
+        VStack::new(cx, |cx| {
+            Label::new(cx, name)
+            .font_size(15.0)
+            .height(Pixels(20.0));
+
+            ParamSlider::new(cx, Data::filter_data, target)
+            .height(Pixels(30.0));
+        }).width(Stretch(1.0))
+        .height(Auto)
+        .child_top(Stretch(1.0))
+        .child_bottom(Stretch(1.0))
+        .row_between(Pixels(6.0));
+
+        knob(cx, time_name, time);
+
+        VStack::new(cx, |cx| {
+            Label::new(cx, "depth")
+            .font_size(15.0)
+            .height(Pixels(20.0))
+            .child_left(Stretch(1.0))
+            .child_right(Stretch(1.0));
+
+            ParamKnob::new(cx, Data::filter_data, depth)
+            .bipolar(true);
+        }).height(Auto)
+        .width(Pixels(80.0))
+        .child_left(Stretch(1.0))
+        .child_right(Stretch(1.0));
+    }).height(Auto)
+    .col_between(Pixels(10.0));
 }
 
 pub(crate) fn create(
     filter_data: Arc<ReverbPluginParams>,
+    meters: Arc<Meters>,
+    ir_plot: Arc<IrPlotState>,
+    spectrum: Arc<SpectrumTaps>,
+    modulation_offsets: Arc<SharedOffsets>,
     editor_state: Arc<ViziaState>,
+    async_executor: AsyncExecutor<ReverbPlugin>,
 ) -> Option<Box<dyn Editor>> {
     create_vizia_editor(editor_state, 
         ViziaTheming::Custom, move |cx, _| {
             assets::register_noto_sans_light(cx);
             assets::register_noto_sans_thin(cx);
 
-            Data {
+            // the state may come from before there were slots
+            filter_data.snapshots.lock().unwrap().init(filter_data.preset_values());
+
+            let mut data = Data {
                 filter_data: filter_data.clone(),
-            }.build(cx);
+                meters: meters.clone(),
+                ir_plot: ir_plot.clone(),
+                spectrum: spectrum.clone(),
+                modulation_offsets: modulation_offsets.clone(),
+                async_executor: async_executor.clone(),
+                ir_sample_rate_idx: 1,
+                fft_size_idx: 2,
+                averaging: Averaging::Short,
+                presets: all_presets(),
+                preset_idx: None,
+                preset_name: String::new(),
+                active_slot: 0,
+                slot_modified: false,
+            };
+            data.update_ir_plot();
+            data.update_slots();
+            data.build(cx);
 
             ResizeHandle::new(cx);
 
@@ -46,62 +377,319 @@ pub(crate) fn create(
                 .height(Pixels(50.0))
                 .child_top(Stretch(1.0))
                 .child_bottom(Pixels(30.0));
-                
-                HStack::new(cx, |cx| {
-                    VStack::new(cx, |cx| {
-                        Label::new(cx, "reverb type")
-                        .font_size(15.0)
-                        .height(Pixels(30.0));
 
-                        Label::new(cx, "decay")
-                        .font_size(15.0)
+                // rebuilt when a user preset gets saved
+                Binding::new(cx, Data::presets.map(|presets| presets.len()), |cx, _| {
+                    HStack::new(cx, |cx| {
+                        Button::new(
+                            cx,
+                            |cx| cx.emit(EditorEvent::PreviousPreset),
+                            |cx| Label::new(cx, "<"),
+                        )
                         .height(Pixels(30.0));
 
-                        Label::new(cx, "damping")
-                        .font_size(15.0)
+                        Dropdown::new(
+                            cx,
+                            |cx| Binding::new(cx, Data::preset_idx, |cx, idx| {
+                                let name = match idx.get(cx) {
+                                    Some(idx) => Data::presets.get(cx)[idx].name.clone(),
+                                    None => String::from("no preset"),
+                                };
+                                Label::new(cx, &name);
+                            }),
+                            |cx| {
+                                let presets = Data::presets.get(cx);
+                                for (idx, preset) in presets.iter().enumerate() {
+                                    Label::new(cx, &preset.name)
+                                    .on_press(move |cx| {
+                                        cx.emit(EditorEvent::LoadPreset(idx));
+                                        cx.emit(PopupEvent::Close);
+                                    })
+                                    .width(Stretch(1.0));
+                                }
+                            },
+                        )
+                        .width(Stretch(1.0))
                         .height(Pixels(30.0));
 
-                        Label::new(cx, "comb type")
-                        .font_size(15.0)
+                        Button::new(
+                            cx,
+                            |cx| cx.emit(EditorEvent::NextPreset),
+                            |cx| Label::new(cx, ">"),
+                        )
                         .height(Pixels(30.0));
 
-                        Label::new(cx, "wet")
-                        .font_size(15.0)
+                        Textbox::new(cx, Data::preset_name)
+                        .on_edit(|cx, name| cx.emit(EditorEvent::SetPresetName(name)))
+                        .width(Stretch(1.0))
                         .height(Pixels(30.0));
 
-                        Label::new(cx, "dry")
-                        .font_size(15.0)
+                        Button::new(
+                            cx,
+                            |cx| cx.emit(EditorEvent::SavePreset),
+                            |cx| Label::new(cx, "save preset"),
+                        )
                         .height(Pixels(30.0));
+                    }).height(Pixels(30.0))
+                    .col_between(Pixels(10.0));
+                });
 
-                        Label::new(cx, "enable chorus")
-                        .font_size(15.0)
+                HStack::new(cx, |cx| {
+                    for (slot, name) in SLOT_NAMES.iter().enumerate() {
+                        Button::new(
+                            cx,
+                            move |cx| cx.emit(EditorEvent::SwitchSlot(slot)),
+                            |cx| Label::new(cx, name),
+                        )
+                        .checked(Data::active_slot.map(move |active_slot| *active_slot == slot))
                         .height(Pixels(30.0));
+                    }
+
+                    Button::new(
+                        cx,
+                        |cx| cx.emit(EditorEvent::CopySlot { from: 0, to: 1 }),
+                        |cx| Label::new(cx, "A → B"),
+                    )
+                    .height(Pixels(30.0));
+
+                    Button::new(
+                        cx,
+                        |cx| cx.emit(EditorEvent::CopySlot { from: 1, to: 0 }),
+                        |cx| Label::new(cx, "B → A"),
+                    )
+                    .height(Pixels(30.0));
+
+                    Label::new(cx, Data::slot_modified.map(|modified| {
+                        String::from(if *modified { "modified" } else { "" })
+                    }))
+                    .font_size(12.0)
+                    .width(Stretch(1.0))
+                    .height(Pixels(30.0))
+                    .child_top(Stretch(1.0))
+                    .child_bottom(Stretch(1.0));
+
+                    Button::new(
+                        cx,
+                        |cx| cx.emit(UndoEvent::Undo),
+                        |cx| Label::new(cx, "undo"),
+                    )
+                    .height(Pixels(30.0));
+
+                    Button::new(
+                        cx,
+                        |cx| cx.emit(UndoEvent::Redo),
+                        |cx| Label::new(cx, "redo"),
+                    )
+                    .height(Pixels(30.0));
+                }).height(Pixels(30.0))
+                .top(Pixels(3.0))
+                .col_between(Pixels(10.0));
 
-                    }).child_top(Pixels(6.0)).row_between(Pixels(3.0));
-    
+                HStack::new(cx, |cx| {
+                    // the reverb's own settings
                     VStack::new(cx, |cx| {
-                        ParamSlider::new(cx, Data::filter_data, |params| &params.reverb_type)
-                        .height(Pixels(30.0));
+                        HStack::new(cx, |cx| {
+                            VStack::new(cx, |cx| {
+                                Label::new(cx, "reverb type")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
 
-                        ParamSlider::new(cx, Data::filter_data, |params| &params.decay)
-                        .height(Pixels(30.0));
+                                Label::new(cx, "comb type")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
 
-                        ParamSlider::new(cx, Data::filter_data, |params| &params.damping)
-                        .height(Pixels(30.0));
+                                Label::new(cx, "dry")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
 
-                        ParamSlider::new(cx, Data::filter_data, |params| &params.comb_type)
-                        .height(Pixels(30.0));
+                                Label::new(cx, "enable chorus")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
 
-                        ParamSlider::new(cx, Data::filter_data, |params| &params.wet)
-                        .height(Pixels(30.0));
+                                Label::new(cx, "mix mode")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
 
-                        ParamSlider::new(cx, Data::filter_data, |params| &params.dry)
-                        .height(Pixels(30.0));
-                
-                        ParamButton::new(cx, Data::filter_data, |params| &params.enable_chorus)
-                        .height(Pixels(30.0));
-                    }).row_between(Pixels(3.0));
-                }).col_between(Pixels(30.0));
+                                Label::new(cx, "dry gain")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
+
+                                Label::new(cx, "wet gain")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
+
+                                Label::new(cx, "send mode")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
+
+                                Label::new(cx, "freeze")
+                                .font_size(15.0)
+                                .height(Pixels(30.0));
+
+                            }).child_top(Pixels(6.0)).row_between(Pixels(3.0));
+
+                            VStack::new(cx, |cx| {
+                                ParamSlider::new(cx, Data::filter_data, |params| &params.reverb_type)
+                                .height(Pixels(30.0));
+
+                                ParamSlider::new(cx, Data::filter_data, |params| &params.comb_type)
+                                .height(Pixels(30.0));
+
+                                ParamSlider::new(cx, Data::filter_data, |params| &params.dry)
+                                .height(Pixels(30.0));
+
+                                ParamButton::new(cx, Data::filter_data, |params| &params.enable_chorus)
+                                .height(Pixels(30.0));
+
+                                ParamSlider::new(cx, Data::filter_data, |params| &params.mix_mode)
+                                .height(Pixels(30.0));
+
+                                ParamSlider::new(cx, Data::filter_data, |params| &params.dry_gain)
+                                .height(Pixels(30.0));
+
+                                ParamSlider::new(cx, Data::filter_data, |params| &params.wet_gain)
+                                .height(Pixels(30.0));
+
+                                ParamButton::new(cx, Data::filter_data, |params| &params.send_mode)
+                                .height(Pixels(30.0));
+
+                                ParamButton::new(cx, Data::filter_data, |params| &params.freeze)
+                                .height(Pixels(30.0));
+                            }).row_between(Pixels(3.0));
+                        }).height(Auto)
+                        .col_between(Pixels(30.0));
+
+                        HStack::new(cx, |cx| {
+                            // decay on the x axis, damping on the y axis
+                            ParamXYPad::new(
+                                cx,
+                                Data::filter_data,
+                                |params| &params.decay,
+                                |params| &params.damping,
+                            );
+
+                            VStack::new(cx, |cx| {
+                                HStack::new(cx, |cx| {
+                                    modulated_knob(cx, "decay", |params| &params.decay, ModTarget::Decay);
+                                    modulated_knob(cx, "damping", |params| &params.damping, ModTarget::Damping);
+                                }).height(Auto);
+
+                                HStack::new(cx, |cx| {
+                                    knob(cx, "mix", |params| &params.mix);
+                                    knob(cx, "output gain", |params| &params.output_gain);
+                                }).height(Auto);
+
+                                // the other modulation targets, as knobs so they show it
+                                HStack::new(cx, |cx| {
+                                    modulated_knob(cx, "wet", |params| &params.wet, ModTarget::Wet);
+                                    modulated_knob(cx, "chorus depth", |params| &params.chorus_depth, ModTarget::ChorusDepth);
+                                    modulated_knob(cx, "pre-delay", |params| &params.pre_delay, ModTarget::PreDelay);
+                                }).height(Auto);
+                            }).height(Auto)
+                            .width(Auto)
+                            .row_between(Pixels(6.0));
+                        }).height(Auto)
+                        .top(Pixels(9.0))
+                        .child_left(Stretch(1.0))
+                        .child_right(Stretch(1.0))
+                        .col_between(Pixels(10.0));
+                    }).width(Stretch(1.0))
+                    .height(Auto);
+
+                    // the modulation sources and the levels
+                    VStack::new(cx, |cx| {
+                        VStack::new(cx, |cx| {
+                            modulation_row(cx, SOURCE_NAMES[0], |params| &params.lfo1_target, "rate", |params| &params.lfo1_rate, |params| &params.lfo1_depth);
+                            modulation_row(cx, SOURCE_NAMES[1], |params| &params.lfo2_target, "rate", |params| &params.lfo2_rate, |params| &params.lfo2_depth);
+                            modulation_row(cx, SOURCE_NAMES[2], |params| &params.env_target, "release", |params| &params.env_release, |params| &params.env_depth);
+                        }).height(Auto)
+                        .row_between(Pixels(3.0));
+
+                        VStack::new(cx, |cx| {
+                            meter_row(cx, "in", |meters| &meters.input);
+                            meter_row(cx, "wet", |meters| &meters.wet);
+                            meter_row(cx, "out", |meters| &meters.output);
+                        }).height(Auto)
+                        .top(Pixels(9.0))
+                        .row_between(Pixels(3.0));
+                    }).width(Stretch(1.0))
+                    .height(Auto);
+
+                    // what the current settings sound like
+                    VStack::new(cx, |cx| {
+                        // rebuilt whenever a new plot is ready
+                        Binding::new(cx, Data::ir_plot.map(|ir_plot| ir_plot.generation()), |cx, _| {
+                            let plot = Data::ir_plot.get(cx).latest();
+                            let description = match &plot {
+                                Some(plot) => format!(
+                                    "{:.2} s impulse response, RT60 {}",
+                                    plot.length_seconds,
+                                    plot.rt60.map_or(String::from("not measurable"), |rt60| format!("{:.2} s", rt60)),
+                                ),
+                                None => String::from("rendering impulse response..."),
+                            };
+
+                            VStack::new(cx, move |cx| {
+                                IrView::new(cx, plot)
+                                .height(Pixels(120.0));
+
+                                Label::new(cx, &description)
+                                .font_size(12.0)
+                                .height(Pixels(20.0));
+                            }).height(Auto);
+                        });
+
+                        // the dry input in grey, the wet signal in orange
+                        SpectrumView::new(cx, Data::spectrum, Data::fft_size_idx, Data::averaging)
+                        .height(Pixels(120.0))
+                        .top(Pixels(9.0));
+
+                        HStack::new(cx, |cx| {
+                            Button::new(
+                                cx,
+                                |cx| cx.emit(EditorEvent::CycleFftSize),
+                                |cx| Label::new(cx, Data::fft_size_idx.map(|idx| {
+                                    format!("FFT {}", spectrum::FFT_SIZES[*idx])
+                                })),
+                            )
+                            .height(Pixels(30.0));
+
+                            Button::new(
+                                cx,
+                                |cx| cx.emit(EditorEvent::CycleAveraging),
+                                |cx| Label::new(cx, Data::averaging.map(|averaging| {
+                                    format!("averaging: {}", Averaging::variants()[averaging.to_index()].to_lowercase())
+                                })),
+                            )
+                            .height(Pixels(30.0));
+                        }).height(Pixels(30.0))
+                        .top(Pixels(9.0))
+                        .col_between(Pixels(10.0));
+
+                        HStack::new(cx, |cx| {
+                            Button::new(
+                                cx,
+                                |cx| cx.emit(EditorEvent::CycleIrSampleRate),
+                                |cx| Label::new(cx, Data::ir_sample_rate_idx.map(|idx| {
+                                    format!("{:.1} kHz", ir::EXPORT_SAMPLE_RATES[*idx] / 1000.0)
+                                })),
+                            )
+                            .height(Pixels(30.0));
+
+                            Button::new(
+                                cx,
+                                |cx| cx.emit(EditorEvent::ExportIr),
+                                |cx| Label::new(cx, "export IR"),
+                            )
+                            .height(Pixels(30.0));
+                        }).height(Pixels(30.0))
+                        .top(Pixels(9.0))
+                        .col_between(Pixels(10.0));
+                    }).width(Stretch(1.0))
+                    .height(Auto);
+                }).height(Auto)
+                .top(Pixels(12.0))
+                .col_between(Pixels(30.0));
                 
             }).row_between(Pixels(0.0))
             .child_left(Stretch(1.0))
@@ -0,0 +1,80 @@
+//! Draws an `IrPlot`: the peak level as filled columns, the energy decay curve as a line on top of
+//! it and a marker at the RT60, all over a logarithmic time axis.
+
+use std::sync::Arc;
+
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+
+use crate::irplot::{IrPlot, PLOT_FLOOR_DB};
+
+pub struct IrView {
+    plot: Option<Arc<IrPlot>>,
+}
+
+impl IrView {
+    /// the view doesn't update by itself, wrap it in a `Binding` on `IrPlotState::generation()`
+    pub fn new(cx: &mut Context, plot: Option<Arc<IrPlot>>) -> Handle<Self> {
+        Self { plot }.build(cx, |_| {})
+    }
+}
+
+impl View for IrView {
+    fn element(&self) -> Option<&'static str> {
+        Some("ir-view")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let opacity = cx.opacity();
+
+        let mut background = vg::Path::new();
+        background.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(&mut background, &vg::Paint::color(vg::Color::rgbaf(0.1, 0.1, 0.1, opacity)));
+
+        let Some(plot) = &self.plot else {
+            return;
+        };
+        let x = |x: f32| bounds.x + x * bounds.w;
+        let y = |db: f32| bounds.y + (db / PLOT_FLOOR_DB).clamp(0.0, 1.0) * bounds.h;
+        let columns = plot.columns.len() as f32;
+
+        let mut waveform = vg::Path::new();
+        waveform.move_to(x(0.0), y(PLOT_FLOOR_DB));
+        for (i, column) in plot.columns.iter().enumerate() {
+            waveform.line_to(x(i as f32 / columns), y(column.peak_db));
+            waveform.line_to(x((i + 1) as f32 / columns), y(column.peak_db));
+        }
+        waveform.line_to(x(1.0), y(PLOT_FLOOR_DB));
+        waveform.close();
+        canvas.fill_path(&mut waveform, &vg::Paint::color(vg::Color::rgbaf(0.45, 0.45, 0.45, opacity)));
+
+        let mut curve = vg::Path::new();
+        for (i, column) in plot.columns.iter().enumerate() {
+            let point = (x(i as f32 / columns), y(column.edc_db));
+            if i == 0 {
+                curve.move_to(point.0, point.1);
+            } else {
+                curve.line_to(point.0, point.1);
+            }
+        }
+        let mut paint = vg::Paint::color(vg::Color::rgbaf(0.9, 0.9, 0.9, opacity));
+        paint.set_line_width(cx.logical_to_physical(1.5));
+        canvas.stroke_path(&mut curve, &paint);
+
+        if let Some(rt60) = plot.rt60 {
+            let rt60_x = plot.time_to_x(rt60);
+            if (0.0..=1.0).contains(&rt60_x) {
+                let mut marker = vg::Path::new();
+                marker.move_to(x(rt60_x), bounds.top());
+                marker.line_to(x(rt60_x), bounds.bottom());
+                let mut paint = vg::Paint::color(vg::Color::rgbaf(0.9, 0.5, 0.2, opacity));
+                paint.set_line_width(cx.logical_to_physical(1.0));
+                canvas.stroke_path(&mut marker, &paint);
+            }
+        }
+    }
+}
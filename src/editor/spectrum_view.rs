@@ -0,0 +1,134 @@
+//! Draws the dry input's and the wet signal's spectra on top of each other, over a logarithmic
+//! frequency axis.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+
+use crate::spectrum::{Analyser, Averaging, SampleRing, SpectrumTaps, FFT_SIZES};
+
+/// the frequency axis
+const MIN_FREQUENCY: f32 = 20.0;
+const MAX_FREQUENCY: f32 = 20000.0;
+/// the level axis
+const MIN_DB: f32 = -90.0;
+const MAX_DB: f32 = 0.0;
+
+pub struct SpectrumView<T, S, A>
+where
+    T: Lens<Target = Arc<SpectrumTaps>>,
+    S: Lens<Target = usize>,
+    A: Lens<Target = Averaging>,
+{
+    taps: T,
+    /// index into `FFT_SIZES`
+    fft_size_idx: S,
+    averaging: A,
+    /// the dry and the wet analyser, replaced when the FFT size changes
+    analysers: RefCell<Option<(Analyser, Analyser)>>,
+}
+
+impl<T, S, A> SpectrumView<T, S, A>
+where
+    T: Lens<Target = Arc<SpectrumTaps>>,
+    S: Lens<Target = usize>,
+    A: Lens<Target = Averaging>,
+{
+    /// the spectra are analysed every time the view gets drawn
+    pub fn new(cx: &mut Context, taps: T, fft_size_idx: S, averaging: A) -> Handle<Self> {
+        Self {
+            taps,
+            fft_size_idx,
+            averaging,
+            analysers: RefCell::new(None),
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<T, S, A> View for SpectrumView<T, S, A>
+where
+    T: Lens<Target = Arc<SpectrumTaps>>,
+    S: Lens<Target = usize>,
+    A: Lens<Target = Averaging>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("spectrum-view")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+        let opacity = cx.opacity();
+
+        let mut background = vg::Path::new();
+        background.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(&mut background, &vg::Paint::color(vg::Color::rgbaf(0.1, 0.1, 0.1, opacity)));
+
+        let x = |frequency: f32| {
+            bounds.x + (frequency / MIN_FREQUENCY).ln() / (MAX_FREQUENCY / MIN_FREQUENCY).ln() * bounds.w
+        };
+        let y = |db: f32| bounds.y + ((MAX_DB - db) / (MAX_DB - MIN_DB)).clamp(0.0, 1.0) * bounds.h;
+
+        // a line at every decade
+        let mut grid = vg::Path::new();
+        for frequency in [100.0, 1000.0, 10000.0] {
+            grid.move_to(x(frequency), bounds.top());
+            grid.line_to(x(frequency), bounds.bottom());
+        }
+        let mut paint = vg::Paint::color(vg::Color::rgbaf(0.3, 0.3, 0.3, opacity));
+        paint.set_line_width(cx.logical_to_physical(1.0));
+        canvas.stroke_path(&mut grid, &paint);
+
+        let taps = self.taps.get(cx);
+        let sample_rate = taps.sample_rate();
+        if sample_rate == 0.0 {
+            return;
+        }
+
+        let fft_size = FFT_SIZES[self.fft_size_idx.get(cx)];
+        let averaging = self.averaging.get(cx);
+        let mut analysers = self.analysers.borrow_mut();
+        if analysers.as_ref().is_none_or(|(dry, _)| dry.fft_size() != fft_size) {
+            *analysers = Some((Analyser::new(fft_size, averaging), Analyser::new(fft_size, averaging)));
+        }
+        let (dry, wet) = analysers.as_mut().unwrap();
+
+        let mut draw_spectrum = |analyser: &mut Analyser, ring: &SampleRing, color: vg::Color| {
+            analyser.set_averaging(averaging);
+            if !analyser.update(ring) {
+                return;
+            }
+
+            let mut curve = vg::Path::new();
+            let mut started = false;
+            for bin in 1..analyser.bins() {
+                let frequency = analyser.bin_frequency(bin, sample_rate);
+                if frequency < MIN_FREQUENCY {
+                    continue;
+                }
+                if frequency > MAX_FREQUENCY {
+                    break;
+                }
+
+                let point = (x(frequency), y(analyser.bin_db(bin)));
+                if started {
+                    curve.line_to(point.0, point.1);
+                } else {
+                    curve.move_to(point.0, point.1);
+                    started = true;
+                }
+            }
+            let mut paint = vg::Paint::color(color);
+            paint.set_line_width(cx.logical_to_physical(1.5));
+            canvas.stroke_path(&mut curve, &paint);
+        };
+
+        draw_spectrum(dry, &taps.dry, vg::Color::rgbaf(0.6, 0.6, 0.6, opacity));
+        draw_spectrum(wet, &taps.wet, vg::Color::rgbaf(0.9, 0.5, 0.2, opacity));
+    }
+}
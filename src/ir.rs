@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use nih_plug::prelude::Enum;
+
+use crate::comb::CombType;
+use crate::denormal::ScopedFtz;
+use crate::reverb::{Reverb, ReverbSettings, ReverbType};
+
+/// the render stops once the tail stays below this level
+pub const TAIL_THRESHOLD_DB: f32 = -90.0;
+/// how long the tail has to stay below the threshold before we call it done. this has to be
+/// longer than the longest comb delay, otherwise a quiet gap between two echoes ends the render
+pub const TAIL_HOLD_SECONDS: f32 = 0.5;
+/// hard limit so a 20 second decay with feedback near 1.0 can't render forever
+pub const MAX_IR_SECONDS: f32 = 60.0;
+
+pub const EXPORT_SAMPLE_RATES: [f32; 4] = [44100.0, 48000.0, 88200.0, 96000.0];
+
+/// a stereo impulse response of the wet signal
+pub struct ImpulseResponse {
+    pub sample_rate: f32,
+    pub left: Vec<f32>,
+    pub right: Vec<f32>,
+}
+
+/// runs a unit impulse through a fresh reverb built from `settings` until the tail falls below
+/// `TAIL_THRESHOLD_DB`
+pub fn render(settings: &ReverbSettings, sample_rate: f32) -> ImpulseResponse {
+    render_with_limit(settings, sample_rate, MAX_IR_SECONDS)
+}
+
+/// same as `render`, but stops after `max_seconds` even if the tail is still going
+pub fn render_with_limit(settings: &ReverbSettings, sample_rate: f32, max_seconds: f32) -> ImpulseResponse {
+    let _ftz = ScopedFtz::enable();
+    let mut reverb = Reverb::from_settings(sample_rate, settings);
+
+    let threshold = 10f32.powf(TAIL_THRESHOLD_DB / 20.0);
+    let hold_samples = (TAIL_HOLD_SECONDS * sample_rate) as usize;
+    let max_samples = (max_seconds * sample_rate) as usize;
+
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    // index one past the last sample that was above the threshold
+    let mut tail_end = 0;
+
+    for i in 0..max_samples {
+        let x = if i == 0 { 1.0 } else { 0.0 };
+        let (l, r) = reverb.process(x, x);
+        left.push(l);
+        right.push(r);
+
+        if l.abs() >= threshold || r.abs() >= threshold {
+            tail_end = i + 1;
+        } else if i >= tail_end + hold_samples {
+            break;
+        }
+    }
+
+    left.truncate(tail_end.max(1));
+    right.truncate(tail_end.max(1));
+
+    ImpulseResponse {
+        sample_rate,
+        left,
+        right,
+    }
+}
+
+impl ImpulseResponse {
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty()
+    }
+
+    pub fn length_seconds(&self) -> f32 {
+        self.len() as f32 / self.sample_rate
+    }
+
+    /// writes a 32 bit float stereo wav file
+    pub fn write_wav(&self, path: impl AsRef<Path>) -> Result<(), hound::Error> {
+        write_stereo_wav(path, self.sample_rate, &self.left, &self.right)
+    }
+}
+
+/// writes two channels to a 32 bit float stereo wav file, creating the parent directory if needed
+pub fn write_stereo_wav(path: impl AsRef<Path>, sample_rate: f32, left: &[f32], right: &[f32]) -> Result<(), hound::Error> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: sample_rate as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for (l, r) in left.iter().zip(right.iter()) {
+        writer.write_sample(*l)?;
+        writer.write_sample(*r)?;
+    }
+    writer.finalize()
+}
+
+/// the directory exported impulse responses end up in, usually `~/Music/tsk reverb IRs`
+pub fn export_dir() -> PathBuf {
+    dirs::audio_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("tsk reverb IRs")
+}
+
+/// file name that describes the settings, so exports of different presets don't overwrite each
+/// other
+pub fn export_file_name(settings: &ReverbSettings, sample_rate: f32) -> String {
+    let reverb_type = ReverbType::ids().unwrap()[settings.reverb_type.to_index()];
+    let comb_type = CombType::ids().unwrap()[settings.comb_type.to_index()].to_lowercase();
+
+    format!(
+        "{}{}_{:.0}ms_{:.0}damp_{}{}_{:.0}Hz_{:016x}.wav",
+        reverb_type,
+        if settings.pre_delay == 0.0 { String::new() } else { format!("_{:.0}mspre", settings.pre_delay) },
+        settings.decay,
+        settings.damping * 100.0,
+        comb_type,
+        match (settings.enable_chorus, settings.chorus_depth) {
+            (false, _) => String::new(),
+            (true, depth) if depth == 0.0 => String::from("_chorus"),
+            (true, depth) => format!("_chorus{:.1}ms", depth),
+        },
+        sample_rate,
+        settings.seed,
+    )
+}
@@ -0,0 +1,150 @@
+//! The data behind the editor's impulse response display.
+//!
+//! Whenever the reverb settings change, the editor asks for a new plot and a background task
+//! renders a short impulse response and boils it down to one column per pixel or so: the peak level
+//! and the energy decay curve over a logarithmic time axis, plus the RT60 measured on the curve.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::analysis;
+use crate::ir::{self, ImpulseResponse};
+use crate::reverb::ReverbSettings;
+
+/// the plot doesn't depend on the host's sample rate
+pub const PLOT_SAMPLE_RATE: f32 = 44100.0;
+/// the longest impulse response that gets rendered, longer tails are cut off
+pub const PLOT_MAX_SECONDS: f32 = 30.0;
+/// where the time axis starts, it's logarithmic so it can't start at zero
+pub const PLOT_MIN_SECONDS: f32 = 0.001;
+/// the bottom of the level axis, the top is 0 dB
+pub const PLOT_FLOOR_DB: f32 = ir::TAIL_THRESHOLD_DB;
+pub const PLOT_COLUMNS: usize = 256;
+
+/// one slice of the time axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Column {
+    /// start and end time in seconds
+    pub start: f32,
+    pub end: f32,
+    /// the loudest sample in the slice relative to the loudest sample overall
+    pub peak_db: f32,
+    /// the energy decay curve at the start of the slice
+    pub edc_db: f32,
+}
+
+pub struct IrPlot {
+    /// what the plot was rendered with
+    pub settings: ReverbSettings,
+    pub length_seconds: f32,
+    pub columns: Vec<Column>,
+    pub rt60: Option<f32>,
+}
+
+impl IrPlot {
+    pub fn new(settings: ReverbSettings, ir: &ImpulseResponse, columns: usize) -> Self {
+        let curve = analysis::energy_decay_curve(&[&ir.left, &ir.right]);
+        let rt60 = analysis::decay_times(&curve, ir.sample_rate).rt60();
+
+        let amplitude = |i: usize| ir.left[i].abs().max(ir.right[i].abs());
+        let max_amplitude = (0..ir.len()).map(amplitude).fold(0.0, f32::max);
+        let to_db = |x: f32| {
+            if x > 0.0 {
+                (20.0 * x.log10()).max(PLOT_FLOOR_DB)
+            } else {
+                PLOT_FLOOR_DB
+            }
+        };
+
+        let length_seconds = ir.length_seconds().max(PLOT_MIN_SECONDS * 10.0);
+        let mut plot = Self {
+            settings,
+            length_seconds,
+            columns: Vec::with_capacity(columns),
+            rt60,
+        };
+
+        for column in 0..columns {
+            let start = plot.x_to_time(column as f32 / columns as f32);
+            let end = plot.x_to_time((column + 1) as f32 / columns as f32);
+            // the first column includes everything before `PLOT_MIN_SECONDS`
+            let first = if column == 0 { 0 } else { (start * ir.sample_rate) as usize };
+            let last = ((end * ir.sample_rate) as usize).max(first + 1).min(ir.len());
+
+            let (peak_db, edc_db) = if first < last {
+                let peak = (first..last).map(amplitude).fold(0.0, f32::max);
+                (to_db(peak / max_amplitude), curve[first].clamp(PLOT_FLOOR_DB, 0.0))
+            } else {
+                (PLOT_FLOOR_DB, PLOT_FLOOR_DB)
+            };
+            plot.columns.push(Column { start, end, peak_db, edc_db });
+        }
+
+        plot
+    }
+
+    /// renders the impulse response for `settings` and builds its plot
+    pub fn render(settings: ReverbSettings) -> Self {
+        let ir = ir::render_with_limit(&settings, PLOT_SAMPLE_RATE, PLOT_MAX_SECONDS);
+        Self::new(settings, &ir, PLOT_COLUMNS)
+    }
+
+    /// where `seconds` lies on the time axis, 0 at `PLOT_MIN_SECONDS` and 1 at the end of the
+    /// impulse response
+    pub fn time_to_x(&self, seconds: f32) -> f32 {
+        (seconds / PLOT_MIN_SECONDS).ln() / (self.length_seconds / PLOT_MIN_SECONDS).ln()
+    }
+
+    pub fn x_to_time(&self, x: f32) -> f32 {
+        PLOT_MIN_SECONDS * (self.length_seconds / PLOT_MIN_SECONDS).powf(x)
+    }
+}
+
+/// the latest plot, shared between the editor and the background task that renders it. only one
+/// render runs at a time, and changes that come in while it runs are picked up right after it
+#[derive(Default)]
+pub struct IrPlotState {
+    plot: Mutex<Option<Arc<IrPlot>>>,
+    /// counts up for every new plot, so the editor knows when to redraw
+    generation: AtomicU64,
+    dirty: AtomicBool,
+    rendering: AtomicBool,
+}
+
+impl IrPlotState {
+    /// marks the plot as outdated. returns `true` when no render is running, the caller then has
+    /// to start a background task that calls `render()`
+    pub fn invalidate(&self) -> bool {
+        self.dirty.store(true, Ordering::SeqCst);
+        !self.rendering.swap(true, Ordering::SeqCst)
+    }
+
+    /// renders plots for the current `settings()` until nothing has been invalidated since. plots
+    /// are only rendered again when the settings actually changed, the dry and wet levels don't
+    /// make a difference
+    pub fn render(&self, settings: impl Fn() -> ReverbSettings) {
+        loop {
+            self.dirty.store(false, Ordering::SeqCst);
+
+            let settings = settings();
+            if self.latest().is_none_or(|plot| plot.settings != settings) {
+                let plot = IrPlot::render(settings);
+                *self.plot.lock().unwrap() = Some(Arc::new(plot));
+                self.generation.fetch_add(1, Ordering::SeqCst);
+            }
+
+            self.rendering.store(false, Ordering::SeqCst);
+            if !self.dirty.load(Ordering::SeqCst) || self.rendering.swap(true, Ordering::SeqCst) {
+                break;
+            }
+        }
+    }
+
+    pub fn latest(&self) -> Option<Arc<IrPlot>> {
+        self.plot.lock().unwrap().clone()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+}
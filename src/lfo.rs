@@ -47,6 +47,13 @@ impl LFO {
         scaled
     }
 
+    /// the same as calling `update_lfo()` `samples` times, for LFOs that are only read once per
+    /// block
+    pub fn advance(&mut self, samples: usize) {
+        self.phase += 2.0 * PI * self.rate * samples as f32 / self.sample_rate;
+        self.phase %= 2.0 * PI;
+    }
+
     pub fn update_lfo(&mut self) {
         self.phase += 2.0 * std::f32::consts::PI * self.rate / self.sample_rate;
         if self.phase > 2.0 * PI {
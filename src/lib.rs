@@ -1,22 +1,41 @@
 use chorus::Chorus;
 use filter::FilterType;
 use nih_plug::prelude::*;
-use std::{sync::{Arc, mpsc::channel}, collections::VecDeque, env};
+use std::{sync::{Arc, Mutex, mpsc::channel, atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}}, collections::VecDeque, env};
 
 use nih_plug::prelude::*;
 use nih_plug_vizia::ViziaState;
 
-mod delay;
-mod lfo;
+pub mod delay;
+pub mod lfo;
 mod editor;
-mod chorus;
-mod filter;
-mod comb;
-mod delayingallpass;
-mod reverb;
+pub mod chorus;
+pub mod filter;
+pub mod comb;
+pub mod combbank;
+pub mod delayingallpass;
+pub mod reverb;
+pub mod ir;
+pub mod analysis;
+pub mod stability;
+pub mod denormal;
+pub mod mix;
+pub mod meter;
+pub mod irplot;
+pub mod spectrum;
+pub mod preset;
+pub mod snapshot;
+pub mod state;
+pub mod modulation;
+pub mod predelay;
 
 const MAX_BLOCK_SIZE: usize = 64;
 
+/// feeds the meters, the spectrum and the modulation display even while the editor is closed, for
+/// every instance. the tests have no window to open, this lets them check that path as well
+#[doc(hidden)]
+pub static METER_WITHOUT_EDITOR: AtomicBool = AtomicBool::new(false);
+
 struct ReverbPlugin {
     params: Arc<ReverbPluginParams>,
     comb_reverb: reverb::Reverb,
@@ -24,6 +43,22 @@ struct ReverbPlugin {
     lpf_comb_reverb: reverb::Reverb,
     lpf_schroeder_reverb: reverb::Reverb,
     sample_rate: f32,
+    /// shared with the editor
+    meters: Arc<meter::Meters>,
+    input_meter: meter::LevelMeter,
+    wet_meter: meter::LevelMeter,
+    output_meter: meter::LevelMeter,
+    /// shared with the editor and the background task that renders it
+    ir_plot: Arc<irplot::IrPlotState>,
+    /// shared with the editor's spectrum analyser
+    spectrum: Arc<spectrum::SpectrumTaps>,
+    modulation: modulation::ModMatrix,
+    /// shared with the editor, which shows the modulation on the knobs
+    modulation_offsets: Arc<modulation::SharedOffsets>,
+    /// fades between the mix mode's gains and the send mode's, 1.0 is fully in send mode
+    send_fade: Smoother<f32>,
+    /// the send mode `send_fade` is heading towards
+    send_mode: bool,
 }
 
 #[derive(Params)]
@@ -31,6 +66,20 @@ struct ReverbPluginParams {
     #[persist = "editor-state"]
     editor_state: Arc<ViziaState>,
 
+    /// seeds the random delay offsets, so a saved session (and its exported IR) sounds the same
+    /// every time it gets loaded
+    #[persist = "seed"]
+    seed: AtomicU64,
+
+    /// the layout of the saved state, see `state.rs`. a state that's loaded has already been
+    /// migrated to the current version
+    #[persist = "state-version"]
+    state_version: AtomicU32,
+
+    /// the A/B comparison slots
+    #[persist = "snapshots"]
+    snapshots: Mutex<snapshot::Snapshots>,
+
     #[id = "reverb-type"]
     reverb_type: EnumParam<reverb::ReverbType>,
 
@@ -51,73 +100,332 @@ struct ReverbPluginParams {
 
     #[id = "enable chorus"]
     enable_chorus: BoolParam,
+
+    #[id = "chorus-depth"]
+    chorus_depth: FloatParam,
+
+    #[id = "pre-delay"]
+    pre_delay: FloatParam,
+
+    #[id = "mix-mode"]
+    mix_mode: EnumParam<mix::MixMode>,
+
+    /// used by `MixMode::Independent`
+    #[id = "dry-gain"]
+    dry_gain: FloatParam,
+
+    #[id = "wet-gain"]
+    wet_gain: FloatParam,
+
+    /// used by `MixMode::EqualPower`
+    #[id = "mix"]
+    mix: FloatParam,
+
+    #[id = "output-gain"]
+    output_gain: FloatParam,
+
+    /// for using the reverb on an aux bus. the dry signal is muted whatever the mix parameters
+    /// say and the wet gain is the return level
+    #[id = "send-mode"]
+    send_mode: BoolParam,
+
+    /// holds the current tail and stops feeding the reverb, see `Reverb::set_freeze()`. not part of
+    /// presets, it's something to play rather than a setting
+    #[id = "freeze"]
+    freeze: BoolParam,
+
+    #[id = "lfo1-rate"]
+    lfo1_rate: FloatParam,
+
+    #[id = "lfo1-target"]
+    lfo1_target: EnumParam<modulation::ModTarget>,
+
+    /// bipolar, in normalized units of the target
+    #[id = "lfo1-depth"]
+    lfo1_depth: FloatParam,
+
+    #[id = "lfo2-rate"]
+    lfo2_rate: FloatParam,
+
+    #[id = "lfo2-target"]
+    lfo2_target: EnumParam<modulation::ModTarget>,
+
+    #[id = "lfo2-depth"]
+    lfo2_depth: FloatParam,
+
+    /// how fast the envelope follower falls when the input gets quieter
+    #[id = "env-release"]
+    env_release: FloatParam,
+
+    #[id = "env-target"]
+    env_target: EnumParam<modulation::ModTarget>,
+
+    #[id = "env-depth"]
+    env_depth: FloatParam,
+}
+
+/// work that can't happen on the audio thread
+pub enum Task {
+    /// render the current settings to a wav file in `ir::export_dir()`
+    ExportIr { sample_rate: f32 },
+    /// bring the editor's impulse response display up to date with the current settings
+    RenderIrPlot,
+}
+
+impl ReverbPluginParams {
+    /// the current (unsmoothed) reverb settings
+    fn reverb_settings(&self) -> reverb::ReverbSettings {
+        reverb::ReverbSettings {
+            reverb_type: self.reverb_type.value(),
+            decay: self.decay.value(),
+            damping: self.damping.value(),
+            comb_type: self.comb_type.value(),
+            enable_chorus: self.enable_chorus.value(),
+            chorus_depth: self.chorus_depth.value(),
+            pre_delay: self.pre_delay.value(),
+            seed: self.seed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// the current value of every parameter, for saving as a preset
+    fn preset_values(&self) -> preset::PresetValues {
+        preset::PresetValues {
+            reverb_type: self.reverb_type.value(),
+            decay: self.decay.value(),
+            damping: self.damping.value(),
+            comb_type: self.comb_type.value(),
+            wet: self.wet.value(),
+            dry: self.dry.value(),
+            enable_chorus: self.enable_chorus.value(),
+            chorus_depth: self.chorus_depth.value(),
+            pre_delay: self.pre_delay.value(),
+            mix_mode: self.mix_mode.value(),
+            dry_gain: self.dry_gain.value(),
+            wet_gain: self.wet_gain.value(),
+            mix: self.mix.value(),
+            output_gain: self.output_gain.value(),
+            send_mode: self.send_mode.value(),
+            lfo1_rate: self.lfo1_rate.value(),
+            lfo1_target: self.lfo1_target.value(),
+            lfo1_depth: self.lfo1_depth.value(),
+            lfo2_rate: self.lfo2_rate.value(),
+            lfo2_target: self.lfo2_target.value(),
+            lfo2_depth: self.lfo2_depth.value(),
+            env_release: self.env_release.value(),
+            env_target: self.env_target.value(),
+            env_depth: self.env_depth.value(),
+        }
+    }
+
+    /// the current routing and the sources' settings
+    fn modulation_settings(&self) -> modulation::ModSettings {
+        let route = |target: &EnumParam<modulation::ModTarget>, depth: &FloatParam| modulation::Route {
+            target: target.value(),
+            depth: depth.value(),
+        };
+
+        modulation::ModSettings {
+            lfo_rates: [self.lfo1_rate.value(), self.lfo2_rate.value()],
+            envelope_release: self.env_release.value(),
+            routes: [
+                route(&self.lfo1_target, &self.lfo1_depth),
+                route(&self.lfo2_target, &self.lfo2_depth),
+                route(&self.env_target, &self.env_depth),
+            ],
+        }
+    }
+}
+
+/// `plain` moved by a normalized modulation `offset`
+fn modulate(param: &FloatParam, plain: f32, offset: f32) -> f32 {
+    if offset == 0.0 {
+        plain
+    } else {
+        param.preview_plain(modulation::modulate(param.preview_normalized(plain), offset))
+    }
+}
+
+impl ReverbPlugin {
+    fn active_reverb(&mut self, reverb_type: reverb::ReverbType) -> &mut reverb::Reverb {
+        match reverb_type {
+            reverb::ReverbType::Comb => &mut self.comb_reverb,
+            reverb::ReverbType::Schroeder => &mut self.schroeder_reverb,
+            reverb::ReverbType::LpfComb => &mut self.lpf_comb_reverb,
+            reverb::ReverbType::Moorer => &mut self.lpf_schroeder_reverb,
+        }
+    }
 }
 
 impl Default for ReverbPlugin {
     fn default() -> Self {
+        let params = Arc::new(ReverbPluginParams::default());
+        let seed = params.seed.load(Ordering::Relaxed);
+
         Self {
-            params: Arc::new(ReverbPluginParams::default()),
+            params,
             sample_rate: 44100.0,
-            comb_reverb: reverb::Reverb::new(
+            meters: Arc::new(meter::Meters::default()),
+            input_meter: meter::LevelMeter::new(44100.0),
+            wet_meter: meter::LevelMeter::new(44100.0),
+            output_meter: meter::LevelMeter::new(44100.0),
+            ir_plot: Arc::new(irplot::IrPlotState::default()),
+            spectrum: Arc::new(spectrum::SpectrumTaps::default()),
+            modulation: modulation::ModMatrix::new(44100.0),
+            modulation_offsets: Arc::new(modulation::SharedOffsets::default()),
+            send_fade: Smoother::new(SmoothingStyle::Linear(10.0)),
+            send_mode: false,
+            comb_reverb: reverb::Reverb::new_with_seed(
                 44100.0,
                 100.0,
                 reverb::ReverbType::Comb,
                 0.0,
+                seed,
             ),
-            schroeder_reverb: reverb::Reverb::new(
+            schroeder_reverb: reverb::Reverb::new_with_seed(
                 44100.0,
                 100.0,
                 reverb::ReverbType::Schroeder,
                 0.0,
+                seed,
             ),
-            lpf_comb_reverb: reverb::Reverb::new(
+            lpf_comb_reverb: reverb::Reverb::new_with_seed(
                 44100.0,
                 100.0,
-                reverb::ReverbType::Comb,
+                reverb::ReverbType::LpfComb,
                 0.2,
+                seed,
             ),
-            lpf_schroeder_reverb: reverb::Reverb::new(
+            lpf_schroeder_reverb: reverb::Reverb::new_with_seed(
                 44100.0,
                 100.0,
-                reverb::ReverbType::Schroeder,
+                reverb::ReverbType::Moorer,
                 0.2,
+                seed,
             ),
         }
     }
 }
 
+/// a gain from silence up to `max_db`, skewed so that 0 dB sits near the middle
+fn gain_range(max_db: f32) -> FloatRange {
+    FloatRange::Skewed {
+        min: 0.0,
+        max: util::db_to_gain(max_db),
+        factor: FloatRange::gain_skew_factor(-60.0, max_db),
+    }
+}
+
+fn lfo_rate(name: &str, default: f32) -> FloatParam {
+    FloatParam::new(name, default, FloatRange::Skewed { min: 0.01, max: 10.0, factor: 0.3 })
+    .with_unit(" Hz")
+    .with_value_to_string(formatters::v2s_f32_rounded(2))
+}
+
+/// -100% to 100% of the target's range
+fn mod_depth(name: &str, default: f32) -> FloatParam {
+    FloatParam::new(name, default, FloatRange::Linear { min: -1.0, max: 1.0 })
+    .with_unit("%")
+    .with_value_to_string(formatters::v2s_f32_percentage(0))
+    .with_string_to_value(formatters::s2v_f32_percentage())
+}
+
+/// the parameters start out at the values of `PresetValues::default()`, so the default preset and
+/// a fresh instance can't disagree
 impl Default for ReverbPluginParams {
     fn default() -> Self {
+        let defaults = preset::PresetValues::default();
+
         Self {
             editor_state: editor::default_state(),
-            reverb_type: EnumParam::new("Reverb Type", reverb::ReverbType::Comb),
+            seed: AtomicU64::new(rand::random()),
+            state_version: AtomicU32::new(state::STATE_VERSION),
+            snapshots: Mutex::new(snapshot::Snapshots::default()),
+            reverb_type: EnumParam::new("Reverb Type", defaults.reverb_type),
 
-            decay: FloatParam::new("Decay", 250.0, FloatRange::Skewed { min: 100.0, max: 20000.0, factor: 0.3 })
+            decay: FloatParam::new("Decay", defaults.decay, FloatRange::Skewed { min: 100.0, max: 20000.0, factor: 0.3 })
             .with_unit("ms")
             .with_smoother(SmoothingStyle::Linear(10.0))
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
 
-            damping: FloatParam::new("Damping", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+            damping: FloatParam::new("Damping", defaults.damping, FloatRange::Linear { min: 0.0, max: 1.0 })
             .with_unit("%")
             .with_smoother(SmoothingStyle::Linear(10.0))
             .with_value_to_string(formatters::v2s_f32_percentage(2))
             .with_string_to_value(formatters::s2v_f32_percentage()),
 
-            comb_type: EnumParam::new("Comb Type", comb::CombType::Positive),
+            comb_type: EnumParam::new("Comb Type", defaults.comb_type),
+
+            wet: FloatParam::new("Wet", defaults.wet, FloatRange::Linear { min: 0.0, max: 1.0 })
+            .with_unit("%")
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(formatters::v2s_f32_percentage(2))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
 
-            wet: FloatParam::new("Wet", 0.25, FloatRange::Linear { min: 0.0, max: 1.0 })
+            dry: FloatParam::new("Dry", defaults.dry, FloatRange::Linear { min: 0.0, max: 1.0 })
             .with_unit("%")
             .with_smoother(SmoothingStyle::Linear(10.0))
             .with_value_to_string(formatters::v2s_f32_percentage(2))
             .with_string_to_value(formatters::s2v_f32_percentage()),
 
-            dry: FloatParam::new("Dry", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+            enable_chorus: BoolParam::new("Enable Chorus", defaults.enable_chorus),
+
+            // the chorus used to be built without any depth, so that's the default
+            chorus_depth: FloatParam::new("Chorus Depth", defaults.chorus_depth, FloatRange::Linear { min: 0.0, max: 20.0 })
+            .with_unit(" ms")
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            // moving the delay bends the pitch, a slower ramp keeps that down
+            pre_delay: FloatParam::new("Pre-Delay", defaults.pre_delay, FloatRange::Linear { min: 0.0, max: predelay::MAX_PRE_DELAY_MS })
+            .with_unit(" ms")
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            // old sessions don't have this parameter, so they load with the default and keep
+            // sounding the same
+            mix_mode: EnumParam::new("Mix Mode", defaults.mix_mode),
+
+            dry_gain: FloatParam::new("Dry Gain", defaults.dry_gain, gain_range(6.0))
+            .with_unit(" dB")
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+
+            wet_gain: FloatParam::new("Wet Gain", defaults.wet_gain, gain_range(6.0))
+            .with_unit(" dB")
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+
+            mix: FloatParam::new("Mix", defaults.mix, FloatRange::Linear { min: 0.0, max: 1.0 })
             .with_unit("%")
             .with_smoother(SmoothingStyle::Linear(10.0))
             .with_value_to_string(formatters::v2s_f32_percentage(2))
             .with_string_to_value(formatters::s2v_f32_percentage()),
 
-            enable_chorus: BoolParam::new("Enable Chorus", false),
+            output_gain: FloatParam::new("Output Gain", defaults.output_gain, gain_range(12.0))
+            .with_unit(" dB")
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+
+            send_mode: BoolParam::new("Send Mode", defaults.send_mode),
+
+            freeze: BoolParam::new("Freeze", false),
+
+            lfo1_rate: lfo_rate("LFO 1 Rate", defaults.lfo1_rate),
+            lfo1_target: EnumParam::new("LFO 1 Target", defaults.lfo1_target),
+            lfo1_depth: mod_depth("LFO 1 Depth", defaults.lfo1_depth),
+
+            lfo2_rate: lfo_rate("LFO 2 Rate", defaults.lfo2_rate),
+            lfo2_target: EnumParam::new("LFO 2 Target", defaults.lfo2_target),
+            lfo2_depth: mod_depth("LFO 2 Depth", defaults.lfo2_depth),
+
+            env_release: FloatParam::new("Envelope Release", defaults.env_release, FloatRange::Skewed { min: 10.0, max: 5000.0, factor: 0.3 })
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(0)),
+            env_target: EnumParam::new("Envelope Target", defaults.env_target),
+            env_depth: mod_depth("Envelope Depth", defaults.env_depth),
         }
     }
 }
@@ -125,9 +433,9 @@ impl Default for ReverbPluginParams {
 impl Plugin for ReverbPlugin {
     const NAME: &'static str = "tsk reverb";
     const VENDOR: &'static str = "236587 & 236598";
-    const URL: &'static str = "none";
+    const URL: &'static str = "https://github.com/Maerorr/reverb_vst";
     const EMAIL: &'static str = "none";
-    const VERSION: &'static str = "test";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
     // The first audio IO layout is used as the default. The other layouts may be selected either
     // explicitly or automatically by the host or the user depending on the plugin API/backend.
@@ -137,6 +445,11 @@ impl Plugin for ReverbPlugin {
             main_output_channels: NonZeroU32::new(2),
             ..AudioIOLayout::const_default()
         },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(1),
+            main_output_channels: NonZeroU32::new(1),
+            ..AudioIOLayout::const_default()
+        },
     ];
 
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
@@ -151,12 +464,16 @@ impl Plugin for ReverbPlugin {
     // More advanced plugins can use this to run expensive background tasks. See the field's
     // documentation for more information. `()` means that the plugin does not have any background
     // tasks.
-    type BackgroundTask = ();
+    type BackgroundTask = Task;
 
     fn params(&self) -> Arc<dyn Params> {
         self.params.clone()
     }
 
+    fn filter_state(state: &mut PluginState) {
+        state::migrate(state);
+    }
+
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
@@ -164,10 +481,18 @@ impl Plugin for ReverbPlugin {
         _context: &mut impl InitContext<Self>,
     ) -> bool {
         self.sample_rate = _buffer_config.sample_rate as f32;
-        self.comb_reverb.resize_buffers(self.sample_rate);
-        self.schroeder_reverb.resize_buffers(self.sample_rate);
-        self.lpf_comb_reverb.resize_buffers(self.sample_rate);
-        self.lpf_schroeder_reverb.resize_buffers(self.sample_rate);
+        // the reverbs are rebuilt instead of resized, the seed may have changed when the state got
+        // restored and the delay lengths in samples depend on the sample rate
+        let seed = self.params.seed.load(Ordering::Relaxed);
+        self.comb_reverb = reverb::Reverb::new_with_seed(self.sample_rate, 100.0, reverb::ReverbType::Comb, 0.0, seed);
+        self.schroeder_reverb = reverb::Reverb::new_with_seed(self.sample_rate, 100.0, reverb::ReverbType::Schroeder, 0.0, seed);
+        self.lpf_comb_reverb = reverb::Reverb::new_with_seed(self.sample_rate, 100.0, reverb::ReverbType::LpfComb, 0.2, seed);
+        self.lpf_schroeder_reverb = reverb::Reverb::new_with_seed(self.sample_rate, 100.0, reverb::ReverbType::Moorer, 0.2, seed);
+        for meter in [&mut self.input_meter, &mut self.wet_meter, &mut self.output_meter] {
+            meter.set_sample_rate(self.sample_rate);
+        }
+        self.spectrum.set_sample_rate(self.sample_rate);
+        self.modulation.set_sample_rate(self.sample_rate);
         // Resize buffers and perform other potentially expensive initialization operations here.
         // The `reset()` function is always called right after this function. You can remove this
         // function if you do not need it.
@@ -177,6 +502,16 @@ impl Plugin for ReverbPlugin {
     fn reset(&mut self) {
         // Reset buffers and envelopes here. This can be called from the audio thread and may not
         // allocate. You can remove this function if you do not need it.
+        self.comb_reverb.reset();
+        self.schroeder_reverb.reset();
+        self.lpf_comb_reverb.reset();
+        self.lpf_schroeder_reverb.reset();
+        for meter in [&mut self.input_meter, &mut self.wet_meter, &mut self.output_meter] {
+            meter.reset();
+        }
+        self.modulation.reset();
+        self.send_mode = self.params.send_mode.value();
+        self.send_fade.reset(if self.send_mode { 1.0 } else { 0.0 });
     }
 
     fn process(
@@ -185,96 +520,172 @@ impl Plugin for ReverbPlugin {
         _aux: &mut AuxiliaryBuffers,
         _context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-
-        
-        // In current configuration this function iterates as follows:
-        // 1. outer loop iterates block-size times
-        // 2. inner loop iterates channel-size times. 
-
-        for (i, channel_samples) in buffer.iter_samples().enumerate() {
-            // Smoothing is optionally built into the parameters themselves
-            // let gain = self.params.gain.smoothed.next();
-            let reverb_type = self.params.reverb_type.value();
-            let comb_type = self.params.comb_type.value();
-            let decay = self.params.decay.smoothed.next();
-            let damping = self.params.damping.smoothed.next();
-            let wet = self.params.wet.smoothed.next();
-            let dry = self.params.dry.smoothed.next();
-            let enable_chorus = self.params.enable_chorus.value();
-
-            match reverb_type {
-                reverb::ReverbType::Comb => {
-                    self.comb_reverb.set_params_comb(decay, comb_type, enable_chorus);
-                },
-                reverb::ReverbType::Schroeder => {
-                    self.schroeder_reverb.set_params_schroeder(decay, damping, comb_type, enable_chorus)
-                },
-                reverb::ReverbType::LpfComb => {
-                    self.lpf_comb_reverb.set_params_lpfcomb(decay, damping, comb_type, enable_chorus);
-                },
-                reverb::ReverbType::Moorer => {
-                    self.lpf_schroeder_reverb.set_params_moorer(decay, damping, comb_type, enable_chorus)
-                },
+        let _ftz = denormal::ScopedFtz::enable();
+
+        // the buffer is split into blocks of at most `MAX_BLOCK_SIZE` samples. the decay and
+        // damping are read once per block and the reverb ramps its gains to them over the block,
+        // the mix levels are smoothed per sample. every mix smoother keeps running whatever the
+        // mix mode is, so switching modes never resumes a stale ramp. switching send mode on or
+        // off fades between the two sets of gains the same way. every parameter is read through
+        // `value()` or its smoother, which both follow `modulated_plain_value()`, so the host's
+        // CLAP modulation reaches all of them
+        let mut dry = [0.0; MAX_BLOCK_SIZE];
+        let mut wet = [0.0; MAX_BLOCK_SIZE];
+        let mut dry_gain = [0.0; MAX_BLOCK_SIZE];
+        let mut wet_gain = [0.0; MAX_BLOCK_SIZE];
+        let mut mix = [0.0; MAX_BLOCK_SIZE];
+        let mut output_gain = [0.0; MAX_BLOCK_SIZE];
+        let mut send = [0.0; MAX_BLOCK_SIZE];
+        let mut wet_left = [0.0; MAX_BLOCK_SIZE];
+        let mut wet_right = [0.0; MAX_BLOCK_SIZE];
+        // nobody looks at the meters or the spectrum while the editor is closed
+        let metering = self.params.editor_state.is_open() || METER_WITHOUT_EDITOR.load(Ordering::Relaxed);
+
+        for (_, block) in buffer.iter_blocks(MAX_BLOCK_SIZE) {
+            let block_len = block.samples();
+            let decay = self.params.decay.smoothed.next_step(block_len as u32);
+            let damping = self.params.damping.smoothed.next_step(block_len as u32);
+            let chorus_depth = self.params.chorus_depth.smoothed.next_step(block_len as u32);
+            let pre_delay = self.params.pre_delay.smoothed.next_step(block_len as u32);
+            let mix_mode = self.params.mix_mode.value();
+            let send_mode = self.params.send_mode.value();
+            if send_mode != self.send_mode {
+                self.send_mode = send_mode;
+                self.send_fade.set_target(self.sample_rate, if send_mode { 1.0 } else { 0.0 });
+            }
+            self.params.dry.smoothed.next_block(&mut dry, block_len);
+            self.params.wet.smoothed.next_block(&mut wet, block_len);
+            self.params.dry_gain.smoothed.next_block(&mut dry_gain, block_len);
+            self.params.wet_gain.smoothed.next_block(&mut wet_gain, block_len);
+            self.params.mix.smoothed.next_block(&mut mix, block_len);
+            self.params.output_gain.smoothed.next_block(&mut output_gain, block_len);
+            self.send_fade.next_block(&mut send, block_len);
+
+            let mut channels = block.into_iter();
+            let Some(left) = channels.next() else {
+                continue;
+            };
+            // `None` with the mono layout
+            let mut right = channels.next();
+
+            // the modulation follows the input. decay and damping are ramped by the reverb, the
+            // wet offset gets ramped from the last block's over this one
+            let previous_offsets = self.modulation.offsets();
+            let offsets = self.modulation.process_block(left, right.as_deref().unwrap_or(left), &self.params.modulation_settings());
+            let block_params = reverb::BlockParams {
+                decay: modulate(&self.params.decay, decay, offsets.decay),
+                damping: modulate(&self.params.damping, damping, offsets.damping),
+                comb_type: self.params.comb_type.value(),
+                enable_chorus: self.params.enable_chorus.value(),
+                chorus_depth: modulate(&self.params.chorus_depth, chorus_depth, offsets.chorus_depth),
+                pre_delay: modulate(&self.params.pre_delay, pre_delay, offsets.pre_delay),
+                freeze: self.params.freeze.value(),
             };
+            if previous_offsets.wet != 0.0 || offsets.wet != 0.0 {
+                for (i, level) in wet[..block_len].iter_mut().enumerate() {
+                    let t = (i + 1) as f32 / block_len as f32;
+                    *level = modulate(&self.params.wet, *level, previous_offsets.wet + (offsets.wet - previous_offsets.wet) * t);
+                }
+            }
+            if metering {
+                self.modulation_offsets.store(&offsets);
+            }
 
-            for (num, sample) in channel_samples.into_iter().enumerate() {
-                if num == 0 {
-                    match reverb_type {
-                        reverb::ReverbType::Comb => {
-                            *sample = *sample * dry + wet * self.comb_reverb.process_left(*sample)
-                        },
-                        reverb::ReverbType::Schroeder => {
-                            *sample = *sample * dry + wet * self.schroeder_reverb.process_left(*sample)
-                        },
-                        reverb::ReverbType::LpfComb => {
-                            *sample = *sample * dry + wet * self.lpf_comb_reverb.process_left(*sample)
-                        },
-                        reverb::ReverbType::Moorer => {
-                            *sample = *sample * dry + wet * self.lpf_schroeder_reverb.process_left(*sample)
-                        },
-                    }
-                } else {
-                    match reverb_type {
-                        reverb::ReverbType::Comb => {
-                            *sample = *sample * dry + wet * self.comb_reverb.process_right(*sample)
-                        },
-                        reverb::ReverbType::Schroeder => {
-                            *sample = *sample * dry + wet * self.schroeder_reverb.process_right(*sample)
-                        },
-                        reverb::ReverbType::LpfComb => {
-                            *sample = *sample * dry + wet * self.lpf_comb_reverb.process_right(*sample)
-                        },
-                        reverb::ReverbType::Moorer => {
-                            *sample = *sample * dry + wet * self.lpf_schroeder_reverb.process_right(*sample)
-                        },
-                    }
+            wet_left[..block_len].copy_from_slice(left);
+            wet_right[..block_len].copy_from_slice(right.as_deref().unwrap_or(left));
+            let reverb = self.active_reverb(self.params.reverb_type.value());
+            reverb.process_block(&mut wet_left[..block_len], &mut wet_right[..block_len], &block_params);
+            if right.is_none() {
+                // the reverb still runs in stereo, a mono track gets both of its outputs
+                for i in 0..block_len {
+                    let mono = (wet_left[i] + wet_right[i]) * 0.5;
+                    wet_left[i] = mono;
+                    wet_right[i] = mono;
                 }
-                
-                if dry + wet > 1.0 {
-                    *sample = *sample / (dry + wet);
+            }
+
+            for i in 0..block_len {
+                let levels = mix::MixLevels {
+                    dry: dry[i],
+                    wet: wet[i],
+                    dry_gain: dry_gain[i],
+                    wet_gain: wet_gain[i],
+                    mix: mix[i],
+                };
+                let (dry, wet) = mix::crossfade(mix_mode.gains(&levels), mix::send_gains(&levels), send[i]);
+                let input_left = left[i];
+                let input_right = right.as_ref().map_or(input_left, |right| right[i]);
+                let (left_wet, right_wet) = (wet_left[i] * wet, wet_right[i] * wet);
+                if metering {
+                    self.input_meter.process_sample(input_left, input_right);
+                    self.wet_meter.process_sample(left_wet, right_wet);
+                    self.spectrum.dry.push((input_left + input_right) * 0.5);
+                    self.spectrum.wet.push((wet_left[i] + wet_right[i]) * 0.5);
+                }
+
+                let output_left = (input_left * dry + left_wet) * output_gain[i];
+                let output_right = (input_right * dry + right_wet) * output_gain[i];
+                left[i] = output_left;
+                if let Some(right) = right.as_mut() {
+                    right[i] = output_right;
+                }
+                if metering {
+                    self.output_meter.process_sample(output_left, output_right);
                 }
             }
         }
 
+        if metering {
+            self.input_meter.publish(&self.meters.input);
+            self.wet_meter.publish(&self.meters.wet);
+            self.output_meter.publish(&self.meters.output);
+        }
+
         ProcessStatus::Normal
     }
 
-    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let params = self.params.clone();
+        let ir_plot = self.ir_plot.clone();
+        Box::new(move |task| match task {
+            Task::ExportIr { sample_rate } => {
+                let settings = params.reverb_settings();
+                let ir = ir::render(&settings, sample_rate);
+                let path = ir::export_dir().join(ir::export_file_name(&settings, sample_rate));
+                match ir.write_wav(&path) {
+                    Ok(()) => nih_log!("Exported a {:.2} s impulse response to '{}'", ir.length_seconds(), path.display()),
+                    Err(err) => nih_error!("Could not write '{}': {}", path.display(), err),
+                }
+            }
+            Task::RenderIrPlot => ir_plot.render(|| params.reverb_settings()),
+        })
+    }
+
+    fn editor(&mut self, async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
         editor::create(
             self.params.clone(),
+            self.meters.clone(),
+            self.ir_plot.clone(),
+            self.spectrum.clone(),
+            self.modulation_offsets.clone(),
             self.params.editor_state.clone(),
+            async_executor,
         )
     }
 }
 
 impl ClapPlugin for ReverbPlugin {
-    const CLAP_ID: &'static str = "{{ cookiecutter.clap_id }}";
-    const CLAP_DESCRIPTION: Option<&'static str> = Some("{{ cookiecutter.description }}");
+    const CLAP_ID: &'static str = "com.tsk.reverb";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("Comb, Schroeder and Moorer reverbs with an optional chorus");
     const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
-    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_SUPPORT_URL: Option<&'static str> = Some("https://github.com/Maerorr/reverb_vst/issues");
 
-    // Don't forget to change these features
-    const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Stereo];
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Reverb,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+    ];
 }
 
 impl Vst3Plugin for ReverbPlugin {
@@ -285,5 +696,5 @@ impl Vst3Plugin for ReverbPlugin {
         &[Vst3SubCategory::Reverb];
 }
 
-//nih_export_clap!(MaerorChorus);
+nih_export_clap!(ReverbPlugin);
 nih_export_vst3!(ReverbPlugin);
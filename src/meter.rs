@@ -0,0 +1,120 @@
+//! Signal levels for the editor's meters.
+//!
+//! The audio thread runs a `LevelMeter` per signal and publishes the results to the `Meters` it
+//! shares with the editor. The levels are stored as the bits of an `f32` in atomics, so neither
+//! side ever has to wait for the other.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use nih_plug::prelude::util;
+
+use crate::denormal;
+
+/// how long the peak level takes to fall by 12 dB once the signal stops
+const PEAK_DECAY_MS: f32 = 150.0;
+/// the time constant of the RMS average
+const RMS_WINDOW_MS: f32 = 300.0;
+
+/// the latest peak and RMS level of one signal
+pub struct Level {
+    peak: AtomicU32,
+    rms: AtomicU32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self {
+            peak: AtomicU32::new(0.0f32.to_bits()),
+            rms: AtomicU32::new(0.0f32.to_bits()),
+        }
+    }
+}
+
+impl Level {
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.peak.load(Ordering::Relaxed))
+    }
+
+    pub fn rms(&self) -> f32 {
+        f32::from_bits(self.rms.load(Ordering::Relaxed))
+    }
+
+    pub fn peak_db(&self) -> f32 {
+        util::gain_to_db(self.peak())
+    }
+
+    pub fn rms_db(&self) -> f32 {
+        util::gain_to_db(self.rms())
+    }
+
+    fn store(&self, peak: f32, rms: f32) {
+        self.peak.store(peak.to_bits(), Ordering::Relaxed);
+        self.rms.store(rms.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// every meter the editor shows, shared between the plugin and the editor
+#[derive(Default)]
+pub struct Meters {
+    pub input: Level,
+    /// the reverb's output after the mix gains, before the output gain
+    pub wet: Level,
+    pub output: Level,
+}
+
+/// measures the level of a stereo signal on the audio thread, the louder channel counts
+#[derive(Clone, Debug)]
+pub struct LevelMeter {
+    peak: f32,
+    mean_square: f32,
+    peak_weight: f32,
+    rms_weight: f32,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut meter = Self {
+            peak: 0.0,
+            mean_square: 0.0,
+            peak_weight: 0.0,
+            rms_weight: 0.0,
+        };
+        meter.set_sample_rate(sample_rate);
+        meter
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.peak_weight = 0.25f32.powf((sample_rate * PEAK_DECAY_MS / 1000.0).recip());
+        self.rms_weight = (-1.0 / (sample_rate * RMS_WINDOW_MS / 1000.0)).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.peak = 0.0;
+        self.mean_square = 0.0;
+    }
+
+    pub fn process_sample(&mut self, left: f32, right: f32) {
+        let amplitude = left.abs().max(right.abs());
+        if amplitude > self.peak {
+            self.peak = amplitude;
+        } else {
+            self.peak = denormal::flush(self.peak * self.peak_weight + amplitude * (1.0 - self.peak_weight));
+        }
+
+        let square = (left * left).max(right * right);
+        self.mean_square = denormal::flush(self.mean_square * self.rms_weight + square * (1.0 - self.rms_weight));
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    pub fn rms(&self) -> f32 {
+        self.mean_square.sqrt()
+    }
+
+    /// makes the current levels visible to the editor
+    pub fn publish(&self, level: &Level) {
+        level.store(self.peak(), self.rms());
+    }
+}
@@ -0,0 +1,96 @@
+//! How the dry and the wet signal are mixed together.
+
+use std::f32::consts::FRAC_PI_2;
+
+use nih_plug::prelude::Enum;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MixMode {
+    /// `dry * x + wet * y`, divided by `dry + wet` when that goes over one. this is how the
+    /// plugin always mixed, so it's the default for sessions saved before the other modes existed
+    Legacy,
+    /// separate dry and wet gains in dB that don't affect each other
+    Independent,
+    /// a single mix knob that keeps the power constant
+    EqualPower,
+}
+
+impl Enum for MixMode {
+    fn variants() -> &'static [&'static str] {
+        &["Legacy", "Independent", "Equal Power"]
+    }
+
+    fn ids() -> Option<&'static [&'static str]> {
+        Some(&["legacy", "independent", "equal-power"])
+    }
+
+    fn to_index(self) -> usize {
+        match self {
+            MixMode::Legacy => 0,
+            MixMode::Independent => 1,
+            MixMode::EqualPower => 2,
+        }
+    }
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => MixMode::Legacy,
+            1 => MixMode::Independent,
+            2 => MixMode::EqualPower,
+            _ => panic!("Invalid index for MixMode"),
+        }
+    }
+}
+
+/// the values of every mix parameter at one sample. each mode only looks at its own
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MixLevels {
+    /// the legacy dry level, 0 to 1
+    pub dry: f32,
+    /// the legacy wet level, 0 to 1
+    pub wet: f32,
+    /// linear gain, the parameter shows it in dB
+    pub dry_gain: f32,
+    pub wet_gain: f32,
+    /// 0 is fully dry, 1 fully wet
+    pub mix: f32,
+}
+
+impl MixMode {
+    /// the gains the dry and the wet signal get multiplied with
+    pub fn gains(self, levels: &MixLevels) -> (f32, f32) {
+        match self {
+            MixMode::Legacy => {
+                let sum = levels.dry + levels.wet;
+                if sum > 1.0 {
+                    (levels.dry / sum, levels.wet / sum)
+                } else {
+                    (levels.dry, levels.wet)
+                }
+            },
+            MixMode::Independent => (levels.dry_gain, levels.wet_gain),
+            MixMode::EqualPower => {
+                let angle = levels.mix.clamp(0.0, 1.0) * FRAC_PI_2;
+                // the cosine of the rounded pi / 2 is slightly negative
+                (angle.cos().max(0.0), angle.sin())
+            },
+        }
+    }
+}
+
+/// the gains in send mode, where the plugin sits on an aux bus. there is no dry signal and nothing
+/// gets normalized, the wet gain is the return level
+pub fn send_gains(levels: &MixLevels) -> (f32, f32) {
+    (0.0, levels.wet_gain)
+}
+
+/// fades from the `from` gains to the `to` gains, an `amount` of 0 gives exactly `from` and 1
+/// exactly `to`
+pub fn crossfade(from: (f32, f32), to: (f32, f32), amount: f32) -> (f32, f32) {
+    (
+        from.0 * (1.0 - amount) + to.0 * amount,
+        from.1 * (1.0 - amount) + to.1 * amount,
+    )
+}
@@ -0,0 +1,289 @@
+//! Modulation from inside the plugin: two LFOs and an envelope follower on the input, each routed
+//! to one parameter with a bipolar depth.
+//!
+//! The sources are read once per block. Their offsets are in normalized units, so a depth of 1
+//! sweeps the whole range of the target, and they are added to the target's value after the
+//! host's automation and modulation. `SharedOffsets` makes the latest offsets visible to the
+//! editor, which draws them like the host's modulation.
+//!
+//! The offsets don't go through the parameters' `modulated_normalized_value()`. nih_plug only
+//! sets that from the host's modulation events and gives the plugin no way to write it, and
+//! changing the parameters' values instead would show up as automation in the host and get saved
+//! with the session. So the knobs take the offsets as a separate lens with
+//! `ParamKnob::with_modulation()` and add them to `modulated_normalized_value()`, which still
+//! shows the host's modulation.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use nih_plug::prelude::{util, Enum};
+use serde::{Deserialize, Serialize};
+
+use crate::{denormal, lfo};
+
+pub const SOURCE_NAMES: [&str; 3] = ["LFO 1", "LFO 2", "envelope"];
+pub const SOURCES: usize = SOURCE_NAMES.len();
+
+/// how fast the envelope follower reacts when the input gets louder
+const ENVELOPE_ATTACK_MS: f32 = 5.0;
+/// the input level the envelope starts rising from, it reaches its maximum at 0 dBFS
+const ENVELOPE_FLOOR_DB: f32 = -60.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModTarget {
+    None,
+    Decay,
+    Damping,
+    /// the `wet` parameter, which only `MixMode::Legacy` uses
+    Wet,
+    /// only heard while the chorus is enabled
+    ChorusDepth,
+    PreDelay,
+}
+
+impl Enum for ModTarget {
+    fn variants() -> &'static [&'static str] {
+        &["None", "Decay", "Damping", "Wet", "Chorus Depth", "Pre-Delay"]
+    }
+
+    fn ids() -> Option<&'static [&'static str]> {
+        Some(&["none", "decay", "damping", "wet", "chorus-depth", "pre-delay"])
+    }
+
+    fn to_index(self) -> usize {
+        match self {
+            ModTarget::None => 0,
+            ModTarget::Decay => 1,
+            ModTarget::Damping => 2,
+            ModTarget::Wet => 3,
+            ModTarget::ChorusDepth => 4,
+            ModTarget::PreDelay => 5,
+        }
+    }
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => ModTarget::None,
+            1 => ModTarget::Decay,
+            2 => ModTarget::Damping,
+            3 => ModTarget::Wet,
+            4 => ModTarget::ChorusDepth,
+            5 => ModTarget::PreDelay,
+            _ => panic!("Invalid index for ModTarget"),
+        }
+    }
+}
+
+/// where one source goes and how much of it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Route {
+    pub target: ModTarget,
+    /// -1 to 1
+    pub depth: f32,
+}
+
+/// the parameters for one `ModMatrix::process_block()` call
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModSettings {
+    /// in Hz
+    pub lfo_rates: [f32; 2],
+    /// in milliseconds
+    pub envelope_release: f32,
+    /// in the order of `SOURCE_NAMES`
+    pub routes: [Route; SOURCES],
+}
+
+/// the normalized offset of every target
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Offsets {
+    pub decay: f32,
+    pub damping: f32,
+    pub wet: f32,
+    pub chorus_depth: f32,
+    pub pre_delay: f32,
+}
+
+impl Offsets {
+    pub fn get(&self, target: ModTarget) -> f32 {
+        match target {
+            ModTarget::None => 0.0,
+            ModTarget::Decay => self.decay,
+            ModTarget::Damping => self.damping,
+            ModTarget::Wet => self.wet,
+            ModTarget::ChorusDepth => self.chorus_depth,
+            ModTarget::PreDelay => self.pre_delay,
+        }
+    }
+
+    fn add(&mut self, target: ModTarget, amount: f32) {
+        match target {
+            ModTarget::None => (),
+            ModTarget::Decay => self.decay += amount,
+            ModTarget::Damping => self.damping += amount,
+            ModTarget::Wet => self.wet += amount,
+            ModTarget::ChorusDepth => self.chorus_depth += amount,
+            ModTarget::PreDelay => self.pre_delay += amount,
+        }
+    }
+}
+
+/// `normalized` moved by `offset`, kept within the parameter's range
+pub fn modulate(normalized: f32, offset: f32) -> f32 {
+    (normalized + offset).clamp(0.0, 1.0)
+}
+
+/// the latest offsets, shared between the plugin and the editor. stored as the bits of an `f32`
+/// like the meters
+#[derive(Default)]
+pub struct SharedOffsets {
+    decay: AtomicU32,
+    damping: AtomicU32,
+    wet: AtomicU32,
+    chorus_depth: AtomicU32,
+    pre_delay: AtomicU32,
+}
+
+impl SharedOffsets {
+    pub fn store(&self, offsets: &Offsets) {
+        self.decay.store(offsets.decay.to_bits(), Ordering::Relaxed);
+        self.damping.store(offsets.damping.to_bits(), Ordering::Relaxed);
+        self.wet.store(offsets.wet.to_bits(), Ordering::Relaxed);
+        self.chorus_depth.store(offsets.chorus_depth.to_bits(), Ordering::Relaxed);
+        self.pre_delay.store(offsets.pre_delay.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn load(&self) -> Offsets {
+        Offsets {
+            decay: f32::from_bits(self.decay.load(Ordering::Relaxed)),
+            damping: f32::from_bits(self.damping.load(Ordering::Relaxed)),
+            wet: f32::from_bits(self.wet.load(Ordering::Relaxed)),
+            chorus_depth: f32::from_bits(self.chorus_depth.load(Ordering::Relaxed)),
+            pre_delay: f32::from_bits(self.pre_delay.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// follows the level of a stereo signal, the louder channel counts. the output goes from 0 at
+/// `ENVELOPE_FLOOR_DB` to 1 at 0 dBFS
+#[derive(Clone, Debug)]
+pub struct EnvelopeFollower {
+    envelope: f32,
+    sample_rate: f32,
+    release_ms: f32,
+    attack_weight: f32,
+    release_weight: f32,
+}
+
+/// the weight of a one pole smoother that gets about two thirds of the way in `time_ms`
+fn smoother_weight(sample_rate: f32, time_ms: f32) -> f32 {
+    (-1.0 / (sample_rate * time_ms / 1000.0)).exp()
+}
+
+impl EnvelopeFollower {
+    pub fn new(sample_rate: f32, release_ms: f32) -> Self {
+        let mut follower = Self {
+            envelope: 0.0,
+            sample_rate,
+            release_ms,
+            attack_weight: 0.0,
+            release_weight: 0.0,
+        };
+        follower.set_sample_rate(sample_rate);
+        follower
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.attack_weight = smoother_weight(sample_rate, ENVELOPE_ATTACK_MS);
+        self.release_weight = smoother_weight(sample_rate, self.release_ms);
+    }
+
+    pub fn set_release(&mut self, release_ms: f32) {
+        if release_ms != self.release_ms {
+            self.release_ms = release_ms;
+            self.release_weight = smoother_weight(self.sample_rate, release_ms);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+
+    pub fn process_sample(&mut self, left: f32, right: f32) {
+        let amplitude = left.abs().max(right.abs());
+        let weight = if amplitude > self.envelope {
+            self.attack_weight
+        } else {
+            self.release_weight
+        };
+        self.envelope = denormal::flush(self.envelope * weight + amplitude * (1.0 - weight));
+    }
+
+    /// 0 to 1
+    pub fn value(&self) -> f32 {
+        ((util::gain_to_db(self.envelope) - ENVELOPE_FLOOR_DB) / -ENVELOPE_FLOOR_DB).clamp(0.0, 1.0)
+    }
+}
+
+/// the modulation sources, run on the audio thread
+#[derive(Clone)]
+pub struct ModMatrix {
+    lfos: [lfo::LFO; 2],
+    envelope: EnvelopeFollower,
+    /// from the last block
+    offsets: Offsets,
+}
+
+impl ModMatrix {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            lfos: [lfo::LFO::new(sample_rate, 1.0), lfo::LFO::new(sample_rate, 1.0)],
+            envelope: EnvelopeFollower::new(sample_rate, 300.0),
+            offsets: Offsets::default(),
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.sample_rate = sample_rate;
+        }
+        self.envelope.set_sample_rate(sample_rate);
+    }
+
+    /// starts the LFOs from the beginning of their cycle, so a render always starts the same way
+    pub fn reset(&mut self) {
+        for lfo in self.lfos.iter_mut() {
+            *lfo = lfo::LFO::new(lfo.sample_rate, lfo.rate);
+        }
+        self.envelope.reset();
+        self.offsets = Offsets::default();
+    }
+
+    /// the offsets `process_block()` returned last
+    pub fn offsets(&self) -> Offsets {
+        self.offsets
+    }
+
+    /// runs the sources over a block of the input and returns the offsets at its end
+    pub fn process_block(&mut self, left: &[f32], right: &[f32], settings: &ModSettings) -> Offsets {
+        let mut values = [0.0; SOURCES];
+        for (i, lfo) in self.lfos.iter_mut().enumerate() {
+            lfo.rate = settings.lfo_rates[i];
+            lfo.advance(left.len());
+            values[i] = lfo.next_value();
+        }
+
+        self.envelope.set_release(settings.envelope_release);
+        for (left, right) in left.iter().zip(right.iter()) {
+            self.envelope.process_sample(*left, *right);
+        }
+        values[2] = self.envelope.value();
+
+        let mut offsets = Offsets::default();
+        for (route, value) in settings.routes.iter().zip(values) {
+            offsets.add(route.target, route.depth * value);
+        }
+        self.offsets = offsets;
+        offsets
+    }
+}
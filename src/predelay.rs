@@ -0,0 +1,110 @@
+//! The pre-delay in front of the combs: a plain stereo delay line without feedback.
+//!
+//! The delay can be changed (and modulated) while audio is running, so it isn't rounded to whole
+//! samples. It moves to a new value in a straight line over a block, and the output is linearly
+//! interpolated between the two samples around the current delay. At a delay of zero the input
+//! comes straight back out, so a reverb without pre-delay sounds exactly like it did before there
+//! was one.
+
+/// the longest pre-delay. `ir::TAIL_HOLD_SECONDS` has to stay longer than this, otherwise the
+/// silence at the start of an impulse response would end the render
+pub const MAX_PRE_DELAY_MS: f32 = 200.0;
+
+#[derive(Clone)]
+pub struct PreDelay {
+    sample_rate: f32,
+    /// both channels' input, the current sample is at `position`
+    history: Vec<[f32; 2]>,
+    position: usize,
+    /// in samples
+    delay: f32,
+    /// `delay` moves towards this in a straight line over `ramp_remaining` samples
+    delay_target: f32,
+    delay_step: f32,
+    ramp_remaining: usize,
+}
+
+impl PreDelay {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut pre_delay = Self {
+            sample_rate,
+            history: Vec::new(),
+            position: 0,
+            delay: 0.0,
+            delay_target: 0.0,
+            delay_step: 0.0,
+            ramp_remaining: 0,
+        };
+        pre_delay.resize_buffers(sample_rate);
+        pre_delay
+    }
+
+    /// reallocates the history for a new sample rate, the delay is kept in milliseconds
+    pub fn resize_buffers(&mut self, sample_rate: f32) {
+        let delay_ms = self.delay_target / self.sample_rate * 1000.0;
+        self.sample_rate = sample_rate;
+        // the interpolation reads one sample past the longest delay
+        let len = (self.max_delay() as usize + 2).next_power_of_two();
+        self.history = vec![[0.0; 2]; len];
+        self.position = 0;
+        self.set_delay(delay_ms);
+    }
+
+    /// clears the history without reallocating it
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|x| *x = [0.0; 2]);
+    }
+
+    /// the current delay in milliseconds, ramps included
+    pub fn delay_ms(&self) -> f32 {
+        self.delay / self.sample_rate * 1000.0
+    }
+
+    /// jumps straight to a delay of `delay_ms` milliseconds
+    pub fn set_delay(&mut self, delay_ms: f32) {
+        self.ramp_delay(delay_ms, 0);
+    }
+
+    /// moves the delay to `delay_ms` milliseconds in a straight line over the next `samples`
+    /// samples, limited to `MAX_PRE_DELAY_MS`
+    pub fn ramp_delay(&mut self, delay_ms: f32, samples: usize) {
+        let delay_ms = if delay_ms.is_finite() { delay_ms } else { 0.0 };
+        self.delay_target = (delay_ms / 1000.0 * self.sample_rate).clamp(0.0, self.max_delay());
+        self.ramp_remaining = samples;
+        if samples == 0 {
+            self.delay = self.delay_target;
+        } else {
+            self.delay_step = (self.delay_target - self.delay) / samples as f32;
+        }
+    }
+
+    /// writes one sample of each channel and returns the delayed ones
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.ramp_remaining > 0 {
+            self.ramp_remaining -= 1;
+            // the last step lands exactly on the target, like `CombBank`'s ramps
+            self.delay = if self.ramp_remaining == 0 { self.delay_target } else { self.delay + self.delay_step };
+        }
+
+        let mask = self.history.len() - 1;
+        self.history[self.position] = [left, right];
+
+        let whole = self.delay as usize;
+        let fraction = self.delay - whole as f32;
+        let [left, right] = self.history[self.position.wrapping_sub(whole) & mask];
+        let output = if fraction == 0.0 {
+            (left, right)
+        } else {
+            let [previous_left, previous_right] = self.history[self.position.wrapping_sub(whole + 1) & mask];
+            (left + (previous_left - left) * fraction, right + (previous_right - right) * fraction)
+        };
+
+        self.position = (self.position + 1) & mask;
+        output
+    }
+
+    /// `MAX_PRE_DELAY_MS` in samples
+    fn max_delay(&self) -> f32 {
+        (MAX_PRE_DELAY_MS / 1000.0 * self.sample_rate).ceil()
+    }
+}
@@ -0,0 +1,289 @@
+//! Factory presets and user presets.
+//!
+//! A preset stores every parameter's plain value. The factory presets are compiled into the
+//! plugin, user presets are JSON files in `user_preset_dir()` with the same layout as
+//! `Preset`'s serialization. Fields missing from a file fall back to the parameters' defaults, so
+//! presets written by older versions keep loading when parameters get added.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use nih_plug::nih_error;
+use nih_plug::prelude::util;
+use serde::{Deserialize, Serialize};
+
+use crate::comb::CombType;
+use crate::mix::MixMode;
+use crate::modulation::ModTarget;
+use crate::reverb::ReverbType;
+
+/// the plain value of every parameter. the gains are linear, like the parameters themselves
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetValues {
+    pub reverb_type: ReverbType,
+    pub decay: f32,
+    pub damping: f32,
+    pub comb_type: CombType,
+    pub wet: f32,
+    pub dry: f32,
+    pub enable_chorus: bool,
+    /// in milliseconds
+    pub chorus_depth: f32,
+    /// in milliseconds
+    pub pre_delay: f32,
+    pub mix_mode: MixMode,
+    pub dry_gain: f32,
+    pub wet_gain: f32,
+    pub mix: f32,
+    pub output_gain: f32,
+    pub send_mode: bool,
+    pub lfo1_rate: f32,
+    pub lfo1_target: ModTarget,
+    pub lfo1_depth: f32,
+    pub lfo2_rate: f32,
+    pub lfo2_target: ModTarget,
+    pub lfo2_depth: f32,
+    pub env_release: f32,
+    pub env_target: ModTarget,
+    pub env_depth: f32,
+}
+
+/// the plugin's parameters take their defaults from here
+impl Default for PresetValues {
+    fn default() -> Self {
+        Self {
+            reverb_type: ReverbType::Comb,
+            decay: 250.0,
+            damping: 0.0,
+            comb_type: CombType::Positive,
+            wet: 0.25,
+            dry: 1.0,
+            enable_chorus: false,
+            chorus_depth: 0.0,
+            pre_delay: 0.0,
+            mix_mode: MixMode::Legacy,
+            dry_gain: 1.0,
+            wet_gain: util::db_to_gain(-12.0),
+            mix: 0.25,
+            output_gain: 1.0,
+            send_mode: false,
+            lfo1_rate: 0.2,
+            lfo1_target: ModTarget::None,
+            lfo1_depth: 0.0,
+            lfo2_rate: 0.07,
+            lfo2_target: ModTarget::None,
+            lfo2_depth: 0.0,
+            env_release: 300.0,
+            env_target: ModTarget::None,
+            env_depth: 0.0,
+        }
+    }
+}
+
+impl PresetValues {
+    /// equal, give or take the rounding that comes from setting the parameters through their
+    /// normalized values
+    pub fn approx_eq(&self, other: &Self) -> bool {
+        let close = |a: f32, b: f32| (a - b).abs() <= 1e-4 * a.abs().max(b.abs()).max(1.0);
+
+        self.reverb_type == other.reverb_type
+            && close(self.decay, other.decay)
+            && close(self.damping, other.damping)
+            && self.comb_type == other.comb_type
+            && close(self.wet, other.wet)
+            && close(self.dry, other.dry)
+            && self.enable_chorus == other.enable_chorus
+            && close(self.chorus_depth, other.chorus_depth)
+            && close(self.pre_delay, other.pre_delay)
+            && self.mix_mode == other.mix_mode
+            && close(self.dry_gain, other.dry_gain)
+            && close(self.wet_gain, other.wet_gain)
+            && close(self.mix, other.mix)
+            && close(self.output_gain, other.output_gain)
+            && self.send_mode == other.send_mode
+            && close(self.lfo1_rate, other.lfo1_rate)
+            && self.lfo1_target == other.lfo1_target
+            && close(self.lfo1_depth, other.lfo1_depth)
+            && close(self.lfo2_rate, other.lfo2_rate)
+            && self.lfo2_target == other.lfo2_target
+            && close(self.lfo2_depth, other.lfo2_depth)
+            && close(self.env_release, other.env_release)
+            && self.env_target == other.env_target
+            && close(self.env_depth, other.env_depth)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    #[serde(flatten)]
+    pub values: PresetValues,
+}
+
+impl Preset {
+    pub fn new(name: impl Into<String>, values: PresetValues) -> Self {
+        Self {
+            name: name.into(),
+            values,
+        }
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+/// the presets that ship with the plugin, the first one is the plugin's defaults
+pub fn factory_presets() -> Vec<Preset> {
+    vec![
+        Preset::new("Default", PresetValues::default()),
+        Preset::new("Small Room", PresetValues {
+            reverb_type: ReverbType::Moorer,
+            decay: 400.0,
+            damping: 0.5,
+            wet: 0.2,
+            ..Default::default()
+        }),
+        Preset::new("Vocal Plate", PresetValues {
+            reverb_type: ReverbType::LpfComb,
+            decay: 1500.0,
+            damping: 0.2,
+            enable_chorus: true,
+            wet: 0.3,
+            ..Default::default()
+        }),
+        Preset::new("Dark Chamber", PresetValues {
+            reverb_type: ReverbType::LpfComb,
+            decay: 1800.0,
+            damping: 0.75,
+            wet: 0.3,
+            ..Default::default()
+        }),
+        Preset::new("Large Hall", PresetValues {
+            reverb_type: ReverbType::Moorer,
+            decay: 3500.0,
+            damping: 0.35,
+            enable_chorus: true,
+            mix_mode: MixMode::EqualPower,
+            mix: 0.35,
+            ..Default::default()
+        }),
+        // freeze isn't part of presets, this is as long as the decay goes
+        Preset::new("Ambient Freeze", PresetValues {
+            reverb_type: ReverbType::Moorer,
+            decay: 20000.0,
+            damping: 0.1,
+            enable_chorus: true,
+            mix_mode: MixMode::EqualPower,
+            mix: 0.6,
+            ..Default::default()
+        }),
+        Preset::new("Metallic Comb", PresetValues {
+            reverb_type: ReverbType::Comb,
+            decay: 800.0,
+            comb_type: CombType::Negative,
+            wet: 0.3,
+            ..Default::default()
+        }),
+        // for an aux bus, the wet gain is the return level
+        Preset::new("Hall Send", PresetValues {
+            reverb_type: ReverbType::Moorer,
+            decay: 2500.0,
+            damping: 0.3,
+            enable_chorus: true,
+            wet_gain: 1.0,
+            send_mode: true,
+            ..Default::default()
+        }),
+        // two slow LFOs on the decay and the damping, the envelope ducks the wet level while the
+        // input is loud
+        Preset::new("Breathing Hall", PresetValues {
+            reverb_type: ReverbType::Moorer,
+            decay: 2800.0,
+            damping: 0.4,
+            enable_chorus: true,
+            wet: 0.35,
+            lfo1_rate: 0.11,
+            lfo1_target: ModTarget::Decay,
+            lfo1_depth: 0.15,
+            lfo2_rate: 0.05,
+            lfo2_target: ModTarget::Damping,
+            lfo2_depth: 0.25,
+            env_release: 600.0,
+            env_target: ModTarget::Wet,
+            env_depth: -0.25,
+            ..Default::default()
+        }),
+    ]
+}
+
+/// where user presets are saved, usually `~/.local/share/tsk reverb/presets` or the platform's
+/// equivalent
+pub fn user_preset_dir() -> PathBuf {
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("tsk reverb")
+        .join("presets")
+}
+
+/// the preset's name with everything that doesn't belong in a file name replaced
+pub fn preset_file_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        String::from("preset.json")
+    } else {
+        format!("{}.json", name)
+    }
+}
+
+pub fn load_preset(path: &Path) -> io::Result<Preset> {
+    let json = std::fs::read_to_string(path)?;
+    Ok(Preset::from_json(&json)?)
+}
+
+/// every preset in `dir`, sorted by name. files that can't be read are logged and skipped, a
+/// missing directory just means there are no user presets yet
+pub fn load_user_presets(dir: &Path) -> Vec<Preset> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut presets: Vec<Preset> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| match load_preset(&path) {
+            Ok(preset) => Some(preset),
+            Err(err) => {
+                nih_error!("Could not load the preset '{}': {}", path.display(), err);
+                None
+            }
+        })
+        .collect();
+    presets.sort_by_key(|preset| preset.name.to_lowercase());
+    presets
+}
+
+/// saves `preset` to `dir`, overwriting a preset with the same name. returns the file's path.
+/// different names can end up with the same file name, e.g. "Hall/1" and "Hall_1", so if the file
+/// holds another preset (or isn't a preset at all) a number is added instead, "Hall_1 2.json"
+pub fn save_user_preset(dir: &Path, preset: &Preset) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let file_name = preset_file_name(&preset.name);
+    let stem = file_name.trim_end_matches(".json");
+    let path = (1..)
+        .map(|n| if n == 1 { dir.join(&file_name) } else { dir.join(format!("{} {}.json", stem, n)) })
+        .find(|path| !path.exists() || load_preset(path).is_ok_and(|saved| saved.name == preset.name))
+        .unwrap();
+    std::fs::write(&path, preset.to_json())?;
+    Ok(path)
+}
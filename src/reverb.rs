@@ -1,10 +1,12 @@
 use nih_plug::prelude::Enum;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-use crate::{delayingallpass::DelayingAllPass, comb::{CombFilter, CombType}, chorus::Chorus};
+use crate::{delayingallpass::DelayingAllPass, comb::CombType, combbank::{CombBank, LANES, MAX_CHANNEL_COMBS}, chorus::Chorus, denormal, predelay::PreDelay, stability};
 
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ReverbType {
     Comb,
     Schroeder,
@@ -41,189 +43,233 @@ impl Enum for ReverbType {
     } 
 }
 
+impl ReverbType {
+    /// the comb delays, allpasses and comb mix this type is built with. the low-pass comb and
+    /// Moorer reverbs are the comb and Schroeder ones with low-passed combs, which is how the
+    /// plugin has always built them
+    fn layout(self) -> ReverbType {
+        match self {
+            ReverbType::Comb | ReverbType::LpfComb => ReverbType::Comb,
+            ReverbType::Schroeder | ReverbType::Moorer => ReverbType::Schroeder,
+        }
+    }
+}
+
+/// everything needed to rebuild a `Reverb` that sounds exactly like a given plugin instance.
+/// the seed drives the random delay offsets and the chorus LFO phases.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReverbSettings {
+    pub reverb_type: ReverbType,
+    pub decay: f32,
+    pub damping: f32,
+    pub comb_type: CombType,
+    pub enable_chorus: bool,
+    /// how far the chorus LFOs move their delays in milliseconds. 0 leaves the delays fixed
+    pub chorus_depth: f32,
+    /// in milliseconds, up to `predelay::MAX_PRE_DELAY_MS`
+    pub pre_delay: f32,
+    pub seed: u64,
+}
+
+/// same defaults as the plugin's parameters
+impl Default for ReverbSettings {
+    fn default() -> Self {
+        Self {
+            reverb_type: ReverbType::Comb,
+            decay: 250.0,
+            damping: 0.0,
+            comb_type: CombType::Positive,
+            enable_chorus: false,
+            chorus_depth: 0.0,
+            pre_delay: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+/// the parameters for one `Reverb::process_block()` call
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockParams {
+    /// decay time in milliseconds. the comb gains ramp from their previous values to the ones for
+    /// this decay over the block
+    pub decay: f32,
+    /// ramped the same way as `decay`
+    pub damping: f32,
+    pub comb_type: CombType,
+    pub enable_chorus: bool,
+    /// not ramped, the chorus rounds its delays to whole samples anyway
+    pub chorus_depth: f32,
+    /// in milliseconds, ramped over the block like `decay`
+    pub pre_delay: f32,
+    /// keeps the tail ringing and stops feeding the combs, see `Reverb::set_freeze()`. the input
+    /// fades out (or back in) over the block
+    pub freeze: bool,
+}
+
+impl ReverbSettings {
+    pub fn block_params(&self) -> BlockParams {
+        BlockParams {
+            decay: self.decay,
+            damping: self.damping,
+            comb_type: self.comb_type,
+            enable_chorus: self.enable_chorus,
+            chorus_depth: self.chorus_depth,
+            pre_delay: self.pre_delay,
+            freeze: false,
+        }
+    }
+}
+
+/// the decay gains of every comb for the last decay time, so the `powf`s only run when the decay
+/// actually changes
+#[derive(Clone)]
+struct CombGains {
+    decay: f32,
+    gains: Vec<f32>,
+}
+
+impl CombGains {
+    fn new() -> Self {
+        Self {
+            decay: f32::NAN,
+            gains: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, combs: &CombBank, decay: f32) -> &[f32] {
+        // NaN never compares equal, so a NaN decay doesn't get stuck in here
+        if decay != self.decay || self.gains.len() != combs.len() {
+            self.decay = decay;
+            self.gains.clear();
+            self.gains.extend((0..combs.len()).map(|comb| comb_gain(combs.get_delay_ms(comb), decay)));
+        }
+        &self.gains
+    }
+}
+
+/// the feedback gain that makes a comb with this delay fall by 60 dB in `decay` milliseconds
+fn comb_gain(delay_ms: f32, decay: f32) -> f32 {
+    let power = -(3.0 * delay_ms / 1000.0) / (decay / 1000.0);
+
+    10f32.powf(power)
+}
+
 #[derive(Clone)]
 pub struct Reverb {
-    left_combs: Vec<CombFilter>,
-    right_combs: Vec<CombFilter>,
+    pre_delay: PreDelay,
+    combs: CombBank,
+    comb_gains: CombGains,
     left_allpasses: Vec<DelayingAllPass>,
     right_allpasses: Vec<DelayingAllPass>,
     decay: f32,
+    damping: f32,
+    comb_type: CombType,
     reverb_type: ReverbType,
     sample_rate: f32,
     enable_chorus: bool,
     choruses: Vec<Chorus>,
+    chorus_depth: f32,
+    freeze: bool,
+    /// scales the input to the combs, 0.0 while frozen
+    input_gain: f32,
 }
 
 impl Reverb {
     pub fn new(sample_rate: f32, decay: f32, reverb_type: ReverbType, damp: f32) -> Self {
-        let mut left_combs = Vec::new();
+        Self::new_with_seed(sample_rate, decay, reverb_type, damp, rand::random())
+    }
+
+    /// builds a reverb from `settings` and applies its parameters right away
+    pub fn from_settings(sample_rate: f32, settings: &ReverbSettings) -> Self {
+        let mut reverb = Self::new_with_seed(
+            sample_rate,
+            settings.decay,
+            settings.reverb_type,
+            settings.damping,
+            settings.seed,
+        );
+        reverb.set_params(settings.decay, settings.damping, settings.comb_type, settings.enable_chorus);
+        reverb.set_chorus_depth(settings.chorus_depth);
+        reverb.set_pre_delay(settings.pre_delay);
+        reverb
+    }
+
+    /// same as `new`, but the random delay offsets and chorus phases are taken from `seed`
+    /// so two reverbs with the same seed produce identical output
+    pub fn new_with_seed(sample_rate: f32, decay: f32, reverb_type: ReverbType, damp: f32, seed: u64) -> Self {
+        let mut left_delays_ms = Vec::new();
+        let mut right_delays_ms = Vec::new();
         let mut left_allpasses = Vec::new();
-        let mut right_combs = Vec::new();
         let mut right_allpasses = Vec::new();
 
-        let mut rng = rand::thread_rng();
+        let mut rng = StdRng::seed_from_u64(seed);
 
         let mut choruses: Vec<Chorus> = Vec::new();
         for _ in 0..6 {
-            choruses.push(Chorus::new(sample_rate, 25.0, 0.5, 10.0, 0.25, 1.0, 0.2));
+            choruses.push(Chorus::new(sample_rate, 25.0, 0.5, 10.0, 0.25, 1.0, 0.2, &mut rng));
         }
 
-        match reverb_type {
+        // the comb gains are set by `set_params` below
+        match reverb_type.layout() {
             ReverbType::Comb => {
                 let delays_ms = [21.0, 26.0, 31.0, 37.0];
-                for i in 0..4 {
-                    // random delay between 3 and 50 ms
-                    let ldelay_ms = delays_ms[i] + rng.gen_range(-0.5..0.5);
-                    let ldelay_seconds = ldelay_ms / 1000.0;
-
-                    let power = -(3.0 * ldelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    left_combs.push(CombFilter::new(sample_rate, comb_type, ldelay_ms, g, false));
-                    left_combs[i].set_damp(0.0);
-
-                    let rdelay_ms = delays_ms[i] + rng.gen_range(-0.5..0.5);
-                    let rdelay_seconds = rdelay_ms / 1000.0;
-
-                    let power = -(3.0 * rdelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    right_combs.push(CombFilter::new(sample_rate, comb_type, rdelay_ms, g, false));
-                    right_combs[i].set_damp(0.0);
+                for delay_ms in delays_ms {
+                    // random offset of up to half a millisecond
+                    left_delays_ms.push(delay_ms + rng.gen_range(-0.5..0.5));
+                    right_delays_ms.push(delay_ms + rng.gen_range(-0.5..0.5));
                 }
             },
-            ReverbType::Schroeder => {
+            _ => {
                 let mut ldelay_ms = 15.02;
                 let mut rdelay_ms = 15.01;
-                for i in 0..4 {
-                    ldelay_ms = ldelay_ms * 1.5;
-                    let ldelay_seconds = ldelay_ms / 1000.0;
-                    let ldelay_samples = (ldelay_seconds * sample_rate).floor() as usize;
-
-                    let power = -(3.0 * ldelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    left_combs.push(CombFilter::new(sample_rate, comb_type, ldelay_ms, g, false));
-                    left_combs[i].set_damp(damp);
-
-                    rdelay_ms = rdelay_ms * 1.5;
-                    let rdelay_seconds = rdelay_ms / 1000.0;
-                    let rdelay_samples = (rdelay_seconds * sample_rate).floor() as usize;
-
-                    let power = -(3.0 * rdelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    right_combs.push(CombFilter::new(sample_rate, comb_type, rdelay_ms, g, false));
-                    right_combs[i].set_damp(damp);
-                }
-
                 for _ in 0..4 {
-                    // random delay between 1 and 5 ms
-                    let delay = rng.gen_range(1.0..5.0);
-                    left_allpasses.push(DelayingAllPass::new(sample_rate, delay, 0.707));
-
-                    let delay = rng.gen_range(1.0..5.0);
-                    right_allpasses.push(DelayingAllPass::new(sample_rate, delay, 0.707));
+                    ldelay_ms *= 1.5;
+                    rdelay_ms *= 1.5;
+                    left_delays_ms.push(ldelay_ms);
+                    right_delays_ms.push(rdelay_ms);
                 }
             },
-            ReverbType::LpfComb => {
-                let mut ldelay_ms = 15.02;
-                let mut rdelay_ms = 15.01;
-                for i in 0..6 {
-                    ldelay_ms = ldelay_ms * 1.5;
-                    let ldelay_seconds = ldelay_ms / 1000.0;
-                    let ldelay_samples = (ldelay_seconds * sample_rate).floor() as usize;
-
-                    let power = -(3.0 * ldelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    left_combs.push(CombFilter::new(sample_rate, comb_type, ldelay_ms, g, false));
-                    left_combs[i].set_damp(damp);
-
-                    rdelay_ms = rdelay_ms * 1.5;
-                    let rdelay_seconds = rdelay_ms / 1000.0;
-                    let rdelay_samples = (rdelay_seconds * sample_rate).floor() as usize;
-
-                    let power = -(3.0 * rdelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    right_combs.push(CombFilter::new(sample_rate, comb_type, rdelay_ms, g, false));
-                    right_combs[i].set_damp(damp);
-                }
-            },
-            ReverbType::Moorer => {
-                let mut ldelay_ms = 15.02;
-                let mut rdelay_ms = 15.01;
-                for i in 0..6 {
-                    ldelay_ms = ldelay_ms * 1.5;
-                    let ldelay_seconds = ldelay_ms / 1000.0;
-                    let ldelay_samples = (ldelay_seconds * sample_rate).floor() as usize;
-
-                    let power = -(3.0 * ldelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
-
-                    let comb_type = CombType::Positive;
-                    left_combs.push(CombFilter::new(sample_rate, comb_type, ldelay_ms, g, false));
-                    left_combs[i].set_damp(damp);
-
-                    rdelay_ms = rdelay_ms * 1.5;
-                    let rdelay_seconds = rdelay_ms / 1000.0;
-                    let rdelay_samples = (rdelay_seconds * sample_rate).floor() as usize;
-
-                    let power = -(3.0 * rdelay_seconds as f32) / (decay) ;
-
-                    let g = 10f32.powf(power);
+        }
 
-                    let comb_type = CombType::Positive;
-                    right_combs.push(CombFilter::new(sample_rate, comb_type, rdelay_ms, g, false));
-                    right_combs[i].set_damp(damp);
-                }
+        if reverb_type.layout() == ReverbType::Schroeder {
+            for _ in 0..4 {
+                // random delay between 1 and 5 ms
+                let delay = rng.gen_range(1.0..5.0);
+                left_allpasses.push(DelayingAllPass::new(sample_rate, delay, 0.707));
 
-                for _ in 0..4 {
-                    // random delay between 1 and 5 ms
-                    let delay = rng.gen_range(1.0..5.0);
-                    left_allpasses.push(DelayingAllPass::new(sample_rate, delay, 0.707));
-
-                    let delay = rng.gen_range(1.0..5.0);
-                    right_allpasses.push(DelayingAllPass::new(sample_rate, delay, 0.707));
-                }
-            },
+                let delay = rng.gen_range(1.0..5.0);
+                right_allpasses.push(DelayingAllPass::new(sample_rate, delay, 0.707));
+            }
         }
 
-        Self {
-            left_combs,
-            right_combs,
+        let mut reverb = Self {
+            pre_delay: PreDelay::new(sample_rate),
+            combs: CombBank::new(sample_rate, &left_delays_ms, &right_delays_ms),
+            comb_gains: CombGains::new(),
             left_allpasses,
             right_allpasses,
             decay,
+            damping: damp,
+            comb_type: CombType::Positive,
             reverb_type,
             sample_rate,
             enable_chorus: false,
             choruses,
-        }
+            chorus_depth: 0.0,
+            freeze: false,
+            input_gain: 1.0,
+        };
+        // `process_block()` only updates the coefficients when a parameter changes, so they have to
+        // be valid from the start
+        reverb.set_params(decay, damp, CombType::Positive, false);
+        reverb
     }
 
     pub fn resize_buffers(&mut self, sample_rate: f32) {
         self.sample_rate = sample_rate;
-        for comb in self.left_combs.iter_mut() {
-            comb.resize_buffers(sample_rate);
-        }
-        for comb in self.right_combs.iter_mut() {
-            comb.resize_buffers(sample_rate);
-        }
+        self.pre_delay.resize_buffers(sample_rate);
+        self.combs.resize_buffers(sample_rate);
         for allpass in self.left_allpasses.iter_mut() {
             allpass.resize_buffers(sample_rate);
         }
@@ -236,225 +282,215 @@ impl Reverb {
         }
     }
 
-    pub fn set_params_comb(&mut self, decay: f32, comb_type: CombType, enable_chorus: bool) {
-        self.decay = decay;
-        for comb in self.left_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
+    pub fn reverb_type(&self) -> ReverbType {
+        self.reverb_type
+    }
 
-            let g = 10f32.powf(power);
+    pub fn combs(&self) -> &CombBank {
+        &self.combs
+    }
 
-            comb.set_params(  g, false, 0.0, comb_type)
-        }
-        for comb in self.right_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
+    /// the largest worst-case loop gain of all feedback paths with the current parameters. this
+    /// is always below 1.0, see the `stability` module
+    pub fn loop_gain(&self) -> f32 {
+        let combs = std::iter::once(self.combs.loop_gain());
+        let allpasses = self.left_allpasses.iter().chain(self.right_allpasses.iter()).map(|allpass| allpass.loop_gain());
+        let choruses = self.choruses.iter().map(|chorus| chorus.loop_gain());
 
-            let g = 10f32.powf(power);
+        combs.chain(allpasses).chain(choruses).fold(0.0, f32::max)
+    }
 
-            comb.set_params( g, false, 0.0, comb_type)
+    /// clears all delay lines without reallocating them
+    pub fn reset(&mut self) {
+        self.pre_delay.reset();
+        self.combs.reset();
+        for allpass in self.left_allpasses.iter_mut().chain(self.right_allpasses.iter_mut()) {
+            allpass.reset();
+        }
+        for chorus in self.choruses.iter_mut() {
+            chorus.reset();
         }
-        self.enable_chorus = enable_chorus;
     }
 
-    pub fn set_params_schroeder(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool) {
-        self.decay = decay;
-        for comb in self.left_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
-
-            let g = 10f32.powf(power);
+    /// calls the `set_params_*` function matching this reverb's type
+    pub fn set_params(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool) {
+        self.ramp_params(decay, damp, comb_type, enable_chorus, 0);
+    }
 
-            comb.set_params(g, false, damp, comb_type)
+    /// `set_params()`, with the comb gains ramping to their new values over `ramp_samples` samples
+    fn ramp_params(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool, ramp_samples: usize) {
+        match self.reverb_type {
+            ReverbType::Comb => self.update_combs(decay, 0.0, false, comb_type, enable_chorus, ramp_samples),
+            ReverbType::Schroeder => self.update_combs(decay, damp, false, comb_type, enable_chorus, ramp_samples),
+            ReverbType::LpfComb | ReverbType::Moorer => {
+                self.update_combs(decay, damp, true, comb_type, enable_chorus, ramp_samples)
+            }
         }
-        for comb in self.right_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
+        // the comb topology doesn't use the damping, but `process_block()` compares against it
+        self.damping = damp;
+    }
 
-            let g = 10f32.powf(power);
+    pub fn set_params_comb(&mut self, decay: f32, comb_type: CombType, enable_chorus: bool) {
+        self.update_combs(decay, 0.0, false, comb_type, enable_chorus, 0);
+    }
 
-            comb.set_params( g, false, damp, comb_type)
-        }
-        self.enable_chorus = enable_chorus;
+    pub fn set_params_schroeder(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool) {
+        self.update_combs(decay, damp, false, comb_type, enable_chorus, 0);
     }
 
     pub fn set_params_lpfcomb(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool) {
-        self.decay = decay;
-        for comb in self.left_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
-
-            let g = 10f32.powf(power);
-
-            let damp = damp.clamp(0.0, 0.9999);
+        self.update_combs(decay, damp, true, comb_type, enable_chorus, 0);
+    }
 
-            let new_g = g * (1.0 - damp);
+    pub fn set_params_moorer(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool) {
+        self.update_combs(decay, damp, true, comb_type, enable_chorus, 0);
+    }
 
-            comb.set_params( new_g, true, damp, comb_type)
+    /// sets the comb coefficients for all `set_params_*` functions, ramping the gains over
+    /// `ramp_samples` samples
+    fn update_combs(&mut self, decay: f32, damp: f32, use_lpf: bool, comb_type: CombType, enable_chorus: bool, ramp_samples: usize) {
+        self.decay = decay;
+        self.damping = damp;
+        let gains = self.comb_gains.update(&self.combs, decay);
+        for (comb, g) in gains.iter().enumerate() {
+            if self.freeze {
+                // as long as the loops can ring without growing, and without damping so the tail
+                // keeps its tone
+                self.combs.ramp_params(comb, stability::MAX_LOOP_GAIN, use_lpf, 0.0, comb_type, ramp_samples)
+            } else if use_lpf {
+                let damp = damp.clamp(0.0, 0.9999);
+
+                let new_g = g * (1.0 - damp);
+
+                self.combs.ramp_params(comb, new_g, true, damp, comb_type, ramp_samples)
+            } else {
+                self.combs.ramp_params(comb, *g, false, damp, comb_type, ramp_samples)
+            }
         }
-        for comb in self.right_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
-
-            let g = 10f32.powf(power);
-
-            let damp = damp.clamp(0.0, 0.9999);
-
-            let new_g = g * (1.0 - damp);
+        self.comb_type = comb_type;
+        self.enable_chorus = enable_chorus;
+    }
 
-            comb.set_params( new_g, true, damp, comb_type)
+    /// sets every chorus' depth in milliseconds
+    pub fn set_chorus_depth(&mut self, depth: f32) {
+        if depth != self.chorus_depth {
+            self.chorus_depth = depth;
+            for chorus in self.choruses.iter_mut() {
+                chorus.set_depth(depth);
+            }
         }
-        self.enable_chorus = enable_chorus;
     }
 
-    pub fn set_params_moorer(&mut self, decay: f32, damp: f32, comb_type: CombType, enable_chorus: bool) {
-        self.decay = decay;
-        for comb in self.left_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
+    /// sets the pre-delay in milliseconds right away, `process_block()` ramps it instead
+    pub fn set_pre_delay(&mut self, pre_delay_ms: f32) {
+        self.pre_delay.set_delay(pre_delay_ms);
+    }
 
-            let g = 10f32.powf(power);
+    pub fn pre_delay(&self) -> &PreDelay {
+        &self.pre_delay
+    }
 
-            let damp = damp.clamp(0.0, 0.9999);
+    /// freezes the reverb: the combs' feedback goes up to `stability::MAX_LOOP_GAIN` and they don't
+    /// get any new input, so the current tail keeps ringing for minutes. unfreezing goes back to
+    /// the decay and damping. both happen right away, `process_block()` ramps them instead
+    pub fn set_freeze(&mut self, freeze: bool) {
+        self.freeze = freeze;
+        self.input_gain = if freeze { 0.0 } else { 1.0 };
+        self.set_params(self.decay, self.damping, self.comb_type, self.enable_chorus);
+    }
 
-            let new_g = g * (1.0 - damp);
+    /// switches the comb bank between its SIMD and scalar implementations, see
+    /// `CombBank::set_use_simd()`
+    pub fn set_use_simd(&mut self, use_simd: bool) {
+        self.combs.set_use_simd(use_simd);
+    }
 
-            comb.set_params( new_g, true, damp, comb_type)
+    /// processes both channels in place. when `params` differ from the previous block the comb
+    /// gains ramp to their new values over the block, so changing the decay doesn't click and
+    /// doesn't cost a `powf` per sample
+    pub fn process_block(&mut self, left: &mut [f32], right: &mut [f32], params: &BlockParams) {
+        debug_assert_eq!(left.len(), right.len());
+        let len = left.len().min(right.len());
+
+        if params.decay != self.decay
+            || params.damping != self.damping
+            || params.comb_type != self.comb_type
+            || params.enable_chorus != self.enable_chorus
+            || params.freeze != self.freeze
+        {
+            self.freeze = params.freeze;
+            self.ramp_params(params.decay, params.damping, params.comb_type, params.enable_chorus, len);
         }
-        for comb in self.right_combs.iter_mut() {
-            let power = -(3.0 * comb.get_delay_ms() / 1000.0 ) / (decay / 1000.0) ;
-
-            let g = 10f32.powf(power);
-
-            let damp = damp.clamp(0.0, 0.9999);
-
-            let new_g = g * (1.0 - damp);
-
-            comb.set_params( new_g, true, damp,comb_type)
+        self.set_chorus_depth(params.chorus_depth);
+        self.pre_delay.ramp_delay(params.pre_delay, len);
+
+        let input_target = if self.freeze { 0.0 } else { 1.0 };
+        let input_step = (input_target - self.input_gain) / len as f32;
+        for (i, (left, right)) in left[..len].iter_mut().zip(right[..len].iter_mut()).enumerate() {
+            if self.input_gain != input_target {
+                self.input_gain = if i + 1 == len { input_target } else { self.input_gain + input_step };
+            }
+            (*left, *right) = self.process(*left, *right);
         }
-        self.enable_chorus = enable_chorus;
     }
 
-    pub fn process_left(&mut self, x: f32) -> f32 {
+    /// sums the outputs of one channel's combs the way each topology does it
+    fn mix_combs(&self, outputs: &[f32]) -> f32 {
         let mut y = 0.0;
-        match self.reverb_type {
+        match self.reverb_type.layout() {
             ReverbType::Comb => {
-                for (i, comb) in self.left_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_left(x);
-                    }
-                    y += comb.process_left(chorused_x);
-                }
-                y *= 0.15;
-            },
-            ReverbType::Schroeder => {
-                for (i, comb) in self.left_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_left(x);
-                    }
-                    if i % 2 == 0 {
-                        y += comb.process_left(chorused_x);
-                    } else {
-                        y -= comb.process_left(chorused_x);
-                    }
-                }
-                y *= 0.15;
-                for allpass in self.left_allpasses.iter_mut() {
-                    y = allpass.process_left(y);
-                }
-            },
-            ReverbType::LpfComb => {
-                for (i, comb) in self.left_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_left(x);
-                    }
-                    if i % 2 == 0 {
-                        y += comb.process_left(chorused_x);
-                    } else {
-                        y -= comb.process_left(chorused_x);
-                    }
+                for output in outputs {
+                    y += output;
                 }
-                y /= 10.0;
             },
-            ReverbType::Moorer => {
-                for (i, comb) in self.left_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_left(x);
-                    }
+            _ => {
+                for (i, output) in outputs.iter().enumerate() {
                     if i % 2 == 0 {
-                        y += comb.process_left(chorused_x);
+                        y += output;
                     } else {
-                        y -= comb.process_left(chorused_x);
+                        y -= output;
                     }
                 }
-                y /= 10.0;
-                for allpass in self.left_allpasses.iter_mut() {
-                    y = allpass.process_left(y);
-                }
             },
         }
+        y *= 0.15;
         y
     }
 
-    pub fn process_right(&mut self, x: f32) -> f32 {
-        let mut y = 0.0;
-        match self.reverb_type {
-            ReverbType::Comb => {
-                for (i, comb) in self.right_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_right(x);
-                    }
-                    y += comb.process_right(chorused_x);
-                }
-                y *= 0.15;
-            },
-            ReverbType::Schroeder => {
-                for (i, comb) in self.right_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_right(x);
-                    }
-                    if i % 2 == 0 {
-                        y += comb.process_right(chorused_x);
-                    } else {
-                        y -= comb.process_right(chorused_x);
-                    }
-                }
-                y *= 0.15;
-                for allpass in self.right_allpasses.iter_mut() {
-                    y = allpass.process_right(y);
-                }
-            },
-            ReverbType::LpfComb => {
-                for (i, comb) in self.right_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_right(x);
-                    }
-                    if i % 2 == 0 {
-                        y += comb.process_right(chorused_x);
-                    } else {
-                        y -= comb.process_right(chorused_x);
-                    }
-                }
-                y /= 10.0;
-            },
-            ReverbType::Moorer => {
-                for (i, comb) in self.right_combs.iter_mut().enumerate() {
-                    let mut chorused_x = x;
-                    if self.enable_chorus {
-                        chorused_x = self.choruses[i].process_right(x);
-                    }
-                    if i % 2 == 0 {
-                        y += comb.process_right(chorused_x);
-                    } else {
-                        y -= comb.process_right(chorused_x);
-                    }
-                }
-                y /= 10.0;
-                for allpass in self.right_allpasses.iter_mut() {
-                    y = allpass.process_right(y);
-                }
-            },
+    /// runs one sample of each channel through the reverb and returns the wet outputs
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let left = denormal::flush(stability::finite_or_zero(left));
+        let right = denormal::flush(stability::finite_or_zero(right));
+        let (left, right) = self.pre_delay.process(left, right);
+        let (left, right) = (left * self.input_gain, right * self.input_gain);
+        let combs = self.combs.channel_combs();
+
+        // both channels' combs run together, see `CombBank`
+        let mut inputs = [0.0; LANES];
+        for i in 0..combs {
+            (inputs[i], inputs[MAX_CHANNEL_COMBS + i]) = if self.enable_chorus {
+                (self.choruses[i].process_left(left), self.choruses[i].process_right(right))
+            } else {
+                (left, right)
+            };
         }
-        y
+        let outputs = self.combs.process(&inputs);
+
+        let mut y_left = self.mix_combs(&outputs[..combs]);
+        for allpass in self.left_allpasses.iter_mut() {
+            y_left = allpass.process_left(y_left);
+        }
+        let mut y_right = self.mix_combs(&outputs[MAX_CHANNEL_COMBS..][..combs]);
+        for allpass in self.right_allpasses.iter_mut() {
+            y_right = allpass.process_right(y_right);
+        }
+
+        // the loops can't blow up on their own, but if something still goes wrong we'd rather
+        // start over than keep producing garbage
+        if !y_left.is_finite() || !y_right.is_finite() {
+            self.reset();
+            return (0.0, 0.0);
+        }
+        (y_left, y_right)
     }
-}
\ No newline at end of file
+}
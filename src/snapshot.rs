@@ -0,0 +1,69 @@
+//! A/B comparison slots.
+//!
+//! The parameters always hold the active slot's values. The other slots keep the values they had
+//! when they were last active, and the active slot keeps the values it started out with, so the
+//! editor can show when it has been modified since. Slots that were never used start out as a
+//! copy of whatever was active.
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::preset::PresetValues;
+
+pub const SLOT_NAMES: [&str; 2] = ["A", "B"];
+pub const SLOTS: usize = SLOT_NAMES.len();
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snapshots {
+    /// index into `slots`
+    #[serde(deserialize_with = "deserialize_slot")]
+    pub active: usize,
+    pub slots: [Option<PresetValues>; SLOTS],
+}
+
+/// a saved index past the last slot can only come from a corrupt state. it falls back to the first
+/// slot instead of panicking the first time it's used
+fn deserialize_slot<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let slot = usize::deserialize(deserializer)?;
+    Ok(if slot < SLOTS { slot } else { 0 })
+}
+
+impl Snapshots {
+    /// makes `slot` the active one. `current` are the parameters' values, they're kept for the
+    /// slot that was active. returns the values the parameters need to be set to, or `None` when
+    /// `slot` is already active
+    pub fn switch(&mut self, slot: usize, current: PresetValues) -> Option<PresetValues> {
+        if slot == self.active {
+            return None;
+        }
+
+        self.slots[self.active] = Some(current);
+        let values = *self.slots[slot].get_or_insert(current);
+        self.active = slot;
+        Some(values)
+    }
+
+    /// copies slot `from` to slot `to`. returns the values the parameters need to be set to when
+    /// `to` is the active slot
+    pub fn copy(&mut self, from: usize, to: usize, current: PresetValues) -> Option<PresetValues> {
+        let values = if from == self.active {
+            current
+        } else {
+            self.slots[from].unwrap_or(current)
+        };
+        self.slots[to] = Some(values);
+
+        (to == self.active).then_some(values)
+    }
+
+    /// whether the parameters changed since the active slot got switched to or copied into
+    pub fn is_modified(&self, current: &PresetValues) -> bool {
+        self.slots[self.active].is_some_and(|values| !values.approx_eq(current))
+    }
+
+    /// starts tracking changes to the active slot from `current`, for when there's nothing to
+    /// compare against yet
+    pub fn init(&mut self, current: PresetValues) {
+        self.slots[self.active].get_or_insert(current);
+    }
+}
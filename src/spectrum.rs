@@ -0,0 +1,224 @@
+//! The editor's spectrum analyser.
+//!
+//! The audio thread writes the dry input and the wet signal into two `SampleRing`s, without locks
+//! or allocations. Whenever the editor draws, an `Analyser` per signal takes the newest samples,
+//! runs a windowed FFT over them and averages the result with the previous frames.
+
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use nih_plug::prelude::Enum;
+use realfft::num_complex::Complex;
+use realfft::{RealFftPlanner, RealToComplex};
+
+pub const FFT_SIZES: [usize; 4] = [1024, 2048, 4096, 8192];
+/// how many samples the rings keep, twice the largest FFT so the audio thread has some room
+/// before it overwrites what the editor is reading
+pub const RING_CAPACITY: usize = 16384;
+/// the spectrum bottoms out here
+pub const SPECTRUM_FLOOR_DB: f32 = -120.0;
+
+/// the newest `RING_CAPACITY` samples of a mono signal. there is exactly one writer, the audio
+/// thread, and any number of readers. a reader that's too slow may see a few samples that got
+/// overwritten while it was reading, which doesn't matter for an analyser
+pub struct SampleRing {
+    samples: Box<[AtomicU32]>,
+    /// how many samples have been written in total
+    written: AtomicUsize,
+}
+
+impl Default for SampleRing {
+    fn default() -> Self {
+        Self {
+            samples: (0..RING_CAPACITY).map(|_| AtomicU32::new(0.0f32.to_bits())).collect(),
+            written: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl SampleRing {
+    /// only ever called from the audio thread
+    pub fn push(&self, x: f32) {
+        let written = self.written.load(Ordering::Relaxed);
+        self.samples[written % RING_CAPACITY].store(x.to_bits(), Ordering::Relaxed);
+        self.written.store(written.wrapping_add(1), Ordering::Release);
+    }
+
+    pub fn written(&self) -> usize {
+        self.written.load(Ordering::Acquire)
+    }
+
+    /// copies the newest `out.len()` samples into `out`, oldest first. returns `false` and leaves
+    /// `out` alone when fewer samples than that have been written so far
+    pub fn read_latest(&self, out: &mut [f32]) -> bool {
+        assert!(out.len() <= RING_CAPACITY);
+        let written = self.written();
+        if written < out.len() {
+            return false;
+        }
+
+        let start = written - out.len();
+        for (i, x) in out.iter_mut().enumerate() {
+            *x = f32::from_bits(self.samples[(start + i) % RING_CAPACITY].load(Ordering::Relaxed));
+        }
+        true
+    }
+}
+
+/// what the analyser looks at, shared between the plugin and the editor
+#[derive(Default)]
+pub struct SpectrumTaps {
+    /// the input, both channels averaged
+    pub dry: SampleRing,
+    /// the reverb's output before the mix, so its level doesn't depend on the wet gain
+    pub wet: SampleRing,
+    sample_rate: AtomicU32,
+}
+
+impl SpectrumTaps {
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.store(sample_rate.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        f32::from_bits(self.sample_rate.load(Ordering::Relaxed))
+    }
+}
+
+/// how much of the previous frames carries over into the spectrum
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Averaging {
+    Off,
+    Short,
+    Long,
+}
+
+impl Enum for Averaging {
+    fn variants() -> &'static [&'static str] {
+        &["Off", "Short", "Long"]
+    }
+
+    fn ids() -> Option<&'static [&'static str]> {
+        Some(&["off", "short", "long"])
+    }
+
+    fn to_index(self) -> usize {
+        match self {
+            Averaging::Off => 0,
+            Averaging::Short => 1,
+            Averaging::Long => 2,
+        }
+    }
+
+    fn from_index(index: usize) -> Self {
+        match index {
+            0 => Averaging::Off,
+            1 => Averaging::Short,
+            2 => Averaging::Long,
+            _ => panic!("Invalid index for Averaging"),
+        }
+    }
+}
+
+impl Averaging {
+    /// the weight of the previous frames, per frame
+    fn weight(self) -> f32 {
+        match self {
+            Averaging::Off => 0.0,
+            Averaging::Short => 0.7,
+            Averaging::Long => 0.93,
+        }
+    }
+}
+
+/// turns the newest samples of a `SampleRing` into a magnitude spectrum. runs on the GUI thread
+pub struct Analyser {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// the averaged power per bin, relative to a full scale sine
+    power: Vec<f32>,
+    averaging: Averaging,
+}
+
+impl Analyser {
+    pub fn new(fft_size: usize, averaging: Averaging) -> Self {
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(fft_size);
+        // hann window
+        let window: Vec<f32> = (0..fft_size)
+            .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / fft_size as f32).cos())
+            .collect();
+
+        Self {
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            power: vec![0.0; fft_size / 2 + 1],
+            fft,
+            window,
+            averaging,
+        }
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn averaging(&self) -> Averaging {
+        self.averaging
+    }
+
+    pub fn set_averaging(&mut self, averaging: Averaging) {
+        self.averaging = averaging;
+    }
+
+    /// analyses the newest samples in `ring` and averages them into the spectrum. returns `false`
+    /// when the ring doesn't have enough samples yet
+    pub fn update(&mut self, ring: &SampleRing) -> bool {
+        if !ring.read_latest(&mut self.input) {
+            return false;
+        }
+        self.analyse();
+        true
+    }
+
+    /// analyses a block of exactly `fft_size()` samples
+    pub fn update_from(&mut self, samples: &[f32]) {
+        self.input.copy_from_slice(samples);
+        self.analyse();
+    }
+
+    fn analyse(&mut self) {
+        for (x, window) in self.input.iter_mut().zip(self.window.iter()) {
+            *x *= window;
+        }
+        self.fft
+            .process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch)
+            .unwrap();
+
+        // a full scale sine ends up at 0 dB whatever the FFT size
+        let window_sum: f32 = self.window.iter().sum();
+        let scale = 2.0 / window_sum;
+        let weight = self.averaging.weight();
+        for (power, bin) in self.power.iter_mut().zip(self.output.iter()) {
+            let magnitude = bin.norm() * scale;
+            *power = *power * weight + magnitude * magnitude * (1.0 - weight);
+        }
+    }
+
+    /// the level of bin `bin` in dB
+    pub fn bin_db(&self, bin: usize) -> f32 {
+        (10.0 * self.power[bin].log10()).max(SPECTRUM_FLOOR_DB)
+    }
+
+    pub fn bins(&self) -> usize {
+        self.power.len()
+    }
+
+    /// the center frequency of bin `bin` in Hz
+    pub fn bin_frequency(&self, bin: usize, sample_rate: f32) -> f32 {
+        bin as f32 * sample_rate / self.fft_size() as f32
+    }
+}
@@ -0,0 +1,72 @@
+//! Loop gain bounds for the feedback paths in the reverb.
+//!
+//! Every recursive filter in here is stable as long as the sum of the absolute values of its
+//! feedback coefficients stays below 1.0. That bound holds for every frequency, so it's the
+//! worst-case loop gain for a parameter set. The setters of `CombBank`, `DelayingAllPass`,
+//! `Chorus` and `Delay` run their coefficients through the functions below so no combination of
+//! parameters can make a loop grow without bounds.
+
+/// the largest loop gain any feedback path is allowed to have
+pub const MAX_LOOP_GAIN: f32 = 0.999;
+
+/// worst-case loop gain of one comb in a `CombBank`.
+///
+/// the feedback path is `feedback * y[n - D]`, and with the low-pass enabled there is an extra
+/// `damp * y[n - 1]` term. the feedforward path (the only thing `CombType::Negative` flips) isn't
+/// part of the loop and doesn't matter here
+pub fn comb_loop_gain(feedback: f32, use_lpf: bool, damp: f32) -> f32 {
+    if use_lpf {
+        feedback.abs() + damp.abs()
+    } else {
+        feedback.abs()
+    }
+}
+
+/// worst-case loop gain of a `DelayingAllPass`, only the `gain * y[n - D]` term feeds back
+pub fn allpass_loop_gain(gain: f32) -> f32 {
+    gain.abs()
+}
+
+/// worst-case loop gain of a `Chorus`. the feedback is the average of the three modulated taps,
+/// scaled by `wet * feedback`
+pub fn chorus_loop_gain(wet: f32, feedback: f32) -> f32 {
+    (wet * feedback).abs()
+}
+
+/// returns `(feedback, damp)` limited so `comb_loop_gain()` stays at or below `MAX_LOOP_GAIN`. the
+/// damping takes priority, the feedback gets whatever is left
+pub fn limit_comb(feedback: f32, use_lpf: bool, damp: f32) -> (f32, f32) {
+    let damp = finite_or_zero(damp).clamp(-MAX_LOOP_GAIN, MAX_LOOP_GAIN);
+    let budget = if use_lpf {
+        MAX_LOOP_GAIN - damp.abs()
+    } else {
+        MAX_LOOP_GAIN
+    };
+
+    (finite_or_zero(feedback).clamp(-budget, budget), damp)
+}
+
+/// limits an allpass gain to `MAX_LOOP_GAIN`
+pub fn limit_allpass(gain: f32) -> f32 {
+    finite_or_zero(gain).clamp(-MAX_LOOP_GAIN, MAX_LOOP_GAIN)
+}
+
+/// limits the chorus feedback so `chorus_loop_gain()` stays at or below `MAX_LOOP_GAIN`
+pub fn limit_chorus_feedback(wet: f32, feedback: f32) -> f32 {
+    let feedback = finite_or_zero(feedback);
+    let wet = finite_or_zero(wet).abs();
+    if chorus_loop_gain(wet, feedback) > MAX_LOOP_GAIN {
+        (MAX_LOOP_GAIN / wet).copysign(feedback)
+    } else {
+        feedback
+    }
+}
+
+/// replaces NaN and infinity with silence
+pub fn finite_or_zero(x: f32) -> f32 {
+    if x.is_finite() {
+        x
+    } else {
+        0.0
+    }
+}
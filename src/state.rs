@@ -0,0 +1,93 @@
+//! Versioning and migration for the plugin's saved state.
+//!
+//! The state a host saves holds every parameter's plain value (enums by their ID) and the
+//! persisted fields, keyed by their IDs. It also has a `state-version` field with the
+//! `STATE_VERSION` it was saved with. nih_plug calls `migrate()` through `Plugin::filter_state()`
+//! before the state gets loaded, which upgrades older states one version at a time.
+//!
+//! Whenever a change would make an older state load differently, bump `STATE_VERSION` and add a
+//! step to `MIGRATIONS` that rewrites the old state so it sounds the same as before:
+//!
+//! - changing a parameter's range or unit: convert the stored plain value.
+//! - renaming a parameter ID: move the value to the new key. Hosts refer to parameters by their
+//!   ID in automation too, and that can't be migrated here, which is why IDs like `comb type`
+//!   are kept as they are.
+//! - removing or renaming an enum variant: map the old variant ID to a new one.
+//! - changing the DSP: add a parameter or field that selects the old behaviour, and set it in the
+//!   migration, like `MixMode::Legacy`.
+//!
+//! Old states are kept in `tests/state` and `tests/state.rs` checks that they still load.
+
+use nih_plug::nih_log;
+use nih_plug::wrapper::state::{ParamValue, PluginState};
+
+/// the version states are saved with. states without a version are version 0
+pub const STATE_VERSION: u32 = 4;
+
+/// the ID of the persisted field that holds the version
+pub const STATE_VERSION_KEY: &str = "state-version";
+
+/// `MIGRATIONS[n]` upgrades a version `n` state to version `n + 1`
+const MIGRATIONS: [fn(&mut PluginState); STATE_VERSION as usize] = [v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4];
+
+/// the version `state` was saved with
+pub fn state_version(state: &PluginState) -> u32 {
+    state
+        .fields
+        .get(STATE_VERSION_KEY)
+        .and_then(|version| serde_json::from_str(version).ok())
+        .unwrap_or(0)
+}
+
+/// upgrades `state` to `STATE_VERSION`. states saved by a newer version of the plugin are loaded
+/// as they are, parameters and fields it doesn't know get skipped
+pub fn migrate(state: &mut PluginState) {
+    let version = state_version(state);
+    if version > STATE_VERSION {
+        nih_log!(
+            "The state was saved by a newer version of the plugin (state version {}, this one supports {}), some settings may be lost",
+            version,
+            STATE_VERSION
+        );
+    }
+
+    for migration in MIGRATIONS.iter().skip(version as usize) {
+        migration(state);
+    }
+    state.fields.insert(STATE_VERSION_KEY.to_owned(), STATE_VERSION.to_string());
+}
+
+/// states from before the version existed. whatever got added since is set to what the plugin did
+/// without it, instead of relying on the defaults for new instances staying the same
+fn v0_to_v1(state: &mut PluginState) {
+    // the delay offsets were random every time the session got loaded before the seed was saved.
+    // this makes them reproducible from now on, 0 is what `reverb-render` uses
+    state.fields.entry("seed".to_owned()).or_insert_with(|| "0".to_owned());
+
+    for (id, value) in [
+        ("mix-mode", ParamValue::String("legacy".to_owned())),
+        ("output-gain", ParamValue::F32(1.0)),
+        ("send-mode", ParamValue::Bool(false)),
+    ] {
+        state.params.entry(id.to_owned()).or_insert(value);
+    }
+}
+
+/// the modulation sources, they're switched off in older states. the chorus depth came with them,
+/// the chorus had no depth before
+fn v1_to_v2(state: &mut PluginState) {
+    for id in ["lfo1-target", "lfo2-target", "env-target"] {
+        state.params.entry(id.to_owned()).or_insert_with(|| ParamValue::String("none".to_owned()));
+    }
+    state.params.entry("chorus-depth".to_owned()).or_insert(ParamValue::F32(0.0));
+}
+
+/// the freeze parameter, a session that's loaded shouldn't stay frozen because the previous one was
+fn v2_to_v3(state: &mut PluginState) {
+    state.params.entry("freeze".to_owned()).or_insert(ParamValue::Bool(false));
+}
+
+/// the pre-delay, older states had none
+fn v3_to_v4(state: &mut PluginState) {
+    state.params.entry("pre-delay".to_owned()).or_insert(ParamValue::F32(0.0));
+}
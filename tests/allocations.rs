@@ -0,0 +1,216 @@
+//! The process path must not allocate.
+//!
+//! The whole plugin runs through the headless host while every parameter gets moved, every reverb
+//! type gets switched to, freeze gets toggled and the plugin gets reinitialized at several sample
+//! rates. The meters, the spectrum and the modulation display are only fed while the editor is
+//! open, the headless host has no editor, so everything runs once more with
+//! `reverb::METER_WITHOUT_EDITOR` set.
+//!
+//! In debug builds nih_plug's `assert_process_allocs` feature installs its own global allocator
+//! that aborts on any allocation inside `process()`, so a failure there takes down the whole test
+//! binary. Release builds don't have that, there the allocator below counts the calls instead.
+//! `allocations_in_process_are_caught` makes sure whichever of the two is active would notice:
+//!
+//! ```shell
+//! cargo test --test allocations
+//! cargo test --release --test allocations
+//! ```
+
+mod common;
+
+use std::process::Command;
+
+use common::host::Host;
+use nih_plug::prelude::{BufferConfig, ProcessMode};
+use std::sync::atomic::Ordering;
+
+const SAMPLE_RATES: [f32; 3] = [44100.0, 48000.0, 96000.0];
+const MAX_BUFFER_SIZE: u32 = 512;
+/// the odd sizes don't line up with the plugin's internal blocks
+const BUFFER_SIZES: [usize; 5] = [1, 63, 64, 65, 512];
+/// set to 1 to make `process_with_host_allocations` allocate
+const ALLOCATE_VAR: &str = "REVERB_TEST_ALLOCATE_IN_PROCESS";
+
+#[cfg(not(debug_assertions))]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    pub struct CountingAllocator;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        /// only the thread running the check counts, the test harness allocates on its own threads
+        static ENABLED: Cell<bool> = const { Cell::new(false) };
+    }
+
+    fn count() {
+        if ENABLED.try_with(|enabled| enabled.get()).unwrap_or(false) {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            count();
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            count();
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            count();
+            System.realloc(ptr, layout, new_size)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            count();
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    /// runs `f` and returns how often it called the allocator, freeing memory counts as well
+    pub fn allocator_calls(f: impl FnOnce()) -> usize {
+        let before = CALLS.load(Ordering::Relaxed);
+        ENABLED.with(|enabled| enabled.set(true));
+        f();
+        ENABLED.with(|enabled| enabled.set(false));
+        CALLS.load(Ordering::Relaxed) - before
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[global_allocator]
+static ALLOCATOR: counting::CountingAllocator = counting::CountingAllocator;
+
+/// nih_plug's allocator aborts on its own in debug builds, so there is nothing to count. see
+/// `allocations_in_process_are_caught` for proof that it does
+#[cfg(debug_assertions)]
+fn allocator_calls(f: impl FnOnce()) -> usize {
+    f();
+    0
+}
+
+#[cfg(not(debug_assertions))]
+use counting::allocator_calls;
+
+fn buffer_config(sample_rate: f32) -> BufferConfig {
+    BufferConfig {
+        sample_rate,
+        min_buffer_size: None,
+        max_buffer_size: MAX_BUFFER_SIZE,
+        process_mode: ProcessMode::Realtime,
+    }
+}
+
+/// the values every parameter gets set to: each step of a stepped parameter, or both ends, the
+/// middle and the default of a continuous one
+fn sweep(step_count: Option<u32>, default_normalized: f64) -> Vec<f64> {
+    match step_count {
+        Some(step_count) => (0..=step_count)
+            .chain((0..step_count).rev())
+            .map(|step| step as f64 / step_count as f64)
+            .collect(),
+        None => vec![1.0, 0.0, 0.5, default_normalized],
+    }
+}
+
+#[test]
+fn process_does_not_allocate() {
+    let input = common::noise_burst(MAX_BUFFER_SIZE as usize, MAX_BUFFER_SIZE as usize, 0);
+    let mut left = input.clone();
+    let mut right = input.clone();
+
+    let mut host = Host::new(&buffer_config(SAMPLE_RATES[0]));
+    let params = host.params().to_vec();
+    assert!(!params.is_empty());
+    let sweeps: Vec<Vec<f64>> = params
+        .iter()
+        .map(|param| sweep(param.step_count, param.default_normalized))
+        .collect();
+    let mut changes = Vec::with_capacity(params.len());
+    assert!(params.iter().any(|param| param.name == "Freeze"));
+
+    for metering in [false, true] {
+        reverb::METER_WITHOUT_EDITOR.store(metering, Ordering::Relaxed);
+        for sample_rate in SAMPLE_RATES {
+            // reinitializing is allowed to allocate, processing right after it isn't
+            host.reinitialize(&buffer_config(sample_rate));
+
+            let calls = allocator_calls(|| {
+                for &buffer_size in BUFFER_SIZES.iter() {
+                    let (left, right) = (&mut left[..buffer_size], &mut right[..buffer_size]);
+
+                    // one parameter at a time, halfway through the buffer
+                    for (param, values) in params.iter().zip(sweeps.iter()) {
+                        for &normalized in values {
+                            left.copy_from_slice(&input[..buffer_size]);
+                            right.copy_from_slice(&input[..buffer_size]);
+                            let time = buffer_size as u32 / 2;
+                            host.process(left, right, &[param.change(time, normalized)]);
+                        }
+                    }
+
+                    // all of them at once, spread over the buffer so the sample accurate automation
+                    // splits it up
+                    for step in 0..4 {
+                        changes.clear();
+                        for (i, (param, values)) in params.iter().zip(sweeps.iter()).enumerate() {
+                            changes.push(param.change((i * buffer_size / params.len()) as u32, values[step % values.len()]));
+                        }
+                        left.copy_from_slice(&input[..buffer_size]);
+                        right.copy_from_slice(&input[..buffer_size]);
+                        host.process(left, right, &changes);
+                    }
+
+                    // and a few buffers without any changes while the smoothers settle
+                    for _ in 0..4 {
+                        left.copy_from_slice(&input[..buffer_size]);
+                        right.copy_from_slice(&input[..buffer_size]);
+                        host.process(left, right, &[]);
+                    }
+                }
+            });
+
+            assert_eq!(calls, 0, "processing at {} Hz (metering {}) called the allocator {} times", sample_rate, metering, calls);
+        }
+    }
+}
+
+/// `allocations_in_process_are_caught` runs this in a separate process, since in debug builds an
+/// allocation aborts the whole test binary
+#[test]
+#[ignore = "run by allocations_in_process_are_caught"]
+fn process_with_host_allocations() {
+    let mut host = Host::new(&buffer_config(SAMPLE_RATES[0]));
+    host.set_allocate_in_process(std::env::var(ALLOCATE_VAR).is_ok_and(|value| value == "1"));
+
+    let mut left = common::noise_burst(64, 64, 0);
+    let mut right = left.clone();
+    let calls = allocator_calls(|| host.process(&mut left, &mut right, &[]));
+    assert_eq!(calls, 0, "processing called the allocator {} times", calls);
+}
+
+/// an allocation during `process()` has to fail the check in both debug and release builds.
+/// without the allocation the same test has to pass, so it can't be failing for another reason
+#[test]
+fn allocations_in_process_are_caught() {
+    for (allocate, should_pass) in [("0", true), ("1", false)] {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["process_with_host_allocations", "--exact", "--ignored", "--test-threads=1"])
+            .env(ALLOCATE_VAR, allocate)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert_eq!(output.status.success(), should_pass, "{}={}: {}", ALLOCATE_VAR, allocate, stdout);
+        if should_pass {
+            assert!(stdout.contains("1 passed"), "{}", stdout);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! `Reverb::process_block()` compared against the per-sample API.
+
+mod common;
+
+use reverb::reverb::{BlockParams, Reverb, ReverbSettings};
+
+const SAMPLE_RATE: f32 = 16000.0;
+const SEED: u64 = 0x7eb;
+/// the block size the plugin uses
+const MAX_BLOCK_SIZE: usize = 64;
+/// how far a render with a parameter change split over two blocks may be off, relative to the
+/// peak level
+const SPLIT_TOLERANCE: f32 = 0.03;
+
+/// with constant parameters the block size shouldn't make any difference
+#[test]
+fn blocks_match_per_sample_processing() {
+    let input = common::noise_burst(4000, 400, SEED);
+
+    for settings in common::all_settings(500.0, 0.3, SEED) {
+        let (expected_left, expected_right) = common::render(&settings, SAMPLE_RATE, &input);
+
+        for block_size in [1, 37, MAX_BLOCK_SIZE, 500] {
+            let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+            let mut left = input.clone();
+            let mut right = input.clone();
+            for (left, right) in left.chunks_mut(block_size).zip(right.chunks_mut(block_size)) {
+                reverb.process_block(left, right, &settings.block_params());
+            }
+
+            assert!(
+                left == expected_left && right == expected_right,
+                "{} differs with {} sample blocks",
+                common::settings_name(&settings),
+                block_size,
+            );
+        }
+    }
+}
+
+/// a parameter change applied in a single block ends up exactly where a fresh reverb with the
+/// new settings would be
+#[test]
+fn parameter_changes_reach_the_target() {
+    for mut settings in common::all_settings(500.0, 0.1, SEED) {
+        let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+
+        settings.decay = 5000.0;
+        settings.damping = 0.6;
+        let target = Reverb::from_settings(SAMPLE_RATE, &settings);
+
+        let mut left = vec![0.0; MAX_BLOCK_SIZE];
+        let mut right = left.clone();
+        reverb.process_block(&mut left, &mut right, &settings.block_params());
+        assert_eq!(reverb.loop_gain(), target.loop_gain(), "{}", common::settings_name(&settings));
+    }
+}
+
+/// the gains ramp over the whole block and land exactly on the new values with its last sample,
+/// whatever the block size
+#[test]
+fn ramps_end_on_the_target() {
+    for settings in common::all_settings(500.0, 0.1, SEED) {
+        let changed = ReverbSettings {
+            decay: 3000.0,
+            damping: 0.5,
+            ..settings
+        };
+        let target = Reverb::from_settings(SAMPLE_RATE, &changed);
+
+        for block_size in [1, 37, MAX_BLOCK_SIZE, 500] {
+            let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+            let input = common::noise_burst(block_size, 20, SEED);
+            let (mut left, mut right) = (input.clone(), input);
+            reverb.process_block(&mut left, &mut right, &changed.block_params());
+
+            assert_eq!(
+                reverb.combs().coefficients(),
+                target.combs().coefficients(),
+                "{} with {} sample blocks",
+                common::settings_name(&settings),
+                block_size,
+            );
+        }
+    }
+}
+
+/// splitting the block a parameter change happens in only changes how the gains get to their new
+/// values, not where they end up, so it sounds almost the same as one block
+#[test]
+fn split_blocks_sound_like_one_block() {
+    const CHANGE_BLOCK: usize = 20;
+    // the change comes once the tail is ringing, so the feedback paths are running
+    let input = common::noise_burst(40 * MAX_BLOCK_SIZE, 400, SEED);
+
+    for settings in common::all_settings(500.0, 0.1, SEED) {
+        // about as far as automation moves the parameters in one block
+        let target = BlockParams {
+            decay: 600.0,
+            damping: 0.15,
+            ..settings.block_params()
+        };
+
+        let render = |split: usize| {
+            let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+            let (mut left, mut right) = (input.clone(), input.clone());
+            let blocks = left.chunks_mut(MAX_BLOCK_SIZE).zip(right.chunks_mut(MAX_BLOCK_SIZE));
+            for (i, (left, right)) in blocks.enumerate() {
+                if i < CHANGE_BLOCK {
+                    reverb.process_block(left, right, &settings.block_params());
+                } else if i == CHANGE_BLOCK {
+                    let (left_a, left_b) = left.split_at_mut(split);
+                    let (right_a, right_b) = right.split_at_mut(split);
+                    reverb.process_block(left_a, right_a, &target);
+                    reverb.process_block(left_b, right_b, &target);
+                } else {
+                    reverb.process_block(left, right, &target);
+                }
+            }
+            (left, right, reverb.combs().coefficients())
+        };
+
+        let (one_left, one_right, one_coefficients) = render(MAX_BLOCK_SIZE);
+        let (two_left, two_right, two_coefficients) = render(MAX_BLOCK_SIZE / 2);
+
+        let name = common::settings_name(&settings);
+        assert_eq!(one_coefficients, two_coefficients, "{}", name);
+        let peak = one_left.iter().chain(one_right.iter()).fold(0.0f32, |peak, x| peak.max(x.abs()));
+        let error = one_left
+            .iter()
+            .zip(two_left.iter())
+            .chain(one_right.iter().zip(two_right.iter()))
+            .fold(0.0f32, |error, (a, b)| error.max((a - b).abs()));
+        assert!(error <= SPLIT_TOLERANCE * peak, "{} differs by {} with a peak of {}", name, error, peak);
+    }
+}
+
+/// a frozen reverb keeps its tail at about the same level and ignores new input, and after
+/// unfreezing the tail decays again
+#[test]
+fn freeze_holds_the_tail() {
+    let rms = |left: &[f32], right: &[f32]| {
+        let sum: f32 = left.iter().chain(right).map(|x| x * x).sum();
+        (sum / (left.len() + right.len()) as f32).sqrt()
+    };
+
+    for settings in common::all_settings(500.0, 0.3, SEED) {
+        let name = common::settings_name(&settings);
+        let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+        let frozen = BlockParams {
+            freeze: true,
+            ..settings.block_params()
+        };
+        let mut process = |input: &[f32], params: &BlockParams| {
+            let (mut left, mut right) = (input.to_vec(), input.to_vec());
+            for (left, right) in left.chunks_mut(MAX_BLOCK_SIZE).zip(right.chunks_mut(MAX_BLOCK_SIZE)) {
+                reverb.process_block(left, right, params);
+            }
+            rms(&left, &right)
+        };
+
+        let second = SAMPLE_RATE as usize;
+        process(&common::noise_burst(second / 4, second / 4, SEED), &settings.block_params());
+        let start = process(&vec![0.0; second / 4], &frozen);
+        // three times the decay time later, and with more noise coming in
+        let held = process(&common::noise_burst(second * 3 / 2, second * 3 / 2, SEED + 1), &frozen);
+        assert!(held > 0.5 * start && held < 2.0 * start, "{}: {} after freezing, {} later", name, start, held);
+
+        process(&vec![0.0; second * 3 / 2], &settings.block_params());
+        let released = process(&vec![0.0; second / 4], &settings.block_params());
+        assert!(released < 0.01 * held, "{}: still at {} after unfreezing", name, released);
+    }
+}
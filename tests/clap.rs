@@ -0,0 +1,108 @@
+//! What the plugin tells CLAP hosts about itself, and CLAP's parameter modulation.
+
+mod common;
+
+use common::host::{self, Host, ParamChange};
+use nih_plug::prelude::{BufferConfig, Enum, ProcessMode};
+use reverb::reverb::ReverbType;
+
+const SAMPLE_RATE: f32 = 44100.0;
+/// the smoothers take 10 ms to reach their targets
+const SETTLED: usize = 1024;
+
+/// the parameters with a continuous range
+const CONTINUOUS_PARAMS: [&str; 9] = ["Decay", "Damping", "Pre-Delay", "Wet", "Dry", "Dry Gain", "Wet Gain", "Mix", "Output Gain"];
+
+/// read from the factory of the `clap_entry` the library exports, the way a host sees it
+#[test]
+fn descriptor() {
+    let descriptor = host::descriptor();
+    assert_eq!(descriptor.id, "com.tsk.reverb");
+    assert_eq!(descriptor.name, "tsk reverb");
+    assert_eq!(descriptor.version, env!("CARGO_PKG_VERSION"));
+    for text in [&descriptor.vendor, &descriptor.description] {
+        assert!(!text.is_empty() && !text.contains("{{"), "{:?}", text);
+    }
+
+    for feature in ["audio-effect", "reverb", "stereo", "mono"] {
+        assert!(descriptor.features.iter().any(|f| f == feature), "{} is missing from {:?}", feature, descriptor.features);
+    }
+}
+
+#[test]
+fn every_parameter_is_modulatable() {
+    let host = Host::new(&buffer_config());
+    for name in CONTINUOUS_PARAMS {
+        assert!(host.param(name).step_count.is_none(), "{}", name);
+    }
+    for param in host.params() {
+        assert!(param.modulatable, "{}", param.name);
+    }
+}
+
+fn buffer_config() -> BufferConfig {
+    BufferConfig {
+        sample_rate: SAMPLE_RATE,
+        min_buffer_size: None,
+        max_buffer_size: 512,
+        process_mode: ProcessMode::Realtime,
+    }
+}
+
+/// renders half a second of noise through the LPF comb reverb, which sounds the same in every
+/// instance, fully wet with the wet level at zero
+fn render(setup: impl FnOnce(&Host) -> Vec<ParamChange>) -> Vec<f32> {
+    let mut host = Host::new(&buffer_config());
+    let input = common::noise_burst(SAMPLE_RATE as usize / 2, SAMPLE_RATE as usize / 2, 1);
+    let mut automation = vec![
+        host.param("Reverb Type").step(0, ReverbType::LpfComb.to_index() as u32),
+        host.param("Dry").change(0, 0.0),
+        host.param("Wet").change(0, 0.0),
+    ];
+    automation.extend(setup(&host));
+
+    let (left, _) = host.render(&input, &input, &automation);
+    left
+}
+
+#[test]
+fn modulation_adds_to_the_parameter() {
+    let modulated = render(|host| vec![host.param("Wet").modulate(0, 0.5)]);
+    let automated = render(|host| vec![host.param("Wet").change(0, 0.5)]);
+    assert!(modulated.iter().any(|x| x.abs() > 1e-3));
+    for (i, (modulated, automated)) in modulated.iter().zip(automated.iter()).enumerate() {
+        assert!((modulated - automated).abs() < 1e-6, "sample {}: {} != {}", i, modulated, automated);
+    }
+}
+
+#[test]
+fn modulation_leaves_the_parameter_alone() {
+    let half = SAMPLE_RATE as u32 / 4;
+    let output = render(|host| {
+        let wet = host.param("Wet");
+        vec![wet.modulate(0, 0.5), wet.modulate(half, 0.0)]
+    });
+
+    assert!(output[..half as usize].iter().any(|x| x.abs() > 1e-3));
+    // without the modulation the wet level is back at zero
+    assert!(output[half as usize + SETTLED..].iter().all(|x| *x == 0.0));
+}
+
+/// the modulation reaches the reverb and not just the mix levels: modulating a parameter sounds
+/// the same as automating it to where the modulation takes it
+#[test]
+fn modulation_reaches_the_reverb() {
+    for (name, target) in [("Decay", 0.8), ("Freeze", 1.0)] {
+        let modulated = render(|host| {
+            let param = host.param(name);
+            vec![host.param("Wet").change(0, 0.5), param.modulate(0, target - param.default_normalized)]
+        });
+        let automated = render(|host| vec![host.param("Wet").change(0, 0.5), host.param(name).change(0, target)]);
+        let unmodulated = render(|host| vec![host.param("Wet").change(0, 0.5)]);
+
+        assert!(modulated.iter().zip(unmodulated.iter()).any(|(a, b)| (a - b).abs() > 1e-3), "{}", name);
+        for (i, (modulated, automated)) in modulated.iter().zip(automated.iter()).enumerate() {
+            assert!((modulated - automated).abs() < 1e-6, "{}, sample {}: {} != {}", name, i, modulated, automated);
+        }
+    }
+}
@@ -0,0 +1,516 @@
+//! A minimal CLAP host that runs the plugin without a DAW.
+//!
+//! The plugin is loaded through the `clap_entry` the library exports, the same way a host loads
+//! the `.clap` bundle, so everything between the host and `ReverbPlugin::process()` is part of
+//! what gets tested: nih_plug's wrapper, its parameter handling, its sample accurate automation and
+//! its `assert_process_allocs` check.
+//!
+//! `Host::process()` runs a single buffer and doesn't allocate, so it can be used to check that the
+//! plugin doesn't either. `Host::set_allocate_in_process()` makes it allocate on purpose, to check
+//! that such a check notices. `Host::render()` runs a whole signal in buffers of the maximum size
+//! with a script of parameter changes and collects the output.
+//!
+//! `descriptor()` reads what the plugin's CLAP factory tells hosts about the plugin.
+
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::ptr;
+use std::sync::Once;
+
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::events::{
+    clap_event_header, clap_event_param_mod, clap_event_param_value, clap_input_events, clap_output_events,
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE,
+};
+use clap_sys::ext::params::{
+    clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS, CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_STEPPED,
+};
+use clap_sys::ext::render::{clap_plugin_render, CLAP_EXT_RENDER, CLAP_RENDER_OFFLINE, CLAP_RENDER_REALTIME};
+use clap_sys::ext::state::{clap_plugin_state, CLAP_EXT_STATE};
+use clap_sys::factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID};
+use clap_sys::host::clap_host;
+use clap_sys::plugin::clap_plugin;
+use clap_sys::process::{clap_process, CLAP_PROCESS_ERROR};
+use clap_sys::stream::{clap_istream, clap_ostream};
+use clap_sys::version::CLAP_VERSION;
+use nih_plug::prelude::{BufferConfig, ProcessMode};
+
+/// more parameter changes than this in a single `Host::process()` call would have to allocate
+const MAX_EVENTS: usize = 1024;
+
+/// a parameter as the plugin describes it to the host
+#[derive(Clone, Debug)]
+pub struct ParamInfo {
+    pub id: u32,
+    pub name: String,
+    /// `None` for continuous parameters
+    pub step_count: Option<u32>,
+    pub default_normalized: f64,
+    /// whether the host may modulate it on top of its automated value
+    pub modulatable: bool,
+}
+
+impl ParamInfo {
+    /// sets the parameter to a normalized value at `time`
+    pub fn change(&self, time: u32, normalized: f64) -> ParamChange {
+        ParamChange { time, param: self.id, normalized, modulation: false }
+    }
+
+    /// modulates the parameter by a normalized `amount` from `time` on. the modulation replaces
+    /// the previous one and doesn't change the parameter's own value
+    pub fn modulate(&self, time: u32, amount: f64) -> ParamChange {
+        ParamChange { time, param: self.id, normalized: amount, modulation: true }
+    }
+
+    /// sets a stepped parameter, like an enum's variant index, at `time`
+    pub fn step(&self, time: u32, step: u32) -> ParamChange {
+        let step_count = self.step_count.expect("the parameter isn't stepped");
+        assert!(step <= step_count);
+        self.change(time, step as f64 / step_count as f64)
+    }
+}
+
+/// a parameter change `time` samples into the buffer, or into the whole signal for
+/// `Host::render()`
+#[derive(Clone, Copy, Debug)]
+pub struct ParamChange {
+    pub time: u32,
+    pub param: u32,
+    pub normalized: f64,
+    /// a CLAP modulation event, `normalized` is then the modulation amount
+    pub modulation: bool,
+}
+
+/// what the plugin describes itself as to hosts
+#[derive(Clone, Debug)]
+pub struct Descriptor {
+    pub id: String,
+    pub name: String,
+    pub vendor: String,
+    pub version: String,
+    pub description: String,
+    pub features: Vec<String>,
+}
+
+/// the plugin's CLAP descriptor, without creating an instance
+pub fn descriptor() -> Descriptor {
+    unsafe {
+        let factory = factory();
+        assert_eq!(((*factory).get_plugin_count.unwrap())(factory), 1);
+        let descriptor = &*((*factory).get_plugin_descriptor.unwrap())(factory, 0);
+
+        let string = |ptr: *const c_char| {
+            assert!(!ptr.is_null());
+            CStr::from_ptr(ptr).to_string_lossy().into_owned()
+        };
+        let mut features = Vec::new();
+        let mut feature = descriptor.features;
+        while !(*feature).is_null() {
+            features.push(string(*feature));
+            feature = feature.add(1);
+        }
+
+        Descriptor {
+            id: string(descriptor.id),
+            name: string(descriptor.name),
+            vendor: string(descriptor.vendor),
+            version: string(descriptor.version),
+            description: string(descriptor.description),
+            features,
+        }
+    }
+}
+
+/// initializes the library on first use, like a host does after loading it
+unsafe fn factory() -> *const clap_plugin_factory {
+    static INIT: Once = Once::new();
+
+    let entry = &reverb::clap_entry;
+    INIT.call_once(|| assert!((entry.init.unwrap())(c"".as_ptr())));
+
+    let factory = (entry.get_factory.unwrap())(CLAP_PLUGIN_FACTORY_ID.as_ptr()) as *const clap_plugin_factory;
+    assert!(!factory.is_null());
+    factory
+}
+
+/// the events for a single `Host::process()` call
+enum Event {
+    Value(clap_event_param_value),
+    Mod(clap_event_param_mod),
+}
+
+impl Event {
+    fn header(&self) -> &clap_event_header {
+        match self {
+            Event::Value(event) => &event.header,
+            Event::Mod(event) => &event.header,
+        }
+    }
+}
+
+pub struct Host {
+    /// boxed because the plugin keeps a pointer to it
+    host: Box<clap_host>,
+    plugin: *const clap_plugin,
+    params_extension: *const clap_plugin_params,
+    params: Vec<ParamInfo>,
+    max_buffer_size: u32,
+    steady_time: i64,
+    /// the plugin reads the input and writes to the caller's buffers, like most hosts it doesn't
+    /// process in place
+    input: [Vec<f32>; 2],
+    events: Vec<Event>,
+    allocate_in_process: bool,
+}
+
+impl Host {
+    /// creates the plugin and activates it with `config`. CLAP has no buffered process mode, so
+    /// `ProcessMode::Buffered` ends up as `ProcessMode::Realtime`
+    pub fn new(config: &BufferConfig) -> Self {
+        unsafe {
+            let factory = factory();
+            let descriptor = ((*factory).get_plugin_descriptor.unwrap())(factory, 0);
+
+            let host = Box::new(clap_host {
+                clap_version: CLAP_VERSION,
+                host_data: ptr::null_mut(),
+                name: c"headless".as_ptr(),
+                vendor: c"reverb tests".as_ptr(),
+                url: c"".as_ptr(),
+                version: c"0.1.0".as_ptr(),
+                get_extension: Some(host_get_extension),
+                request_restart: Some(host_request),
+                request_process: Some(host_request),
+                request_callback: Some(host_request),
+            });
+            let plugin = ((*factory).create_plugin.unwrap())(factory, &*host, (*descriptor).id);
+            assert!(!plugin.is_null());
+            assert!(((*plugin).init.unwrap())(plugin));
+
+            let params_extension = ((*plugin).get_extension.unwrap())(plugin, CLAP_EXT_PARAMS.as_ptr()) as *const clap_plugin_params;
+            assert!(!params_extension.is_null());
+
+            let mut host = Self {
+                host,
+                plugin,
+                params_extension,
+                params: query_params(plugin, params_extension),
+                max_buffer_size: 0,
+                steady_time: 0,
+                input: [Vec::new(), Vec::new()],
+                events: Vec::with_capacity(MAX_EVENTS),
+                allocate_in_process: false,
+            };
+            host.activate(config);
+            host
+        }
+    }
+
+    /// deactivates the plugin and activates it again with a new config, this is how hosts change
+    /// the sample rate or the buffer size
+    pub fn reinitialize(&mut self, config: &BufferConfig) {
+        unsafe {
+            ((*self.plugin).stop_processing.unwrap())(self.plugin);
+            ((*self.plugin).deactivate.unwrap())(self.plugin);
+        }
+        self.activate(config);
+    }
+
+    fn activate(&mut self, config: &BufferConfig) {
+        self.max_buffer_size = config.max_buffer_size;
+        self.input = [
+            vec![0.0; config.max_buffer_size as usize],
+            vec![0.0; config.max_buffer_size as usize],
+        ];
+
+        unsafe {
+            let render = ((*self.plugin).get_extension.unwrap())(self.plugin, CLAP_EXT_RENDER.as_ptr()) as *const clap_plugin_render;
+            if !render.is_null() {
+                let mode = match config.process_mode {
+                    ProcessMode::Offline => CLAP_RENDER_OFFLINE,
+                    ProcessMode::Realtime | ProcessMode::Buffered => CLAP_RENDER_REALTIME,
+                };
+                ((*render).set.unwrap())(self.plugin, mode);
+            }
+
+            assert!(((*self.plugin).activate.unwrap())(
+                self.plugin,
+                config.sample_rate as f64,
+                config.min_buffer_size.unwrap_or(1),
+                config.max_buffer_size,
+            ));
+            assert!(((*self.plugin).start_processing.unwrap())(self.plugin));
+        }
+    }
+
+    /// makes the callbacks the plugin calls from its `process()` allocate, as if the plugin
+    /// allocated itself
+    pub fn set_allocate_in_process(&mut self, allocate: bool) {
+        self.allocate_in_process = allocate;
+    }
+
+    pub fn params(&self) -> &[ParamInfo] {
+        &self.params
+    }
+
+    /// the parameter with the display name `name`
+    pub fn param(&self, name: &str) -> &ParamInfo {
+        self.params
+            .iter()
+            .find(|param| param.name == name)
+            .unwrap_or_else(|| panic!("the plugin has no parameter called '{}'", name))
+    }
+
+    /// sets the parameter called `name` at `time` to what `text` parses to, the same as typing it
+    /// into the parameter's text field. this way plain values like `-6 dB` don't need to be
+    /// converted to normalized values by hand
+    pub fn change_text(&self, name: &str, time: u32, text: &str) -> ParamChange {
+        let param = self.param(name);
+        let text = CString::new(text).unwrap();
+        let mut value = 0.0;
+        unsafe {
+            assert!(
+                ((*self.params_extension).text_to_value.unwrap())(self.plugin, param.id, text.as_ptr(), &mut value),
+                "'{}' can't parse {:?}",
+                name,
+                text
+            );
+        }
+
+        let normalized = match param.step_count {
+            Some(step_count) => value / step_count as f64,
+            None => value,
+        };
+        param.change(time, normalized)
+    }
+
+    /// the plugin's state, the same way a host saves it with the session
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state: Vec<u8> = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut state as *mut Vec<u8> as *mut c_void,
+            write: Some(ostream_write),
+        };
+        unsafe {
+            assert!(((*self.state_extension()).save.unwrap())(self.plugin, &stream));
+        }
+        state
+    }
+
+    /// restores a state from `save_state()` or a saved session. returns whether the plugin
+    /// accepted it
+    pub fn load_state(&mut self, state: &[u8]) -> bool {
+        let mut remaining = state;
+        let stream = clap_istream {
+            ctx: &mut remaining as *mut &[u8] as *mut c_void,
+            read: Some(istream_read),
+        };
+        unsafe { ((*self.state_extension()).load.unwrap())(self.plugin, &stream) }
+    }
+
+    fn state_extension(&self) -> *const clap_plugin_state {
+        unsafe {
+            let extension = ((*self.plugin).get_extension.unwrap())(self.plugin, CLAP_EXT_STATE.as_ptr()) as *const clap_plugin_state;
+            assert!(!extension.is_null());
+            extension
+        }
+    }
+
+    /// processes one buffer of stereo audio in place. `changes` have to be sorted by time and
+    /// fall within the buffer
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32], changes: &[ParamChange]) {
+        let len = left.len();
+        assert_eq!(len, right.len());
+        assert!(len <= self.max_buffer_size as usize, "the buffer is larger than the max buffer size");
+        assert!(changes.len() <= MAX_EVENTS);
+        assert!(changes.windows(2).all(|pair| pair[0].time <= pair[1].time));
+
+        self.input[0][..len].copy_from_slice(left);
+        self.input[1][..len].copy_from_slice(right);
+
+        self.events.clear();
+        for change in changes {
+            assert!((change.time as usize) < len);
+            let param = self.params.iter().find(|param| param.id == change.param).unwrap();
+            // stepped parameters take the step index, continuous ones the normalized value
+            let value = match param.step_count {
+                Some(step_count) => (change.normalized * step_count as f64).round(),
+                None => change.normalized,
+            };
+
+            let header = |size: usize, type_: u16| clap_event_header {
+                size: size as u32,
+                time: change.time,
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_,
+                flags: 0,
+            };
+            // -1 for the note, port, channel and key means it applies to everything
+            self.events.push(if change.modulation {
+                Event::Mod(clap_event_param_mod {
+                    header: header(std::mem::size_of::<clap_event_param_mod>(), CLAP_EVENT_PARAM_MOD),
+                    param_id: change.param,
+                    cookie: ptr::null_mut(),
+                    note_id: -1,
+                    port_index: -1,
+                    channel: -1,
+                    key: -1,
+                    amount: value,
+                })
+            } else {
+                Event::Value(clap_event_param_value {
+                    header: header(std::mem::size_of::<clap_event_param_value>(), CLAP_EVENT_PARAM_VALUE),
+                    param_id: change.param,
+                    cookie: ptr::null_mut(),
+                    note_id: -1,
+                    port_index: -1,
+                    channel: -1,
+                    key: -1,
+                    value,
+                })
+            });
+        }
+
+        let mut inputs = [self.input[0].as_mut_ptr(), self.input[1].as_mut_ptr()];
+        let mut outputs = [left.as_mut_ptr(), right.as_mut_ptr()];
+        let input_buffer = clap_audio_buffer {
+            data32: inputs.as_mut_ptr(),
+            data64: ptr::null_mut(),
+            channel_count: 2,
+            latency: 0,
+            constant_mask: 0,
+        };
+        let mut output_buffer = clap_audio_buffer {
+            data32: outputs.as_mut_ptr(),
+            data64: ptr::null_mut(),
+            channel_count: 2,
+            latency: 0,
+            constant_mask: 0,
+        };
+        let in_events = clap_input_events {
+            ctx: &self.events as *const Vec<Event> as *mut c_void,
+            size: Some(if self.allocate_in_process { input_events_size_allocating } else { input_events_size }),
+            get: Some(input_events_get),
+        };
+        let out_events = clap_output_events {
+            ctx: ptr::null_mut(),
+            try_push: Some(output_events_try_push),
+        };
+
+        let process = clap_process {
+            steady_time: self.steady_time,
+            frames_count: len as u32,
+            transport: ptr::null(),
+            audio_inputs: &input_buffer,
+            audio_outputs: &mut output_buffer,
+            audio_inputs_count: 1,
+            audio_outputs_count: 1,
+            in_events: &in_events,
+            out_events: &out_events,
+        };
+        let status = unsafe { ((*self.plugin).process.unwrap())(self.plugin, &process) };
+        assert_ne!(status, CLAP_PROCESS_ERROR);
+
+        self.steady_time += len as i64;
+    }
+
+    /// processes a whole stereo signal in buffers of the maximum size and returns the output.
+    /// `automation` has to be sorted by time, which counts from the start of the signal
+    pub fn render(&mut self, left: &[f32], right: &[f32], automation: &[ParamChange]) -> (Vec<f32>, Vec<f32>) {
+        assert_eq!(left.len(), right.len());
+        assert!(automation.windows(2).all(|pair| pair[0].time <= pair[1].time));
+
+        let mut output_left = left.to_vec();
+        let mut output_right = right.to_vec();
+        let mut automation = automation.iter().peekable();
+        let mut changes = Vec::new();
+
+        let buffer_size = self.max_buffer_size as usize;
+        for (buffer, (left, right)) in output_left
+            .chunks_mut(buffer_size)
+            .zip(output_right.chunks_mut(buffer_size))
+            .enumerate()
+        {
+            let start = (buffer * buffer_size) as u32;
+            let end = start + left.len() as u32;
+
+            changes.clear();
+            while let Some(change) = automation.next_if(|change| change.time < end) {
+                changes.push(ParamChange { time: change.time - start, ..*change });
+            }
+            self.process(left, right, &changes);
+        }
+        assert!(automation.next().is_none(), "the automation goes past the end of the signal");
+
+        (output_left, output_right)
+    }
+}
+
+impl Drop for Host {
+    fn drop(&mut self) {
+        unsafe {
+            ((*self.plugin).stop_processing.unwrap())(self.plugin);
+            ((*self.plugin).deactivate.unwrap())(self.plugin);
+            ((*self.plugin).destroy.unwrap())(self.plugin);
+        }
+    }
+}
+
+unsafe fn query_params(plugin: *const clap_plugin, extension: *const clap_plugin_params) -> Vec<ParamInfo> {
+    let count = ((*extension).count.unwrap())(plugin);
+    (0..count)
+        .map(|index| {
+            let mut info: clap_param_info = std::mem::zeroed();
+            assert!(((*extension).get_info.unwrap())(plugin, index, &mut info));
+
+            let stepped = info.flags & CLAP_PARAM_IS_STEPPED != 0;
+            let range = info.max_value - info.min_value;
+            ParamInfo {
+                id: info.id,
+                name: CStr::from_ptr(info.name.as_ptr()).to_string_lossy().into_owned(),
+                step_count: stepped.then_some(range as u32),
+                default_normalized: (info.default_value - info.min_value) / range,
+                modulatable: info.flags & CLAP_PARAM_IS_MODULATABLE != 0,
+            }
+        })
+        .collect()
+}
+
+unsafe extern "C" fn host_get_extension(_host: *const clap_host, _extension_id: *const c_char) -> *const c_void {
+    ptr::null()
+}
+
+unsafe extern "C" fn host_request(_host: *const clap_host) {}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    let events = &*((*list).ctx as *const Vec<Event>);
+    events.len() as u32
+}
+
+unsafe extern "C" fn input_events_size_allocating(list: *const clap_input_events) -> u32 {
+    std::hint::black_box(Vec::<u8>::with_capacity(64));
+    input_events_size(list)
+}
+
+unsafe extern "C" fn input_events_get(list: *const clap_input_events, index: u32) -> *const clap_event_header {
+    let events = &*((*list).ctx as *const Vec<Event>);
+    events.get(index as usize).map_or(ptr::null(), |event| event.header())
+}
+
+unsafe extern "C" fn ostream_write(stream: *const clap_ostream, buffer: *const c_void, size: u64) -> i64 {
+    let state = &mut *((*stream).ctx as *mut Vec<u8>);
+    state.extend_from_slice(std::slice::from_raw_parts(buffer as *const u8, size as usize));
+    size as i64
+}
+
+/// hands out the state in pieces, hosts don't have to give the plugin all of it at once
+unsafe extern "C" fn istream_read(stream: *const clap_istream, buffer: *mut c_void, size: u64) -> i64 {
+    let remaining = &mut *((*stream).ctx as *mut &[u8]);
+    let len = remaining.len().min(size as usize).min(1000);
+    ptr::copy_nonoverlapping(remaining.as_ptr(), buffer as *mut u8, len);
+    *remaining = &remaining[len..];
+    len as i64
+}
+
+unsafe extern "C" fn output_events_try_push(_list: *const clap_output_events, _event: *const clap_event_header) -> bool {
+    true
+}
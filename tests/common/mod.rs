@@ -0,0 +1,79 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+pub mod host;
+
+use nih_plug::prelude::Enum;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reverb::comb::CombType;
+use reverb::reverb::{Reverb, ReverbSettings, ReverbType};
+
+pub const REVERB_TYPES: [ReverbType; 4] = [
+    ReverbType::Comb,
+    ReverbType::Schroeder,
+    ReverbType::LpfComb,
+    ReverbType::Moorer,
+];
+
+pub const COMB_TYPES: [CombType; 2] = [CombType::Positive, CombType::Negative];
+
+/// every `ReverbType` x `CombType` x chorus on/off combination with the given decay and damping
+pub fn all_settings(decay: f32, damping: f32, seed: u64) -> Vec<ReverbSettings> {
+    let mut settings = Vec::new();
+    for reverb_type in REVERB_TYPES {
+        for comb_type in COMB_TYPES {
+            for enable_chorus in [false, true] {
+                settings.push(ReverbSettings {
+                    reverb_type,
+                    decay,
+                    damping,
+                    comb_type,
+                    enable_chorus,
+                    chorus_depth: 0.0,
+                    pre_delay: 0.0,
+                    seed,
+                });
+            }
+        }
+    }
+    settings
+}
+
+/// a short name like `moorer_negative_chorus`
+pub fn settings_name(settings: &ReverbSettings) -> String {
+    format!(
+        "{}_{}_{}",
+        ReverbType::ids().unwrap()[settings.reverb_type.to_index()],
+        CombType::ids().unwrap()[settings.comb_type.to_index()].to_lowercase(),
+        if settings.enable_chorus { "chorus" } else { "nochorus" },
+    )
+}
+
+/// a unit impulse followed by `len - 1` zeroes
+pub fn impulse(len: usize) -> Vec<f32> {
+    let mut signal = vec![0.0; len];
+    signal[0] = 1.0;
+    signal
+}
+
+/// `burst_len` samples of seeded white noise at half scale followed by silence
+pub fn noise_burst(len: usize, burst_len: usize, seed: u64) -> Vec<f32> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..len)
+        .map(|i| if i < burst_len { rng.gen_range(-0.5..0.5) } else { 0.0 })
+        .collect()
+}
+
+/// runs the same mono signal through both channels of a fresh reverb and returns the wet output
+pub fn render(settings: &ReverbSettings, sample_rate: f32, input: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let mut reverb = Reverb::from_settings(sample_rate, settings);
+    let mut left = Vec::with_capacity(input.len());
+    let mut right = Vec::with_capacity(input.len());
+    for x in input {
+        let (l, r) = reverb.process(*x, *x);
+        left.push(l);
+        right.push(r);
+    }
+    (left, right)
+}
@@ -0,0 +1,106 @@
+//! Checks that the decay parameter produces the reverb time it promises.
+
+mod common;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reverb::analysis;
+use reverb::ir;
+use reverb::reverb::{ReverbSettings, ReverbType};
+
+const SAMPLE_RATE: f32 = 24000.0;
+const SEED: u64 = 0x7eb;
+
+/// measured RT60 may differ this much (relative) from the decay parameter
+const RT60_TOLERANCE: f32 = 0.1;
+
+fn measure_rt60(settings: &ReverbSettings) -> f32 {
+    let ir = ir::render(settings, SAMPLE_RATE);
+    let curve = analysis::energy_decay_curve(&[&ir.left, &ir.right]);
+    analysis::decay_times(&curve, SAMPLE_RATE)
+        .rt60()
+        .expect("the impulse response did not decay far enough to measure it")
+}
+
+#[test]
+fn analysis_measures_synthetic_decay() {
+    // white noise with an exponential envelope that drops 60 dB in exactly 1.5 seconds
+    let rt60 = 1.5;
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let signal: Vec<f32> = (0..(3.0 * SAMPLE_RATE) as usize)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE;
+            let envelope = 10f32.powf(-3.0 * t / rt60);
+            envelope * rng.gen_range(-1.0..1.0)
+        })
+        .collect();
+
+    let curve = analysis::energy_decay_curve(&[&signal]);
+    assert!(curve[0].abs() < 1e-4);
+
+    let times = analysis::decay_times(&curve, SAMPLE_RATE);
+    for (name, time) in [("EDT", times.edt), ("T20", times.t20), ("T30", times.t30)] {
+        let time = time.unwrap();
+        assert!((time - rt60).abs() < 0.03 * rt60, "{} was {} s, expected {} s", name, time, rt60);
+    }
+}
+
+#[test]
+fn analysis_rejects_short_curves() {
+    // a single impulse has no decay to measure
+    let curve = analysis::energy_decay_curve(&[&[1.0, 0.0, 0.0]]);
+    assert_eq!(analysis::decay_times(&curve, SAMPLE_RATE).rt60(), None);
+}
+
+#[test]
+fn decay_parameter_matches_measured_rt60() {
+    let mut failures = Vec::new();
+
+    for decay_ms in [500.0, 1000.0, 2000.0] {
+        for settings in common::all_settings(decay_ms, 0.0, SEED) {
+            if settings.enable_chorus {
+                continue;
+            }
+
+            let rt60 = measure_rt60(&settings);
+            let expected = decay_ms / 1000.0;
+            if (rt60 - expected).abs() > RT60_TOLERANCE * expected {
+                failures.push(format!(
+                    "{}: decay {} ms measured {:.3} s",
+                    common::settings_name(&settings),
+                    decay_ms,
+                    rt60
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "RT60 is off by more than {}%:\n{}", RT60_TOLERANCE * 100.0, failures.join("\n"));
+}
+
+#[test]
+fn damping_shortens_the_decay() {
+    // damping only applies a low-pass filter in the low-pass comb and Moorer topologies, which
+    // removes high frequency energy from the tail
+    for reverb_type in [ReverbType::LpfComb, ReverbType::Moorer] {
+        let undamped = ReverbSettings {
+            reverb_type,
+            decay: 2000.0,
+            damping: 0.0,
+            seed: SEED,
+            ..Default::default()
+        };
+        let damped = ReverbSettings {
+            damping: 0.5,
+            ..undamped
+        };
+
+        let (undamped_rt60, damped_rt60) = (measure_rt60(&undamped), measure_rt60(&damped));
+        assert!(
+            damped_rt60 < undamped_rt60,
+            "{:?}: damped {} s, undamped {} s",
+            reverb_type,
+            damped_rt60,
+            undamped_rt60
+        );
+    }
+}
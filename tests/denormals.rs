@@ -0,0 +1,57 @@
+//! Checks that the tails decay into real silence instead of lingering in the subnormal range.
+
+mod common;
+
+use reverb::denormal::{self, ScopedFtz, DENORMAL_THRESHOLD};
+use reverb::reverb::Reverb;
+
+const SAMPLE_RATE: f32 = 16000.0;
+const SEED: u64 = 0x7eb;
+
+#[test]
+fn flush_only_touches_tiny_values() {
+    assert_eq!(denormal::flush(f32::MIN_POSITIVE / 2.0), 0.0);
+    assert_eq!(denormal::flush(-DENORMAL_THRESHOLD / 2.0), 0.0);
+    assert_eq!(denormal::flush(DENORMAL_THRESHOLD * 2.0), DENORMAL_THRESHOLD * 2.0);
+    assert_eq!(denormal::flush(-0.5), -0.5);
+}
+
+#[test]
+fn tails_reach_exact_silence_without_ftz() {
+    // the tails fall by 60 dB per decay time, with a 500 ms decay they pass the flush threshold
+    // (about -300 dB) well within 10 seconds
+    let burst_len = (0.1 * SAMPLE_RATE) as usize;
+    let len = (10.0 * SAMPLE_RATE) as usize;
+    let input = common::noise_burst(len, burst_len, SEED);
+
+    for settings in common::all_settings(500.0, 0.3, SEED) {
+        let (left, right) = common::render(&settings, SAMPLE_RATE, &input);
+        let last = left[len - 1000..].iter().chain(&right[len - 1000..]);
+        assert!(
+            last.clone().all(|y| *y == 0.0),
+            "{} still produces output after 10 seconds: {:e}",
+            common::settings_name(&settings),
+            last.fold(0.0f32, |max, y| max.max(y.abs())),
+        );
+    }
+}
+
+#[test]
+fn scoped_ftz_flushes_and_restores() {
+    if !ScopedFtz::is_supported() {
+        return;
+    }
+
+    // `black_box` keeps the multiplication from being folded at compile time
+    let subnormal = || std::hint::black_box(f32::MIN_POSITIVE) * std::hint::black_box(0.25);
+    assert_ne!(subnormal(), 0.0);
+    {
+        let _ftz = ScopedFtz::enable();
+        assert_eq!(subnormal(), 0.0);
+
+        // processing with FTZ enabled shouldn't change anything audible
+        let mut reverb = Reverb::new_with_seed(SAMPLE_RATE, 500.0, reverb::reverb::ReverbType::Moorer, 0.3, SEED);
+        assert!(reverb.process(1.0, 1.0).0.is_finite());
+    }
+    assert_ne!(subnormal(), 0.0);
+}
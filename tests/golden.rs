@@ -0,0 +1,135 @@
+//! Golden-file regression tests. Every `ReverbType` x `CombType` x chorus combination is rendered
+//! with a fixed seed and compared against the reference files in `tests/golden`.
+//!
+//! After an intentional change to the DSP, regenerate the references with
+//!
+//! ```shell
+//! REVERB_BLESS=1 cargo test --test golden
+//! ```
+//!
+//! and commit the new files together with the change.
+
+mod common;
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use reverb::ir;
+
+const SAMPLE_RATE: f32 = 16000.0;
+const LENGTH: usize = 8000;
+const NOISE_BURST_LENGTH: usize = 800;
+const SEED: u64 = 0x7eb;
+
+const DECAY_MS: f32 = 500.0;
+const DAMPING: f32 = 0.3;
+
+/// the largest absolute difference to the reference that still counts as equal. this leaves room
+/// for `powf()` and friends rounding differently on other platforms
+const TOLERANCE: f32 = 1e-4;
+
+struct Difference {
+    rms_error: f32,
+    peak_error: f32,
+    first_differing_sample: Option<(usize, f32, f32)>,
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn blessing() -> bool {
+    std::env::var_os("REVERB_BLESS").is_some()
+}
+
+fn read_reference(path: &Path) -> Result<(Vec<f32>, Vec<f32>), hound::Error> {
+    let mut reader = hound::WavReader::open(path)?;
+    let samples: Vec<f32> = reader.samples::<f32>().collect::<Result<_, _>>()?;
+    let left = samples.iter().step_by(2).copied().collect();
+    let right = samples.iter().skip(1).step_by(2).copied().collect();
+    Ok((left, right))
+}
+
+fn compare(actual: &[f32], expected: &[f32]) -> Difference {
+    let mut squared_error = 0.0f64;
+    let mut peak_error = 0.0f32;
+    let mut first_differing_sample = None;
+
+    for (i, (a, e)) in actual.iter().zip(expected.iter()).enumerate() {
+        let error = (a - e).abs();
+        // NaNs always count as a difference
+        if (error > TOLERANCE || error.is_nan()) && first_differing_sample.is_none() {
+            first_differing_sample = Some((i, *a, *e));
+        }
+        squared_error += (error as f64) * (error as f64);
+        peak_error = peak_error.max(error);
+    }
+    if actual.len() != expected.len() && first_differing_sample.is_none() {
+        let i = actual.len().min(expected.len());
+        first_differing_sample = Some((i, f32::NAN, f32::NAN));
+    }
+
+    Difference {
+        rms_error: (squared_error / actual.len().max(1) as f64).sqrt() as f32,
+        peak_error,
+        first_differing_sample,
+    }
+}
+
+/// renders `input` for every combination, and either compares the result against the reference
+/// files or overwrites them when blessing
+fn check_against_golden_files(signal_name: &str, input: &[f32]) {
+    let mut report = String::new();
+
+    for settings in common::all_settings(DECAY_MS, DAMPING, SEED) {
+        let name = format!("{}_{}", common::settings_name(&settings), signal_name);
+        let path = golden_dir().join(format!("{}.wav", name));
+        let (left, right) = common::render(&settings, SAMPLE_RATE, input);
+
+        if blessing() {
+            ir::write_stereo_wav(&path, SAMPLE_RATE, &left, &right).unwrap();
+            continue;
+        }
+
+        let (expected_left, expected_right) = match read_reference(&path) {
+            Ok(reference) => reference,
+            Err(err) => {
+                writeln!(report, "{}: could not read '{}': {}", name, path.display(), err).unwrap();
+                continue;
+            }
+        };
+
+        for (channel, actual, expected) in [("left", &left, &expected_left), ("right", &right, &expected_right)] {
+            let difference = compare(actual, expected);
+            if let Some((i, a, e)) = difference.first_differing_sample {
+                writeln!(
+                    report,
+                    "{} ({}): rms error {:.3e}, peak error {:.3e}, first difference at sample {} \
+                     (got {}, expected {})",
+                    name, channel, difference.rms_error, difference.peak_error, i, a, e
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    assert!(
+        report.is_empty(),
+        "output differs from the golden files (decay {} ms, damping {}, seed {}):\n{}\nrun with \
+         REVERB_BLESS=1 if this change is intentional",
+        DECAY_MS,
+        DAMPING,
+        SEED,
+        report
+    );
+}
+
+#[test]
+fn impulse_responses_match_golden_files() {
+    check_against_golden_files("impulse", &common::impulse(LENGTH));
+}
+
+#[test]
+fn noise_burst_responses_match_golden_files() {
+    check_against_golden_files("noise", &common::noise_burst(LENGTH, NOISE_BURST_LENGTH, SEED));
+}
@@ -0,0 +1,99 @@
+//! Rendering and naming the impulse responses the editor exports.
+
+mod common;
+
+use reverb::comb::CombType;
+use reverb::ir::{self, MAX_IR_SECONDS, TAIL_HOLD_SECONDS, TAIL_THRESHOLD_DB};
+use reverb::reverb::{ReverbSettings, ReverbType};
+
+const SAMPLE_RATE: f32 = 16000.0;
+
+fn settings(decay: f32, seed: u64) -> ReverbSettings {
+    ReverbSettings {
+        reverb_type: ReverbType::Moorer,
+        decay,
+        damping: 0.4,
+        comb_type: CombType::Negative,
+        enable_chorus: true,
+        chorus_depth: 0.0,
+        pre_delay: 0.0,
+        seed,
+    }
+}
+
+#[test]
+fn the_same_seed_renders_the_same_ir() {
+    let first = ir::render(&settings(800.0, 1234), SAMPLE_RATE);
+    let second = ir::render(&settings(800.0, 1234), SAMPLE_RATE);
+    assert_eq!(first.left, second.left);
+    assert_eq!(first.right, second.right);
+
+    // the allpass delays and chorus phases come from the seed
+    let other = ir::render(&settings(800.0, 4321), SAMPLE_RATE);
+    assert_ne!(first.left, other.left);
+
+    // and their depth from the settings
+    let deeper = ir::render(&ReverbSettings { chorus_depth: 5.0, ..settings(800.0, 1234) }, SAMPLE_RATE);
+    assert_ne!(first.left, deeper.left);
+}
+
+#[test]
+fn the_tail_ends_below_the_threshold() {
+    let settings = settings(800.0, 1234);
+    let threshold = 10f32.powf(TAIL_THRESHOLD_DB / 20.0);
+    let ir = ir::render(&settings, SAMPLE_RATE);
+    let last = ir.len() - 1;
+    assert!(ir.left[last].abs() >= threshold || ir.right[last].abs() >= threshold);
+
+    // what got cut off stays below the threshold for at least the hold time
+    let hold = (TAIL_HOLD_SECONDS * SAMPLE_RATE) as usize;
+    let (left, right) = common::render(&settings, SAMPLE_RATE, &common::impulse(ir.len() + hold));
+    for (full, cut) in [(&left, &ir.left), (&right, &ir.right)] {
+        assert!(full.iter().zip(cut.iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+        assert!(full[ir.len()..].iter().all(|x| x.abs() < threshold));
+    }
+}
+
+#[test]
+fn long_tails_stop_at_the_limit() {
+    let ir = ir::render_with_limit(&settings(20000.0, 1234), SAMPLE_RATE, 0.5);
+    assert_eq!(ir.len(), (0.5 * SAMPLE_RATE) as usize);
+
+    // `render()` uses the same limit, which even the longest decay stays well within
+    let ir = ir::render(&settings(20000.0, 1234), 4000.0);
+    assert!(ir.length_seconds() > 10.0 && ir.length_seconds() < MAX_IR_SECONDS, "{}", ir.length_seconds());
+}
+
+#[test]
+fn file_names_describe_the_settings() {
+    let settings = settings(1800.0, 0x4d2);
+    assert_eq!(ir::export_file_name(&settings, 48000.0), "moorer_1800ms_40damp_negative_chorus_48000Hz_00000000000004d2.wav");
+
+    let plain = ReverbSettings {
+        reverb_type: ReverbType::LpfComb,
+        enable_chorus: false,
+        comb_type: CombType::Positive,
+        ..settings
+    };
+    assert_eq!(ir::export_file_name(&plain, 44100.0), "lpfcomb_1800ms_40damp_positive_44100Hz_00000000000004d2.wav");
+    assert_eq!(
+        ir::export_file_name(&ReverbSettings { pre_delay: 25.0, ..plain }, 44100.0),
+        "lpfcomb_25mspre_1800ms_40damp_positive_44100Hz_00000000000004d2.wav"
+    );
+
+    // nothing that changes the sound shares a name
+    let names = [
+        ir::export_file_name(&settings, 48000.0),
+        ir::export_file_name(&settings, 96000.0),
+        ir::export_file_name(&ReverbSettings { seed: 7, ..settings }, 48000.0),
+        ir::export_file_name(&ReverbSettings { decay: 1801.0, ..settings }, 48000.0),
+        ir::export_file_name(&ReverbSettings { damping: 0.5, ..settings }, 48000.0),
+        ir::export_file_name(&ReverbSettings { chorus_depth: 2.5, ..settings }, 48000.0),
+        ir::export_file_name(&ReverbSettings { pre_delay: 20.0, ..settings }, 48000.0),
+    ];
+    for (i, a) in names.iter().enumerate() {
+        for b in &names[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
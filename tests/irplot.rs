@@ -0,0 +1,68 @@
+//! The editor's impulse response plot and the bookkeeping around rendering it.
+
+use reverb::irplot::{IrPlot, IrPlotState, PLOT_COLUMNS, PLOT_FLOOR_DB, PLOT_MIN_SECONDS};
+use reverb::reverb::{ReverbSettings, ReverbType};
+
+fn settings(decay: f32) -> ReverbSettings {
+    ReverbSettings {
+        reverb_type: ReverbType::Moorer,
+        decay,
+        damping: 0.4,
+        seed: 5,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plot_covers_the_impulse_response() {
+    let plot = IrPlot::render(settings(1000.0));
+    assert_eq!(plot.columns.len(), PLOT_COLUMNS);
+
+    // the columns tile the log time axis from `PLOT_MIN_SECONDS` to the end
+    assert!((plot.columns[0].start - PLOT_MIN_SECONDS).abs() < 1e-6);
+    assert!((plot.columns.last().unwrap().end - plot.length_seconds).abs() < 1e-3 * plot.length_seconds);
+    for pair in plot.columns.windows(2) {
+        assert!((pair[0].end - pair[1].start).abs() < 1e-6);
+        assert!(pair[1].start > pair[0].start);
+    }
+    assert!((plot.time_to_x(plot.x_to_time(0.3)) - 0.3).abs() < 1e-5);
+
+    // the decay curve only goes down
+    for pair in plot.columns.windows(2) {
+        assert!(pair[1].edc_db <= pair[0].edc_db);
+    }
+    for column in plot.columns.iter() {
+        assert!((PLOT_FLOOR_DB..=0.0).contains(&column.peak_db));
+        assert!((PLOT_FLOOR_DB..=0.0).contains(&column.edc_db));
+    }
+    assert!(plot.columns.iter().any(|column| column.peak_db == 0.0));
+}
+
+#[test]
+fn rt60_follows_the_decay() {
+    let short = IrPlot::render(settings(500.0)).rt60.unwrap();
+    let long = IrPlot::render(settings(2500.0)).rt60.unwrap();
+    assert!(long > short * 3.0, "RT60 {} s and {} s", short, long);
+}
+
+#[test]
+fn renders_only_when_the_settings_change() {
+    let state = IrPlotState::default();
+    assert!(state.latest().is_none());
+
+    // the first invalidation starts a render, the next ones wait for it
+    assert!(state.invalidate());
+    assert!(!state.invalidate());
+    state.render(|| settings(800.0));
+    assert_eq!(state.generation(), 1);
+    assert_eq!(state.latest().unwrap().settings, settings(800.0));
+
+    assert!(state.invalidate());
+    state.render(|| settings(800.0));
+    assert_eq!(state.generation(), 1);
+
+    assert!(state.invalidate());
+    state.render(|| settings(1200.0));
+    assert_eq!(state.generation(), 2);
+    assert_eq!(state.latest().unwrap().settings, settings(1200.0));
+}
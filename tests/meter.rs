@@ -0,0 +1,62 @@
+//! The level meters behind the editor's peak and RMS displays.
+
+use std::f32::consts::TAU;
+
+use reverb::meter::{Level, LevelMeter, Meters};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+fn sine(amplitude: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| amplitude * (TAU * 1000.0 * i as f32 / SAMPLE_RATE).sin())
+        .collect()
+}
+
+#[test]
+fn sine_levels() {
+    let mut meter = LevelMeter::new(SAMPLE_RATE);
+    for x in sine(0.5, SAMPLE_RATE as usize * 2) {
+        meter.process_sample(x, 0.0);
+    }
+
+    // the louder channel counts, the RMS of a sine is its amplitude over the square root of two
+    assert!((meter.peak() - 0.5).abs() < 1e-2, "peak {}", meter.peak());
+    assert!((meter.rms() - 0.5 / 2.0f32.sqrt()).abs() < 1e-2, "rms {}", meter.rms());
+}
+
+#[test]
+fn levels_fall_back_to_silence() {
+    let mut meter = LevelMeter::new(SAMPLE_RATE);
+    for x in sine(1.0, SAMPLE_RATE as usize / 10) {
+        meter.process_sample(x, x);
+    }
+
+    // the peak falls by 12 dB in 150 ms
+    for _ in 0..(0.15 * SAMPLE_RATE) as usize {
+        meter.process_sample(0.0, 0.0);
+    }
+    assert!((meter.peak() - 0.25).abs() < 1e-2, "peak {}", meter.peak());
+
+    for _ in 0..SAMPLE_RATE as usize * 10 {
+        meter.process_sample(0.0, 0.0);
+    }
+    assert_eq!(meter.peak(), 0.0);
+    assert_eq!(meter.rms(), 0.0);
+}
+
+#[test]
+fn publishing() {
+    let meters = Meters::default();
+    assert_eq!(meters.output.peak(), 0.0);
+
+    let mut meter = LevelMeter::new(SAMPLE_RATE);
+    for x in sine(0.5, SAMPLE_RATE as usize) {
+        meter.process_sample(x, x);
+    }
+    meter.publish(&meters.output);
+
+    let level: &Level = &meters.output;
+    assert_eq!(level.peak(), meter.peak());
+    assert_eq!(level.rms(), meter.rms());
+    assert!((level.peak_db() - 20.0 * 0.5f32.log10()).abs() < 0.1);
+}
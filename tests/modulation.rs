@@ -0,0 +1,150 @@
+//! The modulation sources and how their offsets add up.
+
+use reverb::lfo::LFO;
+use reverb::modulation::{self, EnvelopeFollower, ModMatrix, ModSettings, ModTarget, Offsets, Route};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const BLOCK: usize = 64;
+
+fn settings(routes: [(ModTarget, f32); 3]) -> ModSettings {
+    ModSettings {
+        lfo_rates: [5.0, 3.0],
+        envelope_release: 100.0,
+        routes: routes.map(|(target, depth)| Route { target, depth }),
+    }
+}
+
+/// the offsets after every block of `input`, which is the same on both channels
+fn run(matrix: &mut ModMatrix, input: &[f32], settings: &ModSettings) -> Vec<Offsets> {
+    input.chunks(BLOCK).map(|block| matrix.process_block(block, block, settings)).collect()
+}
+
+/// a tenth of a second at -6 dB, then silence
+fn burst() -> Vec<f32> {
+    let mut input = vec![0.5; SAMPLE_RATE as usize / 10];
+    input.resize(SAMPLE_RATE as usize, 0.0);
+    input
+}
+
+#[test]
+fn advancing_matches_updating_every_sample() {
+    let mut advanced = LFO::new(SAMPLE_RATE, 0.7);
+    let mut updated = advanced.clone();
+    // summing up the phase one sample at a time drifts, so this only compares the first second
+    for _ in 0..SAMPLE_RATE as usize / BLOCK {
+        advanced.advance(BLOCK);
+        for _ in 0..BLOCK {
+            updated.update_lfo();
+        }
+        let (advanced, updated) = (advanced.next_value(), updated.next_value());
+        assert!((advanced - updated).abs() < 1e-3, "{} != {}", advanced, updated);
+    }
+}
+
+#[test]
+fn lfo_offsets_stay_within_the_depth() {
+    let mut matrix = ModMatrix::new(SAMPLE_RATE);
+    let silence = vec![0.0; SAMPLE_RATE as usize];
+    let offsets = run(&mut matrix, &silence, &settings([(ModTarget::Decay, 0.3), (ModTarget::None, 0.0), (ModTarget::None, 0.0)]));
+
+    let decay = offsets.iter().map(|offsets| offsets.decay);
+    assert!(decay.clone().all(|x| x.abs() <= 0.3 + 1e-6));
+    // a 5 Hz LFO goes through its whole range within a second
+    assert!(decay.clone().fold(0.0f32, f32::max) > 0.29);
+    assert!(decay.fold(0.0f32, f32::min) < -0.29);
+    assert!(offsets.iter().all(|offsets| offsets.damping == 0.0 && offsets.wet == 0.0));
+}
+
+#[test]
+fn unrouted_sources_do_nothing() {
+    let mut matrix = ModMatrix::new(SAMPLE_RATE);
+    let loud = vec![0.5; SAMPLE_RATE as usize / 10];
+    let routes = [(ModTarget::None, 1.0), (ModTarget::Wet, 0.0), (ModTarget::Damping, 0.0)];
+    for offsets in run(&mut matrix, &loud, &settings(routes)) {
+        assert_eq!(offsets, Offsets::default());
+    }
+}
+
+#[test]
+fn sources_on_the_same_target_add_up() {
+    let input = burst();
+    let routes = [(ModTarget::Wet, 0.2), (ModTarget::Wet, -0.1), (ModTarget::Wet, 0.4)];
+    let both = run(&mut ModMatrix::new(SAMPLE_RATE), &input, &settings(routes));
+
+    let mut sum = vec![0.0; both.len()];
+    for source in 0..3 {
+        let mut alone = [(ModTarget::None, 0.0); 3];
+        alone[source] = routes[source];
+        for (sum, offsets) in sum.iter_mut().zip(run(&mut ModMatrix::new(SAMPLE_RATE), &input, &settings(alone))) {
+            *sum += offsets.wet;
+        }
+    }
+
+    for (both, sum) in both.iter().zip(sum) {
+        assert!((both.wet - sum).abs() < 1e-6, "{} != {}", both.wet, sum);
+    }
+}
+
+#[test]
+fn the_envelope_follows_the_input() {
+    let mut follower = EnvelopeFollower::new(SAMPLE_RATE, 100.0);
+    assert_eq!(follower.value(), 0.0);
+
+    // -6 dB is 90% of the way from the -60 dB floor
+    for _ in 0..SAMPLE_RATE as usize / 10 {
+        follower.process_sample(0.5, -0.25);
+    }
+    assert!((follower.value() - 0.9).abs() < 0.01, "{}", follower.value());
+
+    // after a second of silence with a 100 ms release it's back at the floor
+    for _ in 0..SAMPLE_RATE as usize {
+        follower.process_sample(0.0, 0.0);
+    }
+    assert_eq!(follower.value(), 0.0);
+}
+
+#[test]
+fn negative_depths_turn_the_parameter_down() {
+    let routes = [(ModTarget::None, 0.0), (ModTarget::None, 0.0), (ModTarget::Damping, -0.5)];
+    let offsets = run(&mut ModMatrix::new(SAMPLE_RATE), &burst(), &settings(routes));
+    assert!(offsets.iter().all(|offsets| offsets.damping <= 0.0));
+    assert!(offsets.iter().any(|offsets| offsets.damping < -0.4));
+}
+
+#[test]
+fn reset_starts_the_lfos_over() {
+    let mut matrix = ModMatrix::new(SAMPLE_RATE);
+    let routes = [(ModTarget::Decay, 1.0), (ModTarget::Damping, 1.0), (ModTarget::None, 0.0)];
+    let silence = vec![0.0; 10 * BLOCK];
+    let first = run(&mut matrix, &silence, &settings(routes));
+    assert_ne!(matrix.offsets(), Offsets::default());
+
+    matrix.reset();
+    assert_eq!(matrix.offsets(), Offsets::default());
+    assert_eq!(run(&mut matrix, &silence, &settings(routes)), first);
+}
+
+#[test]
+fn modulated_values_stay_in_range() {
+    assert_eq!(modulation::modulate(0.5, 0.25), 0.75);
+    assert_eq!(modulation::modulate(0.9, 0.5), 1.0);
+    assert_eq!(modulation::modulate(0.1, -0.5), 0.0);
+}
+
+#[test]
+fn every_target_gets_its_own_offset() {
+    let targets = [ModTarget::Decay, ModTarget::Damping, ModTarget::Wet, ModTarget::ChorusDepth, ModTarget::PreDelay];
+    let silence = vec![0.0; 10 * BLOCK];
+    for target in targets {
+        let routes = [(target, 1.0), (ModTarget::None, 0.0), (ModTarget::None, 0.0)];
+        let offsets = *run(&mut ModMatrix::new(SAMPLE_RATE), &silence, &settings(routes)).last().unwrap();
+        for other in targets {
+            assert_eq!(offsets.get(other) != 0.0, other == target, "{:?} routed, {:?} got {}", target, other, offsets.get(other));
+        }
+
+        // and the editor sees the same offsets
+        let shared = modulation::SharedOffsets::default();
+        shared.store(&offsets);
+        assert_eq!(shared.load(), offsets);
+    }
+}
@@ -0,0 +1,229 @@
+//! Tests for the whole plugin, run through the headless host in `common::host`.
+
+mod common;
+
+use common::host::{Host, ParamChange};
+use nih_plug::prelude::{BufferConfig, Enum, ProcessMode};
+use reverb::mix::MixMode;
+use reverb::reverb::ReverbType;
+
+const SAMPLE_RATE: f32 = 44100.0;
+/// the dry and wet smoothers take 10 ms to reach their targets
+const SETTLED: usize = 1024;
+
+fn buffer_config(max_buffer_size: u32) -> BufferConfig {
+    BufferConfig {
+        sample_rate: SAMPLE_RATE,
+        min_buffer_size: None,
+        max_buffer_size,
+        process_mode: ProcessMode::Realtime,
+    }
+}
+
+/// renders half a second of noise with the parameter changes `setup` returns. the LPF comb reverb
+/// is used because it doesn't depend on the random seed, so every instance produces the same wet
+/// signal
+fn render(setup: impl FnOnce(&Host) -> Vec<ParamChange>) -> Vec<f32> {
+    let mut host = Host::new(&buffer_config(512));
+    let input = common::noise_burst(SAMPLE_RATE as usize / 2, SAMPLE_RATE as usize / 2, 1);
+    let mut automation = vec![
+        host.param("Reverb Type").step(0, ReverbType::LpfComb.to_index() as u32),
+        host.param("Decay").change(0, 0.5),
+    ];
+    automation.extend(setup(&host));
+
+    let (left, _) = host.render(&input, &input, &automation);
+    left
+}
+
+/// renders with the legacy dry and wet levels
+fn render_mix(dry: f64, wet: f64) -> Vec<f32> {
+    render(|host| vec![host.param("Dry").change(0, dry), host.param("Wet").change(0, wet)])
+}
+
+fn mix_mode(host: &Host, mix_mode: MixMode) -> ParamChange {
+    host.param("Mix Mode").step(0, mix_mode.to_index() as u32)
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (actual, expected)) in actual.iter().zip(expected).enumerate().skip(SETTLED) {
+        assert!(
+            (actual - expected).abs() <= 1e-5,
+            "sample {}: expected {}, got {}",
+            i,
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn dry_only_passes_the_input_through() {
+    let input = common::noise_burst(SAMPLE_RATE as usize / 2, SAMPLE_RATE as usize / 2, 1);
+    let output = render_mix(1.0, 0.0);
+
+    assert_eq!(output[SETTLED..], input[SETTLED..]);
+}
+
+#[test]
+fn dry_and_wet_add_up_to_unity() {
+    let dry = render_mix(1.0, 0.0);
+    let wet = render_mix(0.0, 1.0);
+    assert!(wet.iter().any(|x| x.abs() > 1e-3));
+
+    let mixed = render_mix(0.6, 0.4);
+    let expected: Vec<f32> = dry.iter().zip(&wet).map(|(dry, wet)| 0.6 * dry + 0.4 * wet).collect();
+    assert_close(&mixed, &expected);
+}
+
+#[test]
+fn dry_and_wet_are_normalized_above_unity() {
+    let dry = render_mix(1.0, 0.0);
+    let wet = render_mix(0.0, 1.0);
+
+    // the sum is divided by `dry + wet` as soon as it goes over one
+    let mixed = render_mix(1.0, 1.0);
+    let expected: Vec<f32> = dry.iter().zip(&wet).map(|(dry, wet)| (dry + wet) / 2.0).collect();
+    assert_close(&mixed, &expected);
+
+    let mixed = render_mix(0.9, 0.6);
+    let expected: Vec<f32> = dry.iter().zip(&wet).map(|(dry, wet)| (0.9 * dry + 0.6 * wet) / 1.5).collect();
+    assert_close(&mixed, &expected);
+}
+
+#[test]
+fn independent_gains_are_in_db() {
+    let dry = render_mix(1.0, 0.0);
+    let wet = render_mix(0.0, 1.0);
+
+    // not normalized, even though the gains add up to more than one
+    let mixed = render(|host| {
+        vec![
+            mix_mode(host, MixMode::Independent),
+            host.change_text("Dry Gain", 0, "-6"),
+            host.change_text("Wet Gain", 0, "0"),
+        ]
+    });
+    let half = 10.0f32.powf(-6.0 / 20.0);
+    let expected: Vec<f32> = dry.iter().zip(&wet).map(|(dry, wet)| half * dry + wet).collect();
+    assert_close(&mixed, &expected);
+}
+
+#[test]
+fn equal_power_mix_keeps_the_power_constant() {
+    let dry = render_mix(1.0, 0.0);
+    let wet = render_mix(0.0, 1.0);
+
+    let mixed = render(|host| vec![mix_mode(host, MixMode::EqualPower), host.param("Mix").change(0, 0.5)]);
+    let expected: Vec<f32> = dry.iter().zip(&wet).map(|(dry, wet)| (dry + wet) * 0.5f32.sqrt()).collect();
+    assert_close(&mixed, &expected);
+
+    // the ends are fully dry and fully wet
+    let mixed = render(|host| vec![mix_mode(host, MixMode::EqualPower), host.param("Mix").change(0, 0.0)]);
+    assert_close(&mixed, &dry);
+    let mixed = render(|host| vec![mix_mode(host, MixMode::EqualPower), host.param("Mix").change(0, 1.0)]);
+    assert_close(&mixed, &wet);
+}
+
+#[test]
+fn output_gain_applies_after_the_mix() {
+    let mixed = render_mix(1.0, 1.0);
+
+    let louder = render(|host| {
+        vec![
+            host.param("Dry").change(0, 1.0),
+            host.param("Wet").change(0, 1.0),
+            host.change_text("Output Gain", 0, "6"),
+        ]
+    });
+    let gain = 10.0f32.powf(6.0 / 20.0);
+    let expected: Vec<f32> = mixed.iter().map(|x| x * gain).collect();
+    assert_close(&louder, &expected);
+}
+
+#[test]
+fn send_mode_mutes_the_dry_signal() {
+    let wet = render_mix(0.0, 1.0);
+
+    // moving the dry level does nothing, and the wet gain isn't normalized against it
+    let sent = render(|host| {
+        vec![
+            host.param("Send Mode").step(0, 1),
+            host.param("Dry").change(0, 1.0),
+            host.change_text("Wet Gain", 0, "6"),
+            host.param("Dry").change(10000, 0.3),
+        ]
+    });
+    let gain = 10.0f32.powf(6.0 / 20.0);
+    let expected: Vec<f32> = wet.iter().map(|x| x * gain).collect();
+    assert_close(&sent, &expected);
+
+    // whatever the mix mode is
+    let sent = render(|host| {
+        vec![
+            host.param("Send Mode").step(0, 1),
+            mix_mode(host, MixMode::EqualPower),
+            host.param("Mix").change(0, 0.0),
+            host.change_text("Wet Gain", 0, "0"),
+        ]
+    });
+    assert_close(&sent, &wet);
+}
+
+#[test]
+fn send_mode_fades_the_dry_signal_out() {
+    // neither a multiple of the plugin's internal block size nor of the buffer size
+    const CHANGE: usize = 3000;
+
+    // no wet signal in either mode, so the output is the input times the dry gain
+    let input = common::noise_burst(SAMPLE_RATE as usize / 2, SAMPLE_RATE as usize / 2, 1);
+    let output = render(|host| {
+        vec![
+            host.param("Wet").change(0, 0.0),
+            host.param("Wet Gain").change(0, 0.0),
+            host.param("Send Mode").step(CHANGE as u32, 1),
+        ]
+    });
+
+    let gains: Vec<(usize, f32)> = (SETTLED..input.len())
+        .filter(|&i| input[i].abs() > 0.01)
+        .map(|i| (i, output[i] / input[i]))
+        .collect();
+    for &(i, gain) in &gains {
+        if i < CHANGE {
+            assert!((gain - 1.0).abs() <= 1e-5, "sample {}: gain {}", i, gain);
+        } else if i >= CHANGE + SETTLED {
+            assert!(gain.abs() <= 1e-5, "sample {}: gain {}", i, gain);
+        }
+    }
+
+    // the dry gain goes down over 10 ms instead of jumping to zero
+    for pair in gains.windows(2) {
+        let ((i, before), (j, after)) = (pair[0], pair[1]);
+        let max_step = (j - i) as f32 / (0.01 * SAMPLE_RATE) + 1e-5;
+        assert!(after <= before + 1e-5 && before - after <= max_step, "sample {}: gain {} after {}", j, after, before);
+    }
+}
+
+#[test]
+fn automation_is_sample_accurate() {
+    // neither a multiple of the plugin's internal block size nor of the buffer sizes
+    const CHANGE: usize = 3000;
+
+    for max_buffer_size in [64, 333, 512] {
+        let mut host = Host::new(&buffer_config(max_buffer_size));
+        let input = common::noise_burst(4096, 4096, 2);
+        let automation = [
+            host.param("Wet").change(0, 0.0),
+            host.param("Dry").change(CHANGE as u32, 0.0),
+        ];
+
+        let (left, right) = host.render(&input, &input, &automation);
+        assert_eq!(left[SETTLED..CHANGE], input[SETTLED..CHANGE], "buffer size {}", max_buffer_size);
+        assert_eq!(right[SETTLED..CHANGE], input[SETTLED..CHANGE], "buffer size {}", max_buffer_size);
+        // the dry level starts ramping down on the exact sample of the change
+        assert_ne!(left[CHANGE], input[CHANGE], "buffer size {}", max_buffer_size);
+        assert!(left[CHANGE..].iter().zip(&input[CHANGE..]).all(|(output, input)| output.abs() <= input.abs()));
+    }
+}
@@ -0,0 +1,113 @@
+//! The pre-delay in front of the combs, on its own and inside `Reverb`.
+
+mod common;
+
+use reverb::predelay::{PreDelay, MAX_PRE_DELAY_MS};
+use reverb::reverb::{BlockParams, Reverb, ReverbSettings};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const SEED: u64 = 0x9d1;
+
+fn run(pre_delay: &mut PreDelay, input: &[f32]) -> Vec<f32> {
+    input.iter().map(|x| pre_delay.process(*x, -*x).0).collect()
+}
+
+#[test]
+fn no_pre_delay_passes_the_input_through() {
+    let input = common::noise_burst(1000, 1000, SEED);
+    assert_eq!(run(&mut PreDelay::new(SAMPLE_RATE), &input), input);
+}
+
+#[test]
+fn whole_samples_delay_exactly() {
+    let mut pre_delay = PreDelay::new(SAMPLE_RATE);
+    // 10 ms are 480 samples
+    pre_delay.set_delay(10.0);
+    let input = common::noise_burst(2000, 1000, SEED);
+    let output = run(&mut pre_delay, &input);
+
+    assert!(output[..480].iter().all(|x| *x == 0.0));
+    assert_eq!(output[480..], input[..input.len() - 480]);
+}
+
+#[test]
+fn fractional_delays_interpolate() {
+    // a millisecond is two samples
+    let mut pre_delay = PreDelay::new(2000.0);
+    pre_delay.set_delay(0.25);
+    assert_eq!(run(&mut pre_delay, &[1.0, 0.0, 0.0]), [0.5, 0.5, 0.0]);
+}
+
+#[test]
+fn the_delay_is_limited() {
+    let mut pre_delay = PreDelay::new(SAMPLE_RATE);
+    pre_delay.set_delay(10.0 * MAX_PRE_DELAY_MS);
+    assert_eq!(pre_delay.delay_ms(), MAX_PRE_DELAY_MS);
+    pre_delay.set_delay(-5.0);
+    assert_eq!(pre_delay.delay_ms(), 0.0);
+    pre_delay.set_delay(f32::NAN);
+    assert_eq!(pre_delay.delay_ms(), 0.0);
+
+    // the longest delay still fits into the history at every sample rate
+    for sample_rate in [22050.0, 44100.0, 96000.0, 192000.0] {
+        let mut pre_delay = PreDelay::new(sample_rate);
+        pre_delay.set_delay(MAX_PRE_DELAY_MS);
+        let samples = (MAX_PRE_DELAY_MS / 1000.0 * sample_rate) as usize;
+        let mut input = vec![0.0; samples + 10];
+        input[0] = 1.0;
+        let output = run(&mut pre_delay, &input);
+        assert_eq!(output.iter().position(|x| *x != 0.0), Some(samples), "{} Hz", sample_rate);
+    }
+}
+
+#[test]
+fn changing_the_sample_rate_keeps_the_delay() {
+    let mut pre_delay = PreDelay::new(SAMPLE_RATE);
+    pre_delay.set_delay(50.0);
+    pre_delay.resize_buffers(96000.0);
+    assert_eq!(pre_delay.delay_ms(), 50.0);
+}
+
+/// the reverb's output is its output without pre-delay, only later
+#[test]
+fn the_reverb_starts_later() {
+    let input = common::noise_burst(20000, 400, SEED);
+
+    for settings in common::all_settings(500.0, 0.3, SEED) {
+        let (expected_left, expected_right) = common::render(&settings, SAMPLE_RATE, &input);
+        let delayed = ReverbSettings { pre_delay: 10.0, ..settings };
+        let (left, right) = common::render(&delayed, SAMPLE_RATE, &input);
+
+        let name = common::settings_name(&settings);
+        assert!(left[..480].iter().chain(&right[..480]).all(|x| *x == 0.0), "{}", name);
+        assert!(left[480..] == expected_left[..input.len() - 480], "{}", name);
+        assert!(right[480..] == expected_right[..input.len() - 480], "{}", name);
+    }
+}
+
+#[test]
+fn ramps_move_in_a_straight_line() {
+    let mut pre_delay = PreDelay::new(SAMPLE_RATE);
+    pre_delay.ramp_delay(20.0, 100);
+    run(&mut pre_delay, &[0.0; 50]);
+    assert!((pre_delay.delay_ms() - 10.0).abs() < 1e-3, "{}", pre_delay.delay_ms());
+    run(&mut pre_delay, &[0.0; 50]);
+    assert_eq!(pre_delay.delay_ms(), 20.0);
+}
+
+/// `process_block()` ramps the delay over the block and lands on the new one with its last sample,
+/// whatever the block size
+#[test]
+fn blocks_end_on_the_new_delay() {
+    let settings = ReverbSettings::default();
+    let mut reverb = Reverb::from_settings(SAMPLE_RATE, &settings);
+    for (block_size, pre_delay) in [(1, 5.0), (37, 20.0), (64, 0.0), (500, 150.0)] {
+        let params = BlockParams {
+            pre_delay,
+            ..settings.block_params()
+        };
+        let (mut left, mut right) = (vec![0.0; block_size], vec![0.0; block_size]);
+        reverb.process_block(&mut left, &mut right, &params);
+        assert_eq!(reverb.pre_delay().delay_ms(), pre_delay, "{} sample blocks", block_size);
+    }
+}
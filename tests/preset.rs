@@ -0,0 +1,137 @@
+//! Factory presets and the user preset files.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use nih_plug::prelude::Enum;
+use reverb::comb::CombType;
+use reverb::mix::MixMode;
+use reverb::modulation::ModTarget;
+use reverb::preset::{self, Preset, PresetValues};
+use reverb::reverb::ReverbType;
+
+/// an empty directory that's removed again when the test is done
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("reverb-preset-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn factory_presets() {
+    let presets = preset::factory_presets();
+    assert_eq!(presets[0].values, PresetValues::default());
+
+    let names: HashSet<&str> = presets.iter().map(|preset| preset.name.as_str()).collect();
+    assert_eq!(names.len(), presets.len());
+    for preset in presets.iter() {
+        let values = &preset.values;
+        assert!((100.0..=20000.0).contains(&values.decay), "{}", preset.name);
+        for level in [values.damping, values.wet, values.dry, values.mix] {
+            assert!((0.0..=1.0).contains(&level), "{}", preset.name);
+        }
+    }
+}
+
+#[test]
+fn json_round_trip() {
+    for preset in preset::factory_presets() {
+        assert_eq!(Preset::from_json(&preset.to_json()).unwrap(), preset);
+    }
+}
+
+/// ignoring case, like `reverb-render` does
+#[test]
+fn enums_use_the_parameter_ids() {
+    fn check<T: Enum + Copy + serde::Serialize>() {
+        for (idx, id) in T::ids().unwrap().iter().enumerate() {
+            let value = serde_json::to_value(T::from_index(idx)).unwrap();
+            assert!(value.as_str().unwrap().eq_ignore_ascii_case(id), "{} and {}", value, id);
+        }
+    }
+
+    check::<ReverbType>();
+    check::<CombType>();
+    check::<MixMode>();
+    check::<ModTarget>();
+}
+
+#[test]
+fn missing_fields_use_the_defaults() {
+    let preset = Preset::from_json(r#"{ "name": "Old", "reverb_type": "moorer", "decay": 1200.0 }"#).unwrap();
+    assert_eq!(preset.name, "Old");
+    assert_eq!(preset.values, PresetValues {
+        reverb_type: ReverbType::Moorer,
+        decay: 1200.0,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn file_names() {
+    assert_eq!(preset::preset_file_name("Big Hall"), "Big Hall.json");
+    assert_eq!(preset::preset_file_name(" ../Hall: 2/3 "), "___Hall_ 2_3.json");
+    assert_eq!(preset::preset_file_name("   "), "preset.json");
+}
+
+#[test]
+fn save_and_load_user_presets() {
+    let dir = TempDir::new("save");
+    assert!(preset::load_user_presets(&dir.0).is_empty());
+
+    let presets = preset::factory_presets();
+    let hall = Preset::new("my hall", presets[4].values);
+    let room = Preset::new("A room", presets[1].values);
+    let path = preset::save_user_preset(&dir.0, &hall).unwrap();
+    assert_eq!(path, dir.0.join("my hall.json"));
+    preset::save_user_preset(&dir.0, &room).unwrap();
+
+    // files that aren't presets are skipped
+    std::fs::write(dir.0.join("broken.json"), "{ not json").unwrap();
+    std::fs::write(dir.0.join("notes.txt"), "hello").unwrap();
+
+    // sorted by name, ignoring case
+    assert_eq!(preset::load_user_presets(&dir.0), vec![room.clone(), hall.clone()]);
+
+    // saving under the same name overwrites
+    let darker = Preset::new("my hall", PresetValues { damping: 0.9, ..hall.values });
+    preset::save_user_preset(&dir.0, &darker).unwrap();
+    assert_eq!(preset::load_user_presets(&dir.0), vec![room, darker]);
+}
+
+#[test]
+fn names_with_the_same_file_name_get_their_own_file() {
+    let dir = TempDir::new("collision");
+    let values = PresetValues::default();
+    let slash = Preset::new("Hall/1", values);
+    let underscore = Preset::new("Hall_1", PresetValues { decay: 0.9, ..values });
+    let colon = Preset::new("Hall:1", PresetValues { decay: 0.1, ..values });
+
+    assert_eq!(preset::save_user_preset(&dir.0, &slash).unwrap(), dir.0.join("Hall_1.json"));
+    assert_eq!(preset::save_user_preset(&dir.0, &underscore).unwrap(), dir.0.join("Hall_1 2.json"));
+    assert_eq!(preset::save_user_preset(&dir.0, &colon).unwrap(), dir.0.join("Hall_1 3.json"));
+
+    // saving one of them again still overwrites its own file
+    let darker = Preset::new("Hall_1", PresetValues { damping: 0.9, ..underscore.values });
+    assert_eq!(preset::save_user_preset(&dir.0, &darker).unwrap(), dir.0.join("Hall_1 2.json"));
+
+    let mut saved = preset::load_user_presets(&dir.0);
+    saved.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(saved, vec![slash, colon, darker]);
+
+    // files that aren't presets are never overwritten
+    std::fs::write(dir.0.join("Room.json"), "{ not json").unwrap();
+    let room = Preset::new("Room", values);
+    assert_eq!(preset::save_user_preset(&dir.0, &room).unwrap(), dir.0.join("Room 2.json"));
+    assert_eq!(std::fs::read_to_string(dir.0.join("Room.json")).unwrap(), "{ not json");
+}
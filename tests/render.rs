@@ -0,0 +1,160 @@
+//! The `reverb-render` command line tool, run the way a user would run it.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use reverb::ir;
+use reverb::reverb::{ReverbSettings, ReverbType};
+
+const SAMPLE_RATE: u32 = 16000;
+
+/// an empty directory that's removed again when the test is done
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("reverb-render-test-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_reverb-render")).args(args).output().unwrap()
+}
+
+fn path(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+fn read_stereo(path: &Path) -> (u32, Vec<f32>, Vec<f32>) {
+    let mut reader = hound::WavReader::open(path).unwrap();
+    let samples: Vec<f32> = reader.samples::<f32>().collect::<Result<_, _>>().unwrap();
+    let left = samples.iter().step_by(2).copied().collect();
+    let right = samples.iter().skip(1).step_by(2).copied().collect();
+    (reader.spec().sample_rate, left, right)
+}
+
+#[test]
+fn renders_a_file() {
+    let dir = TempDir::new("file");
+    let input = common::noise_burst(SAMPLE_RATE as usize / 4, SAMPLE_RATE as usize / 10, 3);
+    ir::write_stereo_wav(dir.file("in.wav"), SAMPLE_RATE as f32, &input, &input).unwrap();
+
+    let output = run(&[
+        path(&dir.file("in.wav")),
+        path(&dir.file("out.wav")),
+        "--type",
+        "moorer",
+        "--decay",
+        "400",
+        "--damping",
+        "0.3",
+        "--seed",
+        "9",
+        "--wet",
+        "1",
+        "--dry",
+        "0",
+        "--tail",
+        "0.5",
+    ]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // fully wet, so it's the reverb on its own followed by half a second of tail
+    let settings = ReverbSettings {
+        reverb_type: ReverbType::Moorer,
+        decay: 400.0,
+        damping: 0.3,
+        seed: 9,
+        ..Default::default()
+    };
+    let mut padded = input.clone();
+    padded.resize(input.len() + SAMPLE_RATE as usize / 2, 0.0);
+    let (expected_left, expected_right) = common::render(&settings, SAMPLE_RATE as f32, &padded);
+
+    let (sample_rate, left, right) = read_stereo(&dir.file("out.wav"));
+    assert_eq!(sample_rate, SAMPLE_RATE);
+    assert_eq!(left.len(), padded.len());
+    for (actual, expected) in [(&left, &expected_left), (&right, &expected_right)] {
+        assert!(actual.iter().zip(expected.iter()).all(|(a, e)| (a - e).abs() < 1e-6));
+    }
+}
+
+#[test]
+fn renders_an_impulse_response() {
+    let dir = TempDir::new("impulse");
+    let output = run(&["--impulse", path(&dir.file("ir.wav")), "--type", "schroeder", "--decay", "300", "--sample-rate", "16000"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let settings = ReverbSettings {
+        reverb_type: ReverbType::Schroeder,
+        decay: 300.0,
+        ..Default::default()
+    };
+    let expected = ir::render(&settings, SAMPLE_RATE as f32);
+    let (sample_rate, left, right) = read_stereo(&dir.file("ir.wav"));
+    assert_eq!(sample_rate, SAMPLE_RATE);
+    assert_eq!(left, expected.left);
+    assert_eq!(right, expected.right);
+}
+
+#[test]
+fn presets_are_overridden_by_options() {
+    let dir = TempDir::new("preset");
+    std::fs::write(dir.file("hall.json"), r#"{ "reverb_type": "lpfcomb", "decay": 900.0, "seed": 4 }"#).unwrap();
+    let output = run(&["--impulse", path(&dir.file("ir.wav")), "--preset", path(&dir.file("hall.json")), "--decay", "600", "--sample-rate", "16000"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let settings = ReverbSettings {
+        reverb_type: ReverbType::LpfComb,
+        decay: 600.0,
+        seed: 4,
+        ..Default::default()
+    };
+    let (_, left, _) = read_stereo(&dir.file("ir.wav"));
+    assert_eq!(left, ir::render(&settings, SAMPLE_RATE as f32).left);
+}
+
+#[test]
+fn rejects_bad_arguments() {
+    let dir = TempDir::new("bad");
+    std::fs::write(dir.file("loud.json"), r#"{ "wet": 2.0 }"#).unwrap();
+    let out = dir.file("ir.wav");
+    let out = path(&out);
+    let preset = dir.file("loud.json");
+
+    for (args, message) in [
+        (vec!["--impulse", out, "--decay", "50"], "decay has to be between 100 and 20000"),
+        (vec!["--impulse", out, "--decay", "NaN"], "decay has to be between"),
+        (vec!["--impulse", out, "--damping", "1.5"], "damping has to be between 0 and 1"),
+        (vec!["--impulse", out, "--dry", "-0.5"], "dry has to be between 0 and 1"),
+        (vec!["--impulse", out, "--chorus-depth", "25"], "chorus depth has to be between 0 and 20"),
+        (vec!["--impulse", out, "--pre-delay", "250"], "pre-delay has to be between 0 and 200"),
+        (vec!["--impulse", out, "--preset", path(&preset)], "wet has to be between 0 and 1"),
+        (vec!["--impulse", out, "--type", "plate"], "'plate' is not a valid value for --type"),
+        (vec!["--impulse", out, "--sample-rate", "0"], "is not a valid sample rate"),
+        (vec!["--impulse", out, "--decay"], "'--decay' needs a value"),
+        (vec!["--impulse", out, "--freeze"], "unknown option '--freeze'"),
+        (vec![out], "wrong number of files"),
+    ] {
+        let output = run(&args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(stderr.contains(message), "{:?}: {}", args, stderr);
+    }
+    assert!(!dir.file("ir.wav").exists());
+}
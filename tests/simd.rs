@@ -0,0 +1,149 @@
+//! The SIMD comb bank against its scalar fallback and against a plain comb filter.
+
+mod common;
+
+use reverb::comb::CombType;
+use reverb::combbank::{CombBank, LANES, MAX_CHANNEL_COMBS};
+use reverb::denormal;
+use reverb::reverb::Reverb;
+
+const SAMPLE_RATE: f32 = 16000.0;
+const SEED: u64 = 0x7eb;
+
+#[test]
+fn simd_matches_scalar() {
+    let left = common::noise_burst(8000, 800, SEED);
+    let right = common::noise_burst(8000, 800, SEED + 1);
+
+    for settings in common::all_settings(1000.0, 0.3, SEED) {
+        let mut simd = Reverb::from_settings(SAMPLE_RATE, &settings);
+        let mut scalar = Reverb::from_settings(SAMPLE_RATE, &settings);
+        scalar.set_use_simd(false);
+
+        for (i, (l, r)) in left.iter().zip(right.iter()).enumerate() {
+            assert_eq!(
+                simd.process(*l, *r),
+                scalar.process(*l, *r),
+                "{} differs at sample {}",
+                common::settings_name(&settings),
+                i,
+            );
+        }
+    }
+}
+
+/// the difference equation from the `combbank` docs, one comb at a time with its whole history
+struct ReferenceComb {
+    delay: usize,
+    feedback: f32,
+    feedforward: f32,
+    damp: f32,
+    x: Vec<f32>,
+    y: Vec<f32>,
+}
+
+impl ReferenceComb {
+    fn new(delay_ms: f32, feedback: f32, comb_type: CombType, damp: f32) -> Self {
+        Self {
+            delay: (delay_ms / 1000.0 * SAMPLE_RATE).round() as usize,
+            feedback,
+            feedforward: if comb_type == CombType::Positive { 1.0 } else { -1.0 },
+            damp,
+            x: Vec::new(),
+            y: Vec::new(),
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let n = self.x.len();
+        self.x.push(x);
+        let past = |history: &[f32], samples: usize| if samples <= n { history[n - samples] } else { 0.0 };
+
+        let mut y = x + self.feedback * past(&self.y, self.delay + 1);
+        y += self.feedforward * past(&self.x, self.delay);
+        y -= self.damp * past(&self.x, self.delay + 2);
+        y += self.damp * past(&self.y, 2);
+        self.y.push(denormal::flush(y));
+        y
+    }
+}
+
+/// every lane of the bank is the same filter, whatever the other lanes do
+#[test]
+fn lanes_match_a_plain_comb_filter() {
+    let left = common::noise_burst(4000, 400, SEED);
+    let right = common::noise_burst(4000, 400, SEED + 1);
+
+    // three combs per channel so each channel has a padding lane
+    let left_delays_ms = [3.1, 4.7, 6.9];
+    let right_delays_ms = [2.3, 5.2, 7.3];
+    let delays_ms: Vec<f32> = left_delays_ms.iter().chain(right_delays_ms.iter()).copied().collect();
+
+    for use_simd in [true, false] {
+        for damp in [0.0, 0.4] {
+            let mut bank = CombBank::new(SAMPLE_RATE, &left_delays_ms, &right_delays_ms);
+            bank.set_use_simd(use_simd);
+            let mut references = Vec::new();
+            for (comb, delay_ms) in delays_ms.iter().enumerate() {
+                // low enough that `stability::limit_comb()` leaves it alone even with the damping
+                let feedback = 0.3 + 0.05 * comb as f32;
+                let comb_type = common::COMB_TYPES[comb % 2];
+                bank.set_params(comb, feedback, damp > 0.0, damp, comb_type);
+                references.push(ReferenceComb::new(*delay_ms, feedback, comb_type, damp));
+            }
+
+            for (i, (l, r)) in left.iter().zip(right.iter()).enumerate() {
+                let mut inputs = [0.0; LANES];
+                inputs[..3].fill(*l);
+                inputs[MAX_CHANNEL_COMBS..][..3].fill(*r);
+                let outputs = bank.process(&inputs);
+
+                let lanes = (0..3).chain(MAX_CHANNEL_COMBS..MAX_CHANNEL_COMBS + 3);
+                let channel_inputs = [*l, *l, *l, *r, *r, *r];
+                for ((lane, reference), x) in lanes.zip(references.iter_mut()).zip(channel_inputs) {
+                    assert_eq!(
+                        outputs[lane],
+                        reference.process(x),
+                        "lane {} with damp {} (simd {}) differs at sample {}",
+                        lane,
+                        damp,
+                        use_simd,
+                        i,
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn process_block_matches_process() {
+    let left = common::noise_burst(2000, 200, SEED);
+    let right = common::noise_burst(2000, 200, SEED + 1);
+    let frames: Vec<[f32; LANES]> = left
+        .iter()
+        .zip(right.iter())
+        .map(|(l, r)| {
+            let mut frame = [*l; LANES];
+            frame[MAX_CHANNEL_COMBS..].fill(*r);
+            frame
+        })
+        .collect();
+
+    for use_simd in [true, false] {
+        let mut by_sample = CombBank::new(SAMPLE_RATE, &[3.1, 4.7, 6.9, 8.3], &[2.3, 5.2, 7.3, 9.1]);
+        by_sample.set_use_simd(use_simd);
+        for comb in 0..by_sample.len() {
+            by_sample.set_params(comb, 0.7, true, 0.2, common::COMB_TYPES[comb % 2]);
+        }
+        let mut by_block = by_sample.clone();
+
+        let expected: Vec<[f32; LANES]> = frames.iter().map(|frame| by_sample.process(frame)).collect();
+        let mut block = frames.clone();
+        // uneven blocks, so the blocks don't line up with anything inside the bank
+        for chunk in block.chunks_mut(37) {
+            by_block.process_block(chunk);
+        }
+        assert_eq!(block, expected, "simd {}", use_simd);
+    }
+}
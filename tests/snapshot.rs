@@ -0,0 +1,86 @@
+//! The A/B comparison slots.
+
+use reverb::preset::{self, PresetValues};
+use reverb::snapshot::Snapshots;
+
+fn values(decay: f32) -> PresetValues {
+    PresetValues {
+        decay,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn switching_keeps_each_slots_values() {
+    let mut snapshots = Snapshots::default();
+    snapshots.init(values(500.0));
+    assert_eq!(snapshots.active, 0);
+
+    // switching to the current slot does nothing, an unused slot starts out as a copy
+    assert_eq!(snapshots.switch(0, values(600.0)), None);
+    assert_eq!(snapshots.switch(1, values(600.0)), Some(values(600.0)));
+    assert_eq!(snapshots.active, 1);
+
+    // B gets edited, A still has what it had when it was left
+    assert_eq!(snapshots.switch(0, values(2000.0)), Some(values(600.0)));
+    assert_eq!(snapshots.switch(1, values(600.0)), Some(values(2000.0)));
+}
+
+#[test]
+fn copying() {
+    let mut snapshots = Snapshots::default();
+    snapshots.init(values(500.0));
+    snapshots.switch(1, values(500.0));
+    snapshots.switch(0, values(3000.0));
+
+    // copying from the active slot takes the current values, the parameters stay as they are
+    assert_eq!(snapshots.copy(0, 1, values(800.0)), None);
+    assert_eq!(snapshots.switch(1, values(800.0)), Some(values(800.0)));
+
+    // copying into the active slot has to change the parameters
+    snapshots.switch(0, values(1200.0));
+    assert_eq!(snapshots.copy(1, 0, values(1500.0)), Some(values(1200.0)));
+}
+
+#[test]
+fn modified_since_switching() {
+    let mut snapshots = Snapshots::default();
+    assert!(!snapshots.is_modified(&values(500.0)));
+    snapshots.init(values(500.0));
+    assert!(!snapshots.is_modified(&values(500.0)));
+    assert!(snapshots.is_modified(&values(510.0)));
+
+    // the parameters never come back exactly as they were set
+    assert!(!snapshots.is_modified(&values(500.0 + 1e-3)));
+
+    // every slot compares against what it was switched to with
+    snapshots.switch(1, values(510.0));
+    assert!(!snapshots.is_modified(&values(510.0)));
+    assert!(snapshots.is_modified(&values(700.0)));
+    let a = snapshots.switch(0, values(700.0)).unwrap();
+    assert!(!snapshots.is_modified(&a));
+}
+
+#[test]
+fn persisted_state() {
+    let mut snapshots = Snapshots::default();
+    snapshots.init(preset::factory_presets()[2].values);
+    snapshots.switch(1, preset::factory_presets()[3].values);
+
+    let json = serde_json::to_string(&snapshots).unwrap();
+    assert_eq!(serde_json::from_str::<Snapshots>(&json).unwrap(), snapshots);
+    // missing fields are fine, an empty object is a fresh set of slots
+    assert_eq!(serde_json::from_str::<Snapshots>("{}").unwrap(), Snapshots::default());
+}
+
+#[test]
+fn corrupt_active_slots_fall_back_to_the_first_one() {
+    let mut snapshots: Snapshots = serde_json::from_str(r#"{ "active": 7 }"#).unwrap();
+    assert_eq!(snapshots.active, 0);
+
+    // and everything that indexes the slots with it keeps working
+    snapshots.init(values(500.0));
+    assert!(!snapshots.is_modified(&values(500.0)));
+    assert_eq!(snapshots.switch(1, values(600.0)), Some(values(600.0)));
+    assert_eq!(snapshots.switch(0, values(700.0)), Some(values(600.0)));
+}
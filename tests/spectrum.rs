@@ -0,0 +1,93 @@
+//! The ring buffers and the analyser behind the editor's spectrum display.
+
+use std::f32::consts::TAU;
+
+use reverb::spectrum::{Analyser, Averaging, SampleRing, RING_CAPACITY, SPECTRUM_FLOOR_DB};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const FFT_SIZE: usize = 4096;
+
+fn sine(amplitude: f32, frequency: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| amplitude * (TAU * frequency * i as f32 / SAMPLE_RATE).sin())
+        .collect()
+}
+
+fn loudest_bin(analyser: &Analyser) -> usize {
+    (0..analyser.bins())
+        .max_by(|a, b| analyser.bin_db(*a).total_cmp(&analyser.bin_db(*b)))
+        .unwrap()
+}
+
+#[test]
+fn ring_keeps_the_newest_samples() {
+    let ring = SampleRing::default();
+    let mut out = [0.0; 4];
+    assert!(!ring.read_latest(&mut out));
+
+    // wraps around a few times
+    for i in 0..RING_CAPACITY * 3 + 10 {
+        ring.push(i as f32);
+    }
+    assert_eq!(ring.written(), RING_CAPACITY * 3 + 10);
+    assert!(ring.read_latest(&mut out));
+    let newest = (RING_CAPACITY * 3 + 9) as f32;
+    assert_eq!(out, [newest - 3.0, newest - 2.0, newest - 1.0, newest]);
+}
+
+#[test]
+fn sine_peaks_at_its_frequency() {
+    // right between two bins and right on one, the hann window keeps the error within 1.5 dB
+    let resolution = SAMPLE_RATE / FFT_SIZE as f32;
+    for frequency in [resolution * 85.5, resolution * 88.0] {
+        let ring = SampleRing::default();
+        for x in sine(0.5, frequency, FFT_SIZE) {
+            ring.push(x);
+        }
+
+        let mut analyser = Analyser::new(FFT_SIZE, Averaging::Off);
+        assert!(analyser.update(&ring));
+        let bin = loudest_bin(&analyser);
+        assert!((analyser.bin_frequency(bin, SAMPLE_RATE) - frequency).abs() <= resolution);
+        let expected = 20.0 * 0.5f32.log10();
+        assert!((analyser.bin_db(bin) - expected).abs() < 1.5, "{} dB", analyser.bin_db(bin));
+
+        // and far away from it there's next to nothing
+        assert!(analyser.bin_db(analyser.bins() - 1) < -80.0);
+    }
+}
+
+#[test]
+fn silence_sits_at_the_floor() {
+    let mut analyser = Analyser::new(1024, Averaging::Off);
+    analyser.update_from(&[0.0; 1024]);
+    for bin in 0..analyser.bins() {
+        assert_eq!(analyser.bin_db(bin), SPECTRUM_FLOOR_DB);
+    }
+}
+
+#[test]
+fn averaging_smooths_changes() {
+    let tone = sine(1.0, 1000.0, FFT_SIZE);
+    let silence = vec![0.0; FFT_SIZE];
+
+    let level_after_silence = |averaging| {
+        let mut analyser = Analyser::new(FFT_SIZE, averaging);
+        for _ in 0..50 {
+            analyser.update_from(&tone);
+        }
+        let bin = loudest_bin(&analyser);
+        let before = analyser.bin_db(bin);
+        analyser.update_from(&silence);
+        (before, analyser.bin_db(bin))
+    };
+
+    // without averaging the tone is gone right away, longer averaging lets it fade out slower
+    let (before, off) = level_after_silence(Averaging::Off);
+    assert!(before > -2.0);
+    assert_eq!(off, SPECTRUM_FLOOR_DB);
+    let (_, short) = level_after_silence(Averaging::Short);
+    let (_, long) = level_after_silence(Averaging::Long);
+    assert!(short > -10.0 && short < before, "{} dB", short);
+    assert!(long > short, "{} dB and {} dB", short, long);
+}
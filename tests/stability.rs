@@ -0,0 +1,132 @@
+//! Randomized parameter sweeps that check the reverb can't blow up.
+
+mod common;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use reverb::chorus::Chorus;
+use reverb::comb::CombType;
+use reverb::combbank::CombBank;
+use reverb::delayingallpass::DelayingAllPass;
+use reverb::reverb::{Reverb, ReverbSettings};
+use reverb::stability::{self, MAX_LOOP_GAIN};
+
+const SAMPLE_RATE: f32 = 24000.0;
+const SEED: u64 = 0x7eb;
+
+/// full scale noise into any of the topologies must stay below this level. the combs can resonate
+/// quite a bit, but anything past this means something is growing that shouldn't be
+const MAX_OUTPUT: f32 = 1000.0;
+
+/// parameter values that have caused trouble before or sit right on the edge of the ranges, mixed
+/// with random values in and outside of the plugin's ranges
+fn random_decay(rng: &mut StdRng) -> f32 {
+    match rng.gen_range(0..6) {
+        0 => 100.0,
+        1 => 20000.0,
+        2 => rng.gen_range(-1000.0..0.0),
+        3 => 0.0,
+        4 => f32::INFINITY,
+        _ => rng.gen_range(100.0..20000.0),
+    }
+}
+
+fn random_damping(rng: &mut StdRng) -> f32 {
+    match rng.gen_range(0..5) {
+        0 => 0.0,
+        1 => 1.0,
+        2 => rng.gen_range(-2.0..3.0),
+        3 => f32::NAN,
+        _ => rng.gen_range(0.0..1.0),
+    }
+}
+
+#[test]
+fn loop_gain_limits() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for _ in 0..10000 {
+        let feedback = rng.gen_range(-4.0..4.0);
+        let damp = rng.gen_range(-4.0..4.0);
+        let use_lpf = rng.gen_bool(0.5);
+
+        let (limited_feedback, limited_damp) = stability::limit_comb(feedback, use_lpf, damp);
+        assert!(stability::comb_loop_gain(limited_feedback, use_lpf, limited_damp) <= MAX_LOOP_GAIN + 1e-6);
+
+        let gain = stability::limit_allpass(feedback);
+        assert!(stability::allpass_loop_gain(gain) <= MAX_LOOP_GAIN);
+
+        let wet = rng.gen_range(0.0..4.0);
+        let chorus_feedback = stability::limit_chorus_feedback(wet, feedback);
+        assert!(stability::chorus_loop_gain(wet, chorus_feedback) <= MAX_LOOP_GAIN + 1e-6);
+    }
+
+    // values that are already stable are left alone
+    assert_eq!(stability::limit_comb(0.5, true, 0.3), (0.5, 0.3));
+    assert_eq!(stability::limit_allpass(0.707), 0.707);
+    assert_eq!(stability::limit_chorus_feedback(1.0, 0.5), 0.5);
+}
+
+#[test]
+fn building_blocks_stay_stable_with_extreme_settings() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    for comb_type in common::COMB_TYPES {
+        let mut combs = CombBank::new(SAMPLE_RATE, &[10.0], &[10.0]);
+        combs.set_params(0, rng.gen_range(1.0..10.0), true, 0.9999, comb_type);
+        combs.set_params(1, 10.0, false, 0.0, comb_type);
+        assert!(combs.loop_gain() < 1.0);
+    }
+
+    let allpass = DelayingAllPass::new(SAMPLE_RATE, 3.0, -5.0);
+    assert!(allpass.loop_gain() < 1.0);
+
+    let mut chorus = Chorus::new(SAMPLE_RATE, 25.0, 4.0, 10.0, 0.25, 1.0, 0.2, &mut rng);
+    assert!(chorus.loop_gain() < 1.0);
+    chorus.set_params(SAMPLE_RATE, 25.0, 0.9, 10.0, 0.25, 2.0, 0.0);
+    assert!(chorus.loop_gain() < 1.0);
+}
+
+#[test]
+fn random_parameter_sweeps_stay_bounded() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    for reverb_type in common::REVERB_TYPES {
+        let mut reverb = Reverb::from_settings(
+            SAMPLE_RATE,
+            &ReverbSettings {
+                reverb_type,
+                seed: SEED,
+                ..Default::default()
+            },
+        );
+
+        for round in 0..24 {
+            let comb_type = if rng.gen_bool(0.5) { CombType::Positive } else { CombType::Negative };
+            let (decay, damping, enable_chorus) = (random_decay(&mut rng), random_damping(&mut rng), rng.gen_bool(0.5));
+            reverb.set_params(decay, damping, comb_type, enable_chorus);
+
+            let loop_gain = reverb.loop_gain();
+            assert!(
+                loop_gain < 1.0,
+                "{:?} has a loop gain of {} with decay {}, damping {}, {:?}",
+                reverb_type, loop_gain, decay, damping, comb_type
+            );
+
+            // full scale noise, with the occasional NaN or infinity from a misbehaving host
+            for i in 0..2400 {
+                let x = match i {
+                    100 if round % 4 == 0 => f32::NAN,
+                    200 if round % 4 == 1 => f32::INFINITY,
+                    _ => rng.gen_range(-1.0..1.0),
+                };
+
+                for y in <[f32; 2]>::from(reverb.process(x, x)) {
+                    assert!(
+                        y.is_finite() && y.abs() < MAX_OUTPUT,
+                        "{:?} produced {} with decay {}, damping {}, {:?}, chorus {}",
+                        reverb_type, y, decay, damping, comb_type, enable_chorus
+                    );
+                }
+            }
+        }
+    }
+}